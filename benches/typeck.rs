@@ -0,0 +1,118 @@
+//! Checks generated programs of increasing size. The time per item should
+//! stay roughly constant as the programs grow.
+//!
+//! Run with `cargo bench`.
+
+use std::thread;
+
+use criterion::{criterion_group, BatchSize, BenchmarkId, Criterion, Throughput};
+use illiol::hir::{Decls, Expr, Literal, Pat, Type, ValueDef};
+use indexmap::IndexMap;
+
+const SIZES: [usize; 4] = [1000, 2000, 4000, 8000];
+
+criterion_group!(benches, bench_all);
+
+fn main() {
+    // The checker recurses over expressions, so deeply nested programs need a
+    // larger stack than the main thread has.
+    thread::Builder::new()
+        .stack_size(1 << 30)
+        .spawn(|| {
+            benches();
+            Criterion::default().configure_from_args().final_summary();
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+fn bench_all(c: &mut Criterion) {
+    bench(c, "declaration chain", declaration_chain);
+    bench(c, "identity applications", identity_applications);
+    bench(c, "let chain", let_chain);
+}
+
+fn bench(c: &mut Criterion, name: &str, generate: fn(usize) -> Decls) {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+    for size in SIZES {
+        let (_, diagnostics) = illiol::typeck(generate(size));
+        assert!(diagnostics.is_empty());
+
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter_batched(|| generate(size), illiol::typeck, BatchSize::LargeInput)
+        });
+    }
+
+    group.finish();
+}
+
+/// `v0 = true`, `v1 = v0`, ..., each with an inferred type.
+fn declaration_chain(size: usize) -> Decls {
+    let mut values = IndexMap::new();
+    values.insert("v0".into(), value(Type::Wildcard, boolean()));
+    for i in 1..size {
+        let body = name(&format!("v{}", i - 1));
+        values.insert(format!("v{i}"), value(Type::Wildcard, body));
+    }
+
+    decls(values)
+}
+
+/// `main = id (id (... (id true)))` with an unannotated `id`.
+fn identity_applications(size: usize) -> Decls {
+    let id = Expr::Fun(Pat::Bind("x".into()), Box::new(name("x")));
+    let body = (0..size).fold(boolean(), |arg, _| {
+        Expr::Call(Box::new(name("id")), Box::new(arg))
+    });
+
+    let mut values = IndexMap::new();
+    values.insert("id".into(), value(Type::Wildcard, id));
+    values.insert("main".into(), value(Type::Bool, body));
+    decls(values)
+}
+
+/// `main = let x1 = x0 in let x2 = x1 in ... in x_n else ...`, where every
+/// let is given its own else branch.
+fn let_chain(size: usize) -> Decls {
+    let body = (1..=size)
+        .rev()
+        .fold(name(&format!("x{size}")), |then, i| Expr::Let {
+            pat: Pat::Bind(format!("x{i}")),
+            bound: Box::new(name(&format!("x{}", i - 1))),
+            then: Box::new(then),
+            elze: Box::new(boolean()),
+        });
+
+    let main = Expr::Fun(Pat::Bind("x0".into()), Box::new(body));
+    let anno = Type::Arrow(Box::new(Type::Bool), Box::new(Type::Wildcard));
+
+    let mut values = IndexMap::new();
+    values.insert("main".into(), value(anno, main));
+    decls(values)
+}
+
+fn decls(values: IndexMap<String, ValueDef>) -> Decls {
+    Decls {
+        values,
+        ..Decls::default()
+    }
+}
+
+fn value(anno: Type, body: Expr) -> ValueDef {
+    ValueDef {
+        vars: Vec::new(),
+        anno,
+        body,
+    }
+}
+
+fn name(name: &str) -> Expr {
+    Expr::Name(name.into())
+}
+
+fn boolean() -> Expr {
+    Expr::Lit(Literal::Boolean(true))
+}
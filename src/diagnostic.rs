@@ -0,0 +1,51 @@
+use std::fmt;
+
+use crate::mir::Name;
+
+/// A problem found while checking a program.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub message: String,
+    /// The top-level declaration the problem was found in, if any.
+    pub within: Option<Name>,
+    /// Additional lines of explanation, printed below the message.
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            within: None,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn within(mut self, name: Option<Name>) -> Self {
+        self.within = name;
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error: {}", self.message)?;
+
+        if let Some(name) = &self.within {
+            write!(f, "\n  in `{name}`")?;
+        }
+
+        for note in self.notes.iter() {
+            for line in note.lines() {
+                write!(f, "\n  {line}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
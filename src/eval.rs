@@ -0,0 +1,510 @@
+//! A tree-walking interpreter for typed programs. Types are erased, so a
+//! polymorphic name evaluates to the same value at every instantiation, and
+//! class dictionaries are ordinary constructor values. The builtins of the
+//! prelude are implemented here, since they are declared without bodies.
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::mir::{BinOp, Expr, ExprNode, Literal, Name, Pat, Program};
+use crate::types::{Type, TypeId};
+use crate::{Diagnostic, Regex};
+
+#[derive(Clone, Debug)]
+pub enum Value {
+    Boolean(bool),
+    Integer(i64),
+    String(String),
+    Regex(Regex),
+    Tuple(Vec<Value>),
+    /// A function closing over the environment it was created in.
+    Closure(Rc<Closure>),
+    /// A constructor applied to some of its fields. It is a function until it
+    /// has been applied to all of them.
+    Data(Name, Vec<Value>),
+    /// A builtin applied to some of its arguments, which is a function until
+    /// it has been applied to all of them.
+    Builtin(Builtin, Vec<Value>),
+}
+
+/// A builtin of the prelude, at the type it is used at where it depends on it.
+#[derive(Clone, Debug)]
+pub enum Builtin {
+    /// A comparison of two integers, which holds if they are ordered in one
+    /// of the given ways.
+    Compare(&'static [Ordering]),
+    Length,
+    Repeat,
+    Matches,
+    Widen,
+    /// Clamp an integer to the range `lo..hi`.
+    Clamp(i64, i64),
+    /// Parse an integer, if it is in the range `lo..hi`, into the given
+    /// constructors of `Option` or of its specialisation.
+    ParseInt {
+        lo: i64,
+        hi: i64,
+        none: Name,
+        some: Name,
+    },
+    ShowInt,
+}
+
+#[derive(Debug)]
+pub struct Closure {
+    pat: Pat,
+    body: Expr,
+    env: Env,
+}
+
+/// The local variables in scope, innermost first.
+#[derive(Clone, Debug, Default)]
+struct Env(Option<Rc<(Name, Value, Env)>>);
+
+impl Env {
+    fn bind(&self, name: Name, value: Value) -> Self {
+        Self(Some(Rc::new((name, value, self.clone()))))
+    }
+
+    fn get(&self, name: &str) -> Option<&Value> {
+        let mut env = self;
+        while let Some(scope) = &env.0 {
+            let (bound, value, outer) = scope.as_ref();
+            if bound == name {
+                return Some(value);
+            }
+
+            env = outer;
+        }
+
+        None
+    }
+}
+
+/// The state of a top-level value, which is evaluated when first used.
+#[derive(Clone)]
+enum Global {
+    InProgress,
+    Done(Value),
+}
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+/// The most characters a string built by `repeat` may have.
+const MAX_REPEAT: usize = 1 << 24;
+
+/// Evaluates the values of a program.
+pub struct Interpreter<'a> {
+    program: &'a Program,
+    globals: RefCell<HashMap<Name, Global>>,
+    /// The number of fields of every constructor.
+    arities: HashMap<&'a str, usize>,
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(program: &'a Program) -> Self {
+        let arities = program
+            .decls
+            .types
+            .values()
+            .flat_map(|def| def.ctors.iter())
+            .filter_map(|ctor| {
+                let template = program.context.get(ctor)?;
+                Some((
+                    ctor.as_str(),
+                    arity(program, program.types.get(&template.uninst)),
+                ))
+            })
+            .collect();
+
+        Self {
+            program,
+            globals: RefCell::new(HashMap::new()),
+            arities,
+        }
+    }
+
+    /// Evaluate the top-level value of the given name.
+    pub fn global(&self, name: &str) -> Result<Value> {
+        if let Some(global) = self.globals.borrow().get(name) {
+            return match global {
+                Global::Done(value) => Ok(value.clone()),
+                Global::InProgress => Err(Diagnostic::new(format!(
+                    "the value of `{name}` depends on itself"
+                ))
+                .within(Some(name.to_string()))),
+            };
+        }
+
+        let value = if self.arities.contains_key(name) {
+            Value::Data(name.to_string(), Vec::new())
+        } else if let Some(expr) = self.program.decls.values.get(name) {
+            self.globals
+                .borrow_mut()
+                .insert(name.to_string(), Global::InProgress);
+            let value = self
+                .eval(expr, &Env::default())
+                .map_err(|err| match err.within {
+                    None => err.within(Some(name.to_string())),
+                    Some(_) => err,
+                });
+
+            match value {
+                Ok(value) => value,
+                Err(err) => {
+                    self.globals.borrow_mut().remove(name);
+                    return Err(err);
+                }
+            }
+        } else {
+            return Err(Diagnostic::new(format!("unknown name `{name}`")));
+        };
+
+        self.globals
+            .borrow_mut()
+            .insert(name.to_string(), Global::Done(value.clone()));
+        Ok(value)
+    }
+
+    /// Evaluate an expression in which the top-level values of the program
+    /// are in scope.
+    pub fn eval_expr(&self, expr: &Expr) -> Result<Value> {
+        self.eval(expr, &Env::default())
+    }
+
+    fn eval(&self, expr: &Expr, env: &Env) -> Result<Value> {
+        match &expr.node {
+            ExprNode::Fun(pat, body) => Ok(Value::Closure(Rc::new(Closure {
+                pat: pat.clone(),
+                body: (**body).clone(),
+                env: env.clone(),
+            }))),
+
+            ExprNode::Let {
+                pat,
+                bound,
+                then,
+                elze,
+            } => {
+                let bound = self.eval(bound, env)?;
+                match self.matches(pat, &bound, env.clone())? {
+                    Some(env) => self.eval(then, &env),
+                    None => self.eval(elze, env),
+                }
+            }
+
+            ExprNode::Tuple(exprs) => exprs
+                .iter()
+                .map(|expr| self.eval(expr, env))
+                .collect::<Result<_>>()
+                .map(Value::Tuple),
+
+            ExprNode::Call(func, arg) => {
+                let func = self.eval(func, env)?;
+                let arg = self.eval(arg, env)?;
+                self.apply(func, arg)
+            }
+
+            ExprNode::Binary(op, lhs, rhs) => {
+                let lhs = self.eval(lhs, env)?;
+                let rhs = self.eval(rhs, env)?;
+                binary(*op, lhs, rhs)
+            }
+
+            ExprNode::Lit(lit) => Ok(literal(lit)),
+
+            ExprNode::Name(name) | ExprNode::Instantiated(name, _) => match env.get(name) {
+                Some(value) => Ok(value.clone()),
+                None => match self.builtin(name, expr.anno) {
+                    Some(builtin) => Ok(Value::Builtin(builtin, Vec::new())),
+                    None => self.global(name),
+                },
+            },
+
+            ExprNode::Impossible => Err(Diagnostic::new("reached `impossible`")),
+            ExprNode::Invalid => Err(Diagnostic::new("reached an invalid expression")),
+        }
+    }
+
+    /// Apply a function value to an argument.
+    pub fn apply(&self, func: Value, arg: Value) -> Result<Value> {
+        match func {
+            Value::Closure(closure) => {
+                match self.matches(&closure.pat, &arg, closure.env.clone())? {
+                    Some(env) => self.eval(&closure.body, &env),
+                    None => Err(Diagnostic::new(format!(
+                        "the argument `{arg}` does not match the function's pattern"
+                    ))),
+                }
+            }
+
+            Value::Data(name, mut fields) if fields.len() < self.arities[name.as_str()] => {
+                fields.push(arg);
+                Ok(Value::Data(name, fields))
+            }
+
+            Value::Builtin(builtin, mut args) => {
+                args.push(arg);
+                match args.len() < builtin.arity() {
+                    true => Ok(Value::Builtin(builtin, args)),
+                    false => builtin.call(args),
+                }
+            }
+
+            func => Err(Diagnostic::new(format!("`{func}` is not a function"))),
+        }
+    }
+
+    /// The builtin a name refers to where it is used at the given type, if it
+    /// is one. Builtins are in the context but have no body.
+    fn builtin(&self, name: &str, ty: TypeId) -> Option<Builtin> {
+        let program = self.program;
+        if self.arities.contains_key(name)
+            || program.decls.values.contains_key(name)
+            || !program.context.contains_key(name)
+        {
+            return None;
+        }
+
+        // A specialisation made by monomorphisation is named after its builtin.
+        let name = name.split_once('#').map_or(name, |(name, _)| name);
+
+        let Type::Arrow(_, into) = program.types.get(&ty) else {
+            return None;
+        };
+
+        let into = program.types.get(into);
+        Some(match name {
+            "lt" => Builtin::Compare(&[Ordering::Less]),
+            "le" => Builtin::Compare(&[Ordering::Less, Ordering::Equal]),
+            "gt" => Builtin::Compare(&[Ordering::Greater]),
+            "ge" => Builtin::Compare(&[Ordering::Greater, Ordering::Equal]),
+            "eq" => Builtin::Compare(&[Ordering::Equal]),
+            "ne" => Builtin::Compare(&[Ordering::Less, Ordering::Greater]),
+            "length" => Builtin::Length,
+            "repeat" => Builtin::Repeat,
+            "matches" => Builtin::Matches,
+            "widen" => Builtin::Widen,
+
+            "clamp" => match into {
+                Type::Range(lo, hi) if lo < hi => Builtin::Clamp(*lo, *hi),
+                _ => return None,
+            },
+
+            // The result is an option of the range, or a specialisation of
+            // one, whose range is that of the field of `Some`.
+            "parse_int" => {
+                let Type::Named(data, args) = into else {
+                    return None;
+                };
+
+                let [none, some] = program.decls.types.get(data)?.ctors.as_slice() else {
+                    return None;
+                };
+
+                let range = match args.as_slice() {
+                    [range] => *range,
+                    _ => match program.types.get(&program.context.get(some)?.uninst) {
+                        Type::Arrow(from, _) => *from,
+                        _ => return None,
+                    },
+                };
+
+                let Type::Range(lo, hi) = program.types.get(&range) else {
+                    return None;
+                };
+
+                Builtin::ParseInt {
+                    lo: *lo,
+                    hi: *hi,
+                    none: none.clone(),
+                    some: some.clone(),
+                }
+            }
+
+            "show_int" => Builtin::ShowInt,
+            _ => return None,
+        })
+    }
+
+    /// Match a value against a pattern, extending `env` with its bindings if
+    /// it matches.
+    fn matches(&self, pat: &Pat, value: &Value, env: Env) -> Result<Option<Env>> {
+        Ok(match (pat, value) {
+            (Pat::Bind(name), value) => Some(env.bind(name.clone(), value.clone())),
+            (Pat::Wildcard, _) => Some(env),
+
+            (Pat::Constructor(ctor), Value::Data(name, fields)) => {
+                (ctor == name && fields.is_empty()).then_some(env)
+            }
+
+            // Match the last field against the argument, and the constructor
+            // applied to the rest against the function.
+            (Pat::Apply(func, arg), Value::Data(name, fields)) => {
+                let Some((last, init)) = fields.split_last() else {
+                    return Ok(None);
+                };
+
+                let init = Value::Data(name.clone(), init.to_vec());
+                match self.matches(func, &init, env)? {
+                    Some(env) => self.matches(arg, last, env)?,
+                    None => None,
+                }
+            }
+
+            (Pat::Lit(lit), value) => literal(lit).same(value).then_some(env),
+
+            (Pat::Invalid, _) => return Err(Diagnostic::new("reached an invalid pattern")),
+            (Pat::Constructor(_) | Pat::Apply(..), _) => None,
+        })
+    }
+}
+
+impl Builtin {
+    /// The number of arguments it takes.
+    fn arity(&self) -> usize {
+        match self {
+            Self::Compare(_) | Self::Repeat | Self::Matches => 2,
+            Self::Length
+            | Self::Widen
+            | Self::Clamp(..)
+            | Self::ParseInt { .. }
+            | Self::ShowInt => 1,
+        }
+    }
+
+    /// Apply it to all of its arguments.
+    fn call(self, args: Vec<Value>) -> Result<Value> {
+        Ok(match (self, args.as_slice()) {
+            (Self::Compare(orderings), [Value::Integer(a), Value::Integer(b)]) => {
+                Value::Boolean(orderings.contains(&a.cmp(b)))
+            }
+
+            (Self::Length, [Value::String(text)]) => {
+                Value::Integer(text.chars().count().try_into().unwrap_or(i64::MAX))
+            }
+
+            (Self::Repeat, [Value::String(text), Value::Integer(count)]) => {
+                let count = usize::try_from(*count).unwrap_or(0);
+                let length = text.chars().count().saturating_mul(count);
+                if length > MAX_REPEAT {
+                    return Err(Diagnostic::new(format!(
+                        "`repeat` would build a string of {length} characters, more than the \
+                         limit of {MAX_REPEAT}"
+                    )));
+                }
+
+                Value::String(text.repeat(count))
+            }
+
+            (Self::Matches, [Value::String(text), Value::Regex(pat)]) => {
+                Value::Boolean(pat.is_match(text))
+            }
+
+            (Self::Widen, [Value::Integer(v)]) => Value::Integer(*v),
+            (Self::Clamp(lo, hi), [Value::Integer(v)]) => Value::Integer((*v).clamp(lo, hi - 1)),
+
+            (Self::ParseInt { lo, hi, none, some }, [Value::String(text)]) => match text.parse() {
+                Ok(v) if lo <= v && v < hi => Value::Data(some, vec![Value::Integer(v)]),
+                _ => Value::Data(none, Vec::new()),
+            },
+
+            (Self::ShowInt, [Value::Integer(v)]) => Value::String(v.to_string()),
+
+            (builtin, args) => {
+                let args: Vec<_> = args.iter().map(|arg| arg.to_string()).collect();
+                return Err(Diagnostic::new(format!(
+                    "cannot apply the builtin `{builtin:?}` to {}",
+                    args.join(", ")
+                )));
+            }
+        })
+    }
+}
+
+impl Value {
+    /// Whether two values of a type with a literal pattern are the same.
+    fn same(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Boolean(a), Self::Boolean(b)) => a == b,
+            (Self::Integer(a), Self::Integer(b)) => a == b,
+            (Self::String(a), Self::String(b)) => a == b,
+            (Self::Regex(a), Self::Regex(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    fn is_atomic(&self) -> bool {
+        !matches!(self, Self::Data(_, fields) if !fields.is_empty())
+    }
+}
+
+/// The number of arguments taken by a function of the given type.
+fn arity(program: &Program, ty: &Type) -> usize {
+    match ty {
+        Type::Arrow(_, into) => 1 + arity(program, program.types.get(into)),
+        Type::Forall(_, ty) => arity(program, program.types.get(ty)),
+        _ => 0,
+    }
+}
+
+fn literal(lit: &Literal) -> Value {
+    match lit {
+        Literal::Boolean(v) => Value::Boolean(*v),
+        Literal::Integer(v) => Value::Integer(*v),
+        Literal::String(v) => Value::String(v.clone()),
+        Literal::Regex(v) => Value::Regex(v.clone()),
+    }
+}
+
+fn binary(op: BinOp, lhs: Value, rhs: Value) -> Result<Value> {
+    match (op, lhs, rhs) {
+        (BinOp::Concat, Value::String(lhs), Value::String(rhs)) => Ok(Value::String(lhs + &rhs)),
+
+        (op, lhs, rhs) => Err(Diagnostic::new(format!(
+            "cannot apply `{op:?}` to `{lhs}` and `{rhs}`"
+        ))),
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Boolean(v) => write!(f, "{v}"),
+            Self::Integer(v) => write!(f, "{v}"),
+            Self::String(v) => write!(f, "{v:?}"),
+            Self::Regex(v) => write!(f, "/{}/", v.as_str()),
+
+            Self::Tuple(values) => {
+                write!(f, "(")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{value}")?;
+                }
+
+                write!(f, ")")
+            }
+
+            Self::Closure(_) | Self::Builtin(..) => write!(f, "<function>"),
+
+            Self::Data(name, fields) => {
+                write!(f, "{name}")?;
+                for field in fields {
+                    if field.is_atomic() {
+                        write!(f, " {field}")?;
+                    } else {
+                        write!(f, " ({field})")?;
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
@@ -1,32 +1,148 @@
-use std::collections::HashMap;
+pub mod visit;
 
-use crate::Regex;
+use indexmap::{IndexMap, IndexSet};
 
 pub type Name = String;
 
-#[derive(Clone, Debug)]
+/// The declarations of a program, in the order they appear in the source.
+#[derive(Clone, Debug, Default)]
 pub struct Decls {
-    pub values: HashMap<Name, ValueDef>,
+    /// The modules this program refers to.
+    pub imports: Vec<Import>,
+    /// The declarations marked `pub`, which importing modules may refer to.
+    /// The constructors of a public data type and the methods of a public
+    /// class are public along with it.
+    pub exports: IndexSet<Name>,
+    pub types: IndexMap<Name, TypeDef>,
+    pub classes: IndexMap<Name, ClassDef>,
+    pub instances: Vec<InstanceDef>,
+    pub values: IndexMap<Name, ValueDef>,
+}
+
+impl Decls {
+    /// Add the given declarations, each replacing any of the same name. A
+    /// replaced declaration moves to the end.
+    pub fn merge(&mut self, new: Decls) {
+        self.imports.extend(new.imports);
+        self.exports.extend(new.exports);
+        replace(&mut self.types, new.types);
+        replace(&mut self.classes, new.classes);
+        self.instances.extend(new.instances);
+        replace(&mut self.values, new.values);
+    }
+}
+
+fn replace<T>(old: &mut IndexMap<Name, T>, new: IndexMap<Name, T>) {
+    for (name, def) in new {
+        old.shift_remove(&name);
+        old.insert(name, def);
+    }
+}
+
+/// An import of another module, such as `import data.list as list`. Its public
+/// declarations are referred to by names qualified with the alias, or with
+/// the name of the module if there is none.
+#[derive(Clone, Debug)]
+pub struct Import {
+    pub module: Name,
+    pub alias: Option<Name>,
+}
+
+#[derive(Clone, Debug)]
+pub enum TypeDef {
+    Alias(AliasDef),
+    Data(DataDef),
+    Newtype(NewtypeDef),
+}
+
+/// A transparent type alias, such as `type Port = 1..65536`. Uses of the alias
+/// are interchangeable with its expansion.
+#[derive(Clone, Debug)]
+pub struct AliasDef {
+    pub vars: Vec<Name>,
+    pub body: Type,
+}
+
+/// A nominal data type. Its constructors may refer to the type itself, which
+/// allows recursive types such as lists and trees.
+#[derive(Clone, Debug)]
+pub struct DataDef {
+    /// Type variables this type is parameterised over.
+    pub vars: Vec<Name>,
+    pub ctors: Vec<Constructor>,
+}
+
+/// An opaque nominal type with the same values as its representation, such as
+/// `newtype UserId = 0..1000000`. The two are not assignable to each other;
+/// values are wrapped with a constructor of the same name as the type, and
+/// unwrapped by matching on it.
+#[derive(Clone, Debug)]
+pub struct NewtypeDef {
+    pub vars: Vec<Name>,
+    pub repr: Type,
+}
+
+#[derive(Clone, Debug)]
+pub struct Constructor {
+    pub name: Name,
+    pub fields: Vec<Type>,
+}
+
+/// A type class over a single type variable, such as
+/// `class Eq a { eq : a -> a -> Bool }`.
+#[derive(Clone, Debug)]
+pub struct ClassDef {
+    pub var: Name,
+    /// The name and type of every method, in which the class variable may
+    /// occur.
+    pub methods: Vec<(Name, Type)>,
+}
+
+/// An implementation of a class for some type, such as
+/// `instance Eq a => Eq (List a) { eq = ... }`.
+#[derive(Clone, Debug)]
+pub struct InstanceDef {
+    /// Type variables occuring in the instance type.
+    pub vars: Vec<TypeParam>,
+    pub class: Name,
+    pub ty: Type,
+    pub methods: Vec<(Name, Expr)>,
 }
 
 #[derive(Clone, Debug)]
 pub struct ValueDef {
     /// Type variables assosciated with this value definition.
-    pub vars: Vec<Name>,
+    pub vars: Vec<TypeParam>,
     pub anno: Type,
     pub body: Expr,
 }
 
+/// A type variable, along with the classes it must be an instance of.
+#[derive(Clone, Debug)]
+pub struct TypeParam {
+    pub name: Name,
+    pub bounds: Vec<Name>,
+}
+
 #[derive(Clone, Debug)]
 pub enum Type {
     Bool,
     Regex,
 
     Range(i64, i64),
-    String(Regex),
+    /// A string type whose values match the given pattern. The pattern is
+    /// compiled and validated during type checking.
+    String(String),
 
     Arrow(Box<Type>, Box<Type>),
 
+    /// Application of a parameterised type to an argument, such as `List a`.
+    Apply(Box<Type>, Box<Type>),
+
+    /// A polymorphic type, such as `forall a. a -> a`. These may occur as the
+    /// types of function arguments.
+    Forall(Vec<Name>, Box<Type>),
+
     Named(Name),
 
     Wildcard,
@@ -49,14 +165,25 @@ pub enum Expr {
 
     Call(Box<Expr>, Box<Expr>),
 
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+
     Lit(Literal),
 
     Name(Name),
+    /// A polymorphic name with explicit type arguments for its first type
+    /// variables, such as `f @(0..10)`.
+    Instantiate(Name, Vec<Type>),
 
     Impossible,
     Invalid,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BinOp {
+    /// String concatenation, `a ++ b`.
+    Concat,
+}
+
 #[derive(Clone, Debug)]
 pub enum Pat {
     Constructor(Name),
@@ -0,0 +1,284 @@
+//! Traversals of expressions, patterns and types. Every method has a default
+//! which walks the children of its node with the matching `walk_*` function,
+//! so a pass only overrides the nodes it is interested in, and calls the walk
+//! function itself to carry on into their children.
+
+use super::{Expr, Literal, Pat, Type};
+
+/// A traversal which reads each node.
+pub trait Visitor {
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_pat(&mut self, pat: &Pat) {
+        walk_pat(self, pat);
+    }
+
+    fn visit_type(&mut self, ty: &Type) {
+        walk_type(self, ty);
+    }
+
+    fn visit_literal(&mut self, _: &Literal) {}
+}
+
+/// A traversal which may change each node in place.
+pub trait VisitorMut {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        walk_expr_mut(self, expr);
+    }
+
+    fn visit_pat_mut(&mut self, pat: &mut Pat) {
+        walk_pat_mut(self, pat);
+    }
+
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        walk_type_mut(self, ty);
+    }
+
+    fn visit_literal_mut(&mut self, _: &mut Literal) {}
+}
+
+/// A traversal which rebuilds each node from its folded children.
+pub trait Folder {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        walk_expr_fold(self, expr)
+    }
+
+    fn fold_pat(&mut self, pat: Pat) -> Pat {
+        walk_pat_fold(self, pat)
+    }
+
+    fn fold_type(&mut self, ty: Type) -> Type {
+        walk_type_fold(self, ty)
+    }
+
+    fn fold_literal(&mut self, lit: Literal) -> Literal {
+        lit
+    }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    match expr {
+        Expr::Anno(expr, ty) => {
+            visitor.visit_expr(expr);
+            visitor.visit_type(ty);
+        }
+
+        Expr::Fun(pat, body) => {
+            visitor.visit_pat(pat);
+            visitor.visit_expr(body);
+        }
+
+        Expr::Let {
+            pat,
+            bound,
+            then,
+            elze,
+        } => {
+            visitor.visit_pat(pat);
+            visitor.visit_expr(bound);
+            visitor.visit_expr(then);
+            visitor.visit_expr(elze);
+        }
+
+        Expr::Call(lhs, rhs) | Expr::Binary(_, lhs, rhs) => {
+            visitor.visit_expr(lhs);
+            visitor.visit_expr(rhs);
+        }
+
+        Expr::Lit(lit) => visitor.visit_literal(lit),
+
+        Expr::Instantiate(_, args) => {
+            for arg in args.iter() {
+                visitor.visit_type(arg);
+            }
+        }
+
+        Expr::Name(_) | Expr::Impossible | Expr::Invalid => {}
+    }
+}
+
+pub fn walk_pat<V: Visitor + ?Sized>(visitor: &mut V, pat: &Pat) {
+    match pat {
+        Pat::Apply(ctor, arg) => {
+            visitor.visit_pat(ctor);
+            visitor.visit_pat(arg);
+        }
+
+        Pat::Lit(lit) => visitor.visit_literal(lit),
+        Pat::Constructor(_) | Pat::Bind(_) | Pat::Wildcard => {}
+    }
+}
+
+pub fn walk_type<V: Visitor + ?Sized>(visitor: &mut V, ty: &Type) {
+    match ty {
+        Type::Arrow(lhs, rhs) | Type::Apply(lhs, rhs) => {
+            visitor.visit_type(lhs);
+            visitor.visit_type(rhs);
+        }
+
+        Type::Forall(_, ty) => visitor.visit_type(ty),
+
+        Type::Bool
+        | Type::Regex
+        | Type::Range(..)
+        | Type::String(_)
+        | Type::Named(_)
+        | Type::Wildcard
+        | Type::Invalid => {}
+    }
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut Expr) {
+    match expr {
+        Expr::Anno(expr, ty) => {
+            visitor.visit_expr_mut(expr);
+            visitor.visit_type_mut(ty);
+        }
+
+        Expr::Fun(pat, body) => {
+            visitor.visit_pat_mut(pat);
+            visitor.visit_expr_mut(body);
+        }
+
+        Expr::Let {
+            pat,
+            bound,
+            then,
+            elze,
+        } => {
+            visitor.visit_pat_mut(pat);
+            visitor.visit_expr_mut(bound);
+            visitor.visit_expr_mut(then);
+            visitor.visit_expr_mut(elze);
+        }
+
+        Expr::Call(lhs, rhs) | Expr::Binary(_, lhs, rhs) => {
+            visitor.visit_expr_mut(lhs);
+            visitor.visit_expr_mut(rhs);
+        }
+
+        Expr::Lit(lit) => visitor.visit_literal_mut(lit),
+
+        Expr::Instantiate(_, args) => {
+            for arg in args.iter_mut() {
+                visitor.visit_type_mut(arg);
+            }
+        }
+
+        Expr::Name(_) | Expr::Impossible | Expr::Invalid => {}
+    }
+}
+
+pub fn walk_pat_mut<V: VisitorMut + ?Sized>(visitor: &mut V, pat: &mut Pat) {
+    match pat {
+        Pat::Apply(ctor, arg) => {
+            visitor.visit_pat_mut(ctor);
+            visitor.visit_pat_mut(arg);
+        }
+
+        Pat::Lit(lit) => visitor.visit_literal_mut(lit),
+        Pat::Constructor(_) | Pat::Bind(_) | Pat::Wildcard => {}
+    }
+}
+
+pub fn walk_type_mut<V: VisitorMut + ?Sized>(visitor: &mut V, ty: &mut Type) {
+    match ty {
+        Type::Arrow(lhs, rhs) | Type::Apply(lhs, rhs) => {
+            visitor.visit_type_mut(lhs);
+            visitor.visit_type_mut(rhs);
+        }
+
+        Type::Forall(_, ty) => visitor.visit_type_mut(ty),
+
+        Type::Bool
+        | Type::Regex
+        | Type::Range(..)
+        | Type::String(_)
+        | Type::Named(_)
+        | Type::Wildcard
+        | Type::Invalid => {}
+    }
+}
+
+pub fn walk_expr_fold<F: Folder + ?Sized>(folder: &mut F, expr: Expr) -> Expr {
+    match expr {
+        Expr::Anno(expr, ty) => {
+            let expr = Box::new(folder.fold_expr(*expr));
+            Expr::Anno(expr, folder.fold_type(ty))
+        }
+
+        Expr::Fun(pat, body) => {
+            let pat = folder.fold_pat(pat);
+            Expr::Fun(pat, Box::new(folder.fold_expr(*body)))
+        }
+
+        Expr::Let {
+            pat,
+            bound,
+            then,
+            elze,
+        } => Expr::Let {
+            pat: folder.fold_pat(pat),
+            bound: Box::new(folder.fold_expr(*bound)),
+            then: Box::new(folder.fold_expr(*then)),
+            elze: Box::new(folder.fold_expr(*elze)),
+        },
+
+        Expr::Call(func, arg) => {
+            let func = Box::new(folder.fold_expr(*func));
+            Expr::Call(func, Box::new(folder.fold_expr(*arg)))
+        }
+
+        Expr::Binary(op, lhs, rhs) => {
+            let lhs = Box::new(folder.fold_expr(*lhs));
+            Expr::Binary(op, lhs, Box::new(folder.fold_expr(*rhs)))
+        }
+
+        Expr::Lit(lit) => Expr::Lit(folder.fold_literal(lit)),
+
+        Expr::Instantiate(name, args) => {
+            let args = args.into_iter().map(|arg| folder.fold_type(arg)).collect();
+            Expr::Instantiate(name, args)
+        }
+
+        expr @ (Expr::Name(_) | Expr::Impossible | Expr::Invalid) => expr,
+    }
+}
+
+pub fn walk_pat_fold<F: Folder + ?Sized>(folder: &mut F, pat: Pat) -> Pat {
+    match pat {
+        Pat::Apply(ctor, arg) => {
+            let ctor = Box::new(folder.fold_pat(*ctor));
+            Pat::Apply(ctor, Box::new(folder.fold_pat(*arg)))
+        }
+
+        Pat::Lit(lit) => Pat::Lit(folder.fold_literal(lit)),
+        pat @ (Pat::Constructor(_) | Pat::Bind(_) | Pat::Wildcard) => pat,
+    }
+}
+
+pub fn walk_type_fold<F: Folder + ?Sized>(folder: &mut F, ty: Type) -> Type {
+    match ty {
+        Type::Arrow(from, into) => {
+            let from = Box::new(folder.fold_type(*from));
+            Type::Arrow(from, Box::new(folder.fold_type(*into)))
+        }
+
+        Type::Apply(func, arg) => {
+            let func = Box::new(folder.fold_type(*func));
+            Type::Apply(func, Box::new(folder.fold_type(*arg)))
+        }
+
+        Type::Forall(vars, ty) => Type::Forall(vars, Box::new(folder.fold_type(*ty))),
+
+        ty @ (Type::Bool
+        | Type::Regex
+        | Type::Range(..)
+        | Type::String(_)
+        | Type::Named(_)
+        | Type::Wildcard
+        | Type::Invalid) => ty,
+    }
+}
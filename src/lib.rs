@@ -1,9 +1,17 @@
+pub mod diagnostic;
+pub mod eval;
 pub mod hir;
 pub mod mir;
+pub mod module;
+pub mod mono;
+pub mod pretty;
+pub mod repl;
+pub mod syntax;
 pub mod types;
 
 mod regex;
 mod typeck;
 
+pub use crate::diagnostic::Diagnostic;
 pub use crate::regex::Regex;
-pub use typeck::typeck;
+pub use typeck::{typeck, typeck_traced, Event, Session, Step, Trace};
@@ -1,49 +1,276 @@
-use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::process::ExitCode;
 
-use illiol::hir::{Decls, Expr, Literal, Pat, Type, ValueDef};
-use illiol::typeck;
+use illiol::eval::Interpreter;
+use illiol::repl::{Repl, Reply};
+use illiol::{hir, mir, module, pretty, syntax, typeck, typeck_traced, Diagnostic};
 
-fn main() {
+const USAGE: &str = "usage:
+  illiol check [--trace-constraints[=text|dot]] [--print=hir|mir|mir-text] [--width=<n>] <file>
+  illiol run <file>|<file.mir> [<value>]
+  illiol fmt [--check] [--width=<n>] <file>...
+  illiol repl";
+
+/// How to print the constraint trace.
+#[derive(Clone, Copy)]
+enum TraceFormat {
+    Text,
+    Dot,
+}
+
+/// Which form of the program to print.
+#[derive(Clone, Copy)]
+enum Print {
+    Hir,
+    Mir,
+    /// The textual form of the typed program, which can be read back.
+    MirText,
+}
+
+fn main() -> ExitCode {
     env_logger::init();
 
-    let f = Expr::Anno(
-        Box::new(Expr::Fun(
-            Pat::Bind("a".into()),
-            Box::new(Expr::Name("a".into())),
-        )),
-        Type::Arrow(
-            Box::new(Type::Named("T".into())),
-            Box::new(Type::Named("T".into())),
-        ),
-    );
-    let f = ValueDef {
-        anno: Type::Wildcard,
-        body: f,
-        vars: vec!["T".into()],
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("check") => check(&args[1..]),
+        Some("run") => run(&args[1..]),
+        Some("fmt") => fmt(&args[1..]),
+        Some("repl") if args.len() == 1 => repl(),
+        _ => usage(),
+    }
+}
+
+fn usage() -> ExitCode {
+    eprintln!("{USAGE}");
+    ExitCode::from(2)
+}
+
+fn check(args: &[String]) -> ExitCode {
+    let mut trace = None;
+    let mut print = None;
+    let mut width = pretty::WIDTH;
+    let mut path = None;
+    for arg in args {
+        match arg.as_str() {
+            "--trace-constraints" | "--trace-constraints=text" => trace = Some(TraceFormat::Text),
+            "--trace-constraints=dot" => trace = Some(TraceFormat::Dot),
+            "--print=hir" => print = Some(Print::Hir),
+            "--print=mir" => print = Some(Print::Mir),
+            "--print=mir-text" => print = Some(Print::MirText),
+            arg if arg.starts_with("--width=") => match arg["--width=".len()..].parse() {
+                Ok(n) => width = n,
+                Err(_) => return usage(),
+            },
+            arg if arg.starts_with('-') || path.is_some() => return usage(),
+            arg => path = Some(arg),
+        }
+    }
+
+    let Some(path) = path else {
+        return usage();
+    };
+
+    let decls = match load(path) {
+        Ok(decls) => decls,
+        Err(code) => return code,
     };
 
-    let x = Expr::Lit(Literal::Integer(5));
-    let x = ValueDef {
-        anno: Type::Range(0, 10),
-        body: x,
-        vars: vec![],
+    if let Some(Print::Hir) = print {
+        print!("{}", pretty::hir(&decls, width));
+    }
+
+    let (program, diagnostics) = match trace {
+        None => typeck(decls),
+        Some(format) => {
+            let (program, diagnostics, trace) = typeck_traced(decls);
+            match format {
+                TraceFormat::Text => print!("{trace}"),
+                TraceFormat::Dot => print!("{}", trace.to_dot()),
+            }
+
+            (program, diagnostics)
+        }
     };
 
-    let y = Expr::Call(
-        Box::new(Expr::Name("f".into())),
-        Box::new(Expr::Name("x".into())),
-    );
-    let y = ValueDef {
-        anno: Type::Wildcard,
-        body: y,
-        vars: vec![],
+    match print {
+        Some(Print::Mir) => print!("{}", pretty::mir(&program, width)),
+        Some(Print::MirText) => print!("{}", mir::text::print(&program)),
+        _ => {}
+    }
+
+    report(&diagnostics)
+}
+
+/// Evaluate a value of the given program, `main` by default, and print it.
+/// A program in a `.mir` file is read in its textual form, and only validated
+/// rather than checked.
+fn run(args: &[String]) -> ExitCode {
+    let (path, name) = match args {
+        [path] => (path, "main"),
+        [path, name] => (path, name.as_str()),
+        _ => return usage(),
     };
 
-    let prog = Decls {
-        values: HashMap::from([("x".into(), x), ("y".into(), y), ("f".into(), f)]),
+    let program = if path.ends_with(".mir") {
+        let src = match read(path) {
+            Ok(src) => src,
+            Err(code) => return code,
+        };
+
+        let program = match mir::text::parse(&src) {
+            Ok(program) => program,
+            Err(diagnostic) => return report(&[diagnostic]),
+        };
+
+        let diagnostics = mir::validate(&program);
+        if !diagnostics.is_empty() {
+            return report(&diagnostics);
+        }
+
+        program
+    } else {
+        let decls = match load(path) {
+            Ok(decls) => decls,
+            Err(code) => return code,
+        };
+
+        let (program, diagnostics) = typeck(decls);
+        if !diagnostics.is_empty() {
+            return report(&diagnostics);
+        }
+
+        program
     };
 
-    let checked = typeck(prog);
+    match Interpreter::new(&program).global(name) {
+        Ok(value) => {
+            println!("{value}");
+            ExitCode::SUCCESS
+        }
+        Err(diagnostic) => report(&[diagnostic]),
+    }
+}
+
+/// Lay out each file canonically, or with `--check` only report the files
+/// which are not laid out canonically.
+fn fmt(args: &[String]) -> ExitCode {
+    let mut check = false;
+    let mut width = pretty::WIDTH;
+    let mut paths = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--check" => check = true,
+            arg if arg.starts_with("--width=") => match arg["--width=".len()..].parse() {
+                Ok(n) => width = n,
+                Err(_) => return usage(),
+            },
+            arg if arg.starts_with('-') => return usage(),
+            arg => paths.push(arg),
+        }
+    }
+
+    if paths.is_empty() {
+        return usage();
+    }
+
+    let mut code = ExitCode::SUCCESS;
+    for path in paths {
+        let src = match std::fs::read_to_string(path) {
+            Ok(src) => src,
+            Err(err) => {
+                eprintln!("error: cannot read `{path}`: {err}");
+                code = ExitCode::FAILURE;
+                continue;
+            }
+        };
+
+        let formatted = match syntax::format(&src, width) {
+            Ok(formatted) => formatted,
+            Err(diagnostic) => {
+                eprintln!("{}", diagnostic.with_note(format!("in `{path}`")));
+                code = ExitCode::FAILURE;
+                continue;
+            }
+        };
+
+        if formatted == src {
+            continue;
+        }
+
+        if check {
+            println!("{path} is not formatted");
+            code = ExitCode::FAILURE;
+        } else if let Err(err) = std::fs::write(path, formatted) {
+            eprintln!("error: cannot write `{path}`: {err}");
+            code = ExitCode::FAILURE;
+        }
+    }
+
+    code
+}
+
+/// Read inputs from the terminal until the end of input or `:quit`.
+fn repl() -> ExitCode {
+    let mut repl = Repl::new();
+    let mut input = String::new();
+    let mut lines = std::io::stdin().lock().lines();
+
+    loop {
+        print!("{}", if input.is_empty() { "> " } else { "| " });
+        let _ = std::io::stdout().flush();
+
+        let Some(Ok(line)) = lines.next() else {
+            println!();
+            return ExitCode::SUCCESS;
+        };
+
+        input.push_str(&line);
+        input.push('\n');
+
+        match repl.input(&input) {
+            Reply::Output(output) if output.is_empty() => {}
+            Reply::Output(output) => println!("{output}"),
+            Reply::Errors(diagnostics) => {
+                for diagnostic in diagnostics {
+                    println!("{diagnostic}");
+                }
+            }
+            Reply::Incomplete => continue,
+            Reply::Quit => return ExitCode::SUCCESS,
+        }
+
+        input.clear();
+    }
+}
+
+/// Load the program in the given file along with the modules it imports,
+/// reporting any problem.
+fn load(path: &str) -> Result<hir::Decls, ExitCode> {
+    let modules = module::load(Path::new(path)).map_err(|diagnostics| report(&diagnostics))?;
+    let (decls, diagnostics) = module::link(modules);
+    match diagnostics.is_empty() {
+        true => Ok(decls),
+        false => Err(report(&diagnostics)),
+    }
+}
+
+fn read(path: &str) -> Result<String, ExitCode> {
+    std::fs::read_to_string(path).map_err(|err| {
+        eprintln!("error: cannot read `{path}`: {err}");
+        ExitCode::FAILURE
+    })
+}
+
+/// Print the given diagnostics, failing if there are any.
+fn report(diagnostics: &[Diagnostic]) -> ExitCode {
+    for diagnostic in diagnostics {
+        eprintln!("{diagnostic}");
+    }
 
-    println!("{checked:#?}");
+    if diagnostics.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
 }
@@ -1,34 +1,51 @@
-use std::collections::HashMap;
+pub mod text;
+mod validate;
+pub mod visit;
+
+pub use validate::validate;
+
+use indexmap::IndexMap;
 
 use crate::types::{TypeId, Types};
+use crate::Regex;
 
 pub type Name = String;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct Program {
-    pub context: HashMap<Name, Template>,
+    pub context: IndexMap<Name, Template>,
     pub decls: Decls,
     pub types: Types,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct Template {
     pub params: Vec<Name>,
     pub uninst: TypeId,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Decls {
-    pub values: HashMap<Name, Expr>,
+    pub types: IndexMap<Name, DataDef>,
+    pub values: IndexMap<Name, Expr>,
+}
+
+/// A nominal data type. The types of its constructors are given by their
+/// entries in [`Program::context`]. Newtypes are data types with a single
+/// constructor of the same name as the type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DataDef {
+    pub params: Vec<Name>,
+    pub ctors: Vec<Name>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Expr {
     pub node: ExprNode,
     pub anno: TypeId,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ExprNode {
     Fun(Pat, Box<Expr>),
 
@@ -43,28 +60,39 @@ pub enum ExprNode {
 
     Call(Box<Expr>, Box<Expr>),
 
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+
     Lit(Literal),
 
     Name(Name),
-    Instantiated(Name),
+    /// A polymorphic name, along with the type each of its type parameters
+    /// was instantiated to.
+    Instantiated(Name, Vec<TypeId>),
 
     Impossible,
     Invalid,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BinOp {
+    /// String concatenation.
+    Concat,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum Pat {
     Constructor(Name),
     Bind(Name),
     Apply(Box<Pat>, Box<Pat>),
     Lit(Literal),
     Wildcard,
+    Invalid,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Literal {
     Boolean(bool),
     Integer(i64),
     String(String),
-    Regex(String),
+    Regex(Regex),
 }
@@ -0,0 +1,528 @@
+//! A textual form of typed programs, which can be read back into an identical
+//! [`Program`]. Unlike the [pretty-printer](crate::pretty::mir) it keeps the
+//! type of every expression and the id of every type.
+//!
+//! A program is a sequence of entries, whose order within each kind is kept:
+//!
+//! ```text
+//! type #0 = Bool
+//! type #1 = Arrow #0 #0
+//! type #2 = Named List [#0]
+//! type #3 = Forall ['a] #1
+//! template id ['a] : #3
+//! data List ['a] [Nil Cons]
+//! val not (fun #1 (lit true) (lit #0 false))
+//! ```
+//!
+//! Expressions and patterns are parenthesised forms, where every expression
+//! is followed by the id of its type. Names which are not plain words are
+//! written as string literals.
+
+use indexmap::IndexMap;
+
+use super::{BinOp, DataDef, Decls, Expr, ExprNode, Literal, Name, Pat, Program, Template};
+use crate::pretty::{Doc, WIDTH};
+use crate::types::{Type, TypeId, Types};
+use crate::{Diagnostic, Regex};
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+/// Write a program in its textual form.
+pub fn print(program: &Program) -> String {
+    let mut out = String::new();
+    for (id, ty) in program.types.iter() {
+        out.push_str(&format!("type {} = {}\n", type_id(id), type_text(ty)));
+    }
+
+    for (name, template) in program.context.iter() {
+        out.push_str(&format!(
+            "template {} {} : {}\n",
+            name_text(name),
+            list(template.params.iter().map(|param| name_text(param))),
+            type_id(template.uninst)
+        ));
+    }
+
+    for (name, def) in program.decls.types.iter() {
+        out.push_str(&format!(
+            "data {} {} {}\n",
+            name_text(name),
+            list(def.params.iter().map(|param| name_text(param))),
+            list(def.ctors.iter().map(|ctor| name_text(ctor)))
+        ));
+    }
+
+    for (name, body) in program.decls.values.iter() {
+        let head = Doc::text(format!("val {}", name_text(name)));
+        let body = Doc::concat([Doc::line(), expr_doc(body)]).nest();
+        out.push_str(&Doc::concat([head, body]).group().render(WIDTH));
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Read a program from its textual form.
+pub fn parse(src: &str) -> Result<Program> {
+    let mut reader = Reader { src, pos: 0 };
+    let mut types = Types::new();
+    let mut context = IndexMap::new();
+    let mut decls = Decls {
+        types: IndexMap::new(),
+        values: IndexMap::new(),
+    };
+
+    loop {
+        reader.skip_space();
+        if reader.peek().is_none() {
+            break;
+        }
+
+        let start = reader.pos;
+        let (name, defined) = match reader.word("an entry")? {
+            "type" => {
+                let id = reader.type_id()?;
+                reader.expect('=')?;
+                let defined = types.contains(&id);
+                types.add(id, reader.ty()?);
+                (type_id(id), defined)
+            }
+
+            "template" => {
+                let name = reader.name()?;
+                let params = reader.list(Reader::name)?;
+                reader.expect(':')?;
+                let uninst = reader.type_id()?;
+                let defined = context
+                    .insert(name.clone(), Template { params, uninst })
+                    .is_some();
+                (name, defined)
+            }
+
+            "data" => {
+                let name = reader.name()?;
+                let params = reader.list(Reader::name)?;
+                let ctors = reader.list(Reader::name)?;
+                let defined = decls
+                    .types
+                    .insert(name.clone(), DataDef { params, ctors })
+                    .is_some();
+                (name, defined)
+            }
+
+            "val" => {
+                let name = reader.name()?;
+                let body = reader.expr()?;
+                let defined = decls.values.insert(name.clone(), body).is_some();
+                (name, defined)
+            }
+
+            word => {
+                reader.pos = start;
+                return Err(reader.error(format!("expected an entry, found `{word}`")));
+            }
+        };
+
+        if defined {
+            reader.pos = start;
+            return Err(reader.error(format!("`{name}` is defined more than once")));
+        }
+    }
+
+    Ok(Program {
+        context,
+        decls,
+        types,
+    })
+}
+
+fn type_id(id: TypeId) -> String {
+    format!("#{}", id.0)
+}
+
+fn type_text(ty: &Type) -> String {
+    match ty {
+        Type::Bottom => "Bottom".into(),
+        Type::Bool => "Bool".into(),
+        Type::Regex => "Regex".into(),
+        Type::Range(lo, hi) => format!("Range {lo} {hi}"),
+        Type::String(pat) => format!("String {}", regex(pat)),
+        Type::Arrow(from, into) => format!("Arrow {} {}", type_id(*from), type_id(*into)),
+        Type::Named(name, args) => format!(
+            "Named {} {}",
+            name_text(name),
+            list(args.iter().map(|arg| type_id(*arg)))
+        ),
+        Type::Forall(vars, ty) => format!(
+            "Forall {} {}",
+            list(vars.iter().map(|var| name_text(var))),
+            type_id(*ty)
+        ),
+        Type::Error => "Error".into(),
+    }
+}
+
+fn list(items: impl Iterator<Item = String>) -> String {
+    format!("[{}]", items.collect::<Vec<_>>().join(" "))
+}
+
+/// Whether a character ends a word.
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '(' | ')' | '[' | ']' | '"' | '/' | '=' | ':')
+}
+
+/// A name as a word, or as a string literal if it would not be read back as
+/// one.
+fn name_text(name: &str) -> String {
+    match !name.is_empty() && !name.contains(is_delimiter) {
+        true => name.to_string(),
+        false => quoted(name, '"'),
+    }
+}
+
+/// Text in the given delimiters, escaping them along with backslashes and
+/// control characters.
+fn quoted(text: &str, delimiter: char) -> String {
+    let mut out = String::from(delimiter);
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c == delimiter => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_control() => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => out.push(c),
+        }
+    }
+
+    out.push(delimiter);
+    out
+}
+
+fn regex(pat: &Regex) -> String {
+    quoted(pat.as_str(), '/')
+}
+
+fn literal(lit: &Literal) -> String {
+    match lit {
+        Literal::Boolean(v) => v.to_string(),
+        Literal::Integer(v) => v.to_string(),
+        Literal::String(v) => quoted(v, '"'),
+        Literal::Regex(pat) => regex(pat),
+    }
+}
+
+/// A parenthesised form, with its arguments on lines of their own if it does
+/// not fit on one.
+fn form(head: String, args: Vec<Doc>) -> Doc {
+    let args = args.into_iter().map(|arg| Doc::concat([Doc::line(), arg]));
+    let args = Doc::concat(args.collect::<Vec<_>>()).nest();
+    Doc::concat([Doc::text(format!("({head}")), args, Doc::text(")")]).group()
+}
+
+fn expr_doc(expr: &Expr) -> Doc {
+    let ty = type_id(expr.anno);
+    match &expr.node {
+        ExprNode::Fun(pat, body) => form(format!("fun {ty}"), vec![pat_doc(pat), expr_doc(body)]),
+
+        ExprNode::Let {
+            pat,
+            bound,
+            then,
+            elze,
+        } => form(
+            format!("let {ty}"),
+            vec![
+                pat_doc(pat),
+                expr_doc(bound),
+                expr_doc(then),
+                expr_doc(elze),
+            ],
+        ),
+
+        ExprNode::Tuple(exprs) => form(format!("tuple {ty}"), exprs.iter().map(expr_doc).collect()),
+        ExprNode::Call(func, arg) => {
+            form(format!("call {ty}"), vec![expr_doc(func), expr_doc(arg)])
+        }
+
+        ExprNode::Binary(op, lhs, rhs) => {
+            let op = match op {
+                BinOp::Concat => "concat",
+            };
+
+            form(format!("{op} {ty}"), vec![expr_doc(lhs), expr_doc(rhs)])
+        }
+
+        ExprNode::Lit(lit) => Doc::text(format!("(lit {ty} {})", literal(lit))),
+        ExprNode::Name(name) => Doc::text(format!("(name {ty} {})", name_text(name))),
+        ExprNode::Instantiated(name, args) => Doc::text(format!(
+            "(inst {ty} {} {})",
+            name_text(name),
+            list(args.iter().map(|arg| type_id(*arg)))
+        )),
+
+        ExprNode::Impossible => Doc::text(format!("(impossible {ty})")),
+        ExprNode::Invalid => Doc::text(format!("(invalid {ty})")),
+    }
+}
+
+fn pat_doc(pat: &Pat) -> Doc {
+    Doc::text(pat_text(pat))
+}
+
+fn pat_text(pat: &Pat) -> String {
+    match pat {
+        Pat::Constructor(name) => format!("(ctor {})", name_text(name)),
+        Pat::Bind(name) => format!("(bind {})", name_text(name)),
+        Pat::Apply(ctor, arg) => format!("(apply {} {})", pat_text(ctor), pat_text(arg)),
+        Pat::Lit(lit) => format!("(lit {})", literal(lit)),
+        Pat::Wildcard => "_".into(),
+        Pat::Invalid => "(invalid)".into(),
+    }
+}
+
+struct Reader<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_space(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn error(&self, message: String) -> Diagnostic {
+        let (line, column) = crate::syntax::position(self.src, self.pos);
+        Diagnostic::new(message).with_note(format!("at line {line}, column {column}"))
+    }
+
+    /// A diagnostic for the next character, which is not what was expected.
+    fn expected(&mut self, what: &str) -> Diagnostic {
+        self.skip_space();
+        let found = match self.peek() {
+            Some(c) => format!("`{c}`"),
+            None => "the end of the input".into(),
+        };
+
+        self.error(format!("expected {what}, found {found}"))
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_space();
+        let found = self.peek() == Some(c);
+        if found {
+            self.bump();
+        }
+
+        found
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        match self.eat(c) {
+            true => Ok(()),
+            false => Err(self.expected(&format!("`{c}`"))),
+        }
+    }
+
+    /// The characters up to the next delimiter.
+    fn word(&mut self, what: &str) -> Result<&'a str> {
+        self.skip_space();
+        let start = self.pos;
+        while self.peek().is_some_and(|c| !is_delimiter(c)) {
+            self.bump();
+        }
+
+        match start == self.pos {
+            true => Err(self.expected(what)),
+            false => Ok(&self.src[start..self.pos]),
+        }
+    }
+
+    /// Text in the given delimiters, with escapes replaced.
+    fn quoted(&mut self, delimiter: char) -> Result<String> {
+        self.expect(delimiter)?;
+        let mut text = String::new();
+        loop {
+            match self.bump() {
+                Some(c) if c == delimiter => return Ok(text),
+                Some('\\') => match self.bump() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('u') => text.push(self.unicode()?),
+                    Some(c) => text.push(c),
+                    None => break,
+                },
+                Some(c) => text.push(c),
+                None => break,
+            }
+        }
+
+        Err(self.error("unterminated literal".into()))
+    }
+
+    /// The character of a `\u{...}` escape, after the `u`.
+    fn unicode(&mut self) -> Result<char> {
+        self.expect('{')?;
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+            self.bump();
+        }
+
+        let digits = &self.src[start..self.pos];
+        self.expect('}')?;
+        u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error(format!("invalid character escape `\\u{{{digits}}}`")))
+    }
+
+    fn name(&mut self) -> Result<Name> {
+        self.skip_space();
+        match self.peek() {
+            Some('"') => self.quoted('"'),
+            _ => Ok(self.word("a name")?.to_string()),
+        }
+    }
+
+    fn integer(&mut self) -> Result<i64> {
+        let word = self.word("an integer")?;
+        word.parse()
+            .map_err(|_| self.error(format!("expected an integer, found `{word}`")))
+    }
+
+    fn type_id(&mut self) -> Result<TypeId> {
+        let word = self.word("a type id")?;
+        word.strip_prefix('#')
+            .and_then(|id| id.parse().ok())
+            .map(TypeId)
+            .ok_or_else(|| self.error(format!("expected a type id, found `{word}`")))
+    }
+
+    fn regex(&mut self) -> Result<Regex> {
+        let pattern = self.quoted('/')?;
+        Regex::new(&pattern).map_err(|err| self.error(format!("invalid regex: {err}")))
+    }
+
+    /// Items in brackets.
+    fn list<T>(&mut self, mut item: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        while !self.eat(']') {
+            items.push(item(self)?);
+        }
+
+        Ok(items)
+    }
+
+    fn ty(&mut self) -> Result<Type> {
+        Ok(match self.word("a type")? {
+            "Bottom" => Type::Bottom,
+            "Bool" => Type::Bool,
+            "Regex" => Type::Regex,
+            "Range" => Type::Range(self.integer()?, self.integer()?),
+            "String" => Type::String(self.regex()?),
+            "Arrow" => Type::Arrow(self.type_id()?, self.type_id()?),
+            "Named" => Type::Named(self.name()?, self.list(Self::type_id)?),
+            "Forall" => Type::Forall(self.list(Self::name)?, self.type_id()?),
+            "Error" => Type::Error,
+            word => return Err(self.error(format!("expected a type, found `{word}`"))),
+        })
+    }
+
+    fn literal(&mut self) -> Result<Literal> {
+        self.skip_space();
+        Ok(match self.peek() {
+            Some('"') => Literal::String(self.quoted('"')?),
+            Some('/') => Literal::Regex(self.regex()?),
+            _ => match self.word("a literal")? {
+                "true" => Literal::Boolean(true),
+                "false" => Literal::Boolean(false),
+                word => match word.parse() {
+                    Ok(v) => Literal::Integer(v),
+                    Err(_) => return Err(self.error(format!("expected a literal, found `{word}`"))),
+                },
+            },
+        })
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        self.expect('(')?;
+        let head = self.word("an expression")?;
+        let anno = self.type_id()?;
+        let node = match head {
+            "fun" => ExprNode::Fun(self.pat()?, Box::new(self.expr()?)),
+            "let" => ExprNode::Let {
+                pat: self.pat()?,
+                bound: Box::new(self.expr()?),
+                then: Box::new(self.expr()?),
+                elze: Box::new(self.expr()?),
+            },
+
+            "tuple" => {
+                let mut exprs = Vec::new();
+                while !self.eat(')') {
+                    exprs.push(self.expr()?);
+                }
+
+                return Ok(Expr {
+                    node: ExprNode::Tuple(exprs),
+                    anno,
+                });
+            }
+
+            "call" => ExprNode::Call(Box::new(self.expr()?), Box::new(self.expr()?)),
+            "concat" => ExprNode::Binary(
+                BinOp::Concat,
+                Box::new(self.expr()?),
+                Box::new(self.expr()?),
+            ),
+            "lit" => ExprNode::Lit(self.literal()?),
+            "name" => ExprNode::Name(self.name()?),
+            "inst" => ExprNode::Instantiated(self.name()?, self.list(Self::type_id)?),
+            "impossible" => ExprNode::Impossible,
+            "invalid" => ExprNode::Invalid,
+            head => return Err(self.error(format!("expected an expression, found `{head}`"))),
+        };
+
+        self.expect(')')?;
+        Ok(Expr { node, anno })
+    }
+
+    fn pat(&mut self) -> Result<Pat> {
+        self.skip_space();
+        if self.peek() != Some('(') {
+            return match self.word("a pattern")? {
+                "_" => Ok(Pat::Wildcard),
+                word => Err(self.error(format!("expected a pattern, found `{word}`"))),
+            };
+        }
+
+        self.bump();
+        let pat = match self.word("a pattern")? {
+            "ctor" => Pat::Constructor(self.name()?),
+            "bind" => Pat::Bind(self.name()?),
+            "apply" => Pat::Apply(Box::new(self.pat()?), Box::new(self.pat()?)),
+            "lit" => Pat::Lit(self.literal()?),
+            "invalid" => Pat::Invalid,
+            word => return Err(self.error(format!("expected a pattern, found `{word}`"))),
+        };
+
+        self.expect(')')?;
+        Ok(pat)
+    }
+}
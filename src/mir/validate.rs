@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+
+use super::visit::{walk_expr, walk_pat, Visitor};
+use super::{DataDef, Expr, ExprNode, Literal, Name, Pat, Program};
+use crate::types::{Type, TypeId};
+use crate::Diagnostic;
+
+/// Check that a program is well-formed, such that a backend can consume it
+/// without checking it again, and report every problem found.
+///
+/// Every type id must be defined without referring to itself, every name must
+/// be bound by a pattern or have an entry in the context, every callee must
+/// have a function type, every literal must have the type it is annotated
+/// with, and only names with type parameters may be instantiated.
+pub fn validate(program: &Program) -> Vec<Diagnostic> {
+    let mut validator = Validator {
+        program,
+        types: HashMap::new(),
+        locals: Vec::new(),
+        within: None,
+        diagnostics: Vec::new(),
+    };
+
+    for (id, _) in program.types.iter() {
+        validator.check_type(id);
+    }
+
+    for (name, template) in program.context.iter() {
+        validator.within = Some(name.clone());
+        validator.type_id(template.uninst);
+    }
+
+    for (name, DataDef { ctors, .. }) in program.decls.types.iter() {
+        validator.within = Some(name.clone());
+        for ctor in ctors.iter() {
+            if !program.context.contains_key(ctor) {
+                validator.report(format!(
+                    "the constructor `{ctor}` has no type in the context"
+                ));
+            }
+        }
+    }
+
+    for (name, body) in program.decls.values.iter() {
+        validator.within = Some(name.clone());
+        if !program.context.contains_key(name) {
+            validator.report(format!("`{name}` has no type in the context"));
+        }
+
+        validator.visit_expr(body);
+    }
+
+    validator.diagnostics
+}
+
+/// Whether a type, along with every type it refers to, is defined.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Status {
+    /// The type is being checked, so is reached again only through a cycle.
+    Checking,
+    Defined,
+    Undefined,
+}
+
+struct Validator<'a> {
+    program: &'a Program,
+    /// The types which have been checked so far.
+    types: HashMap<TypeId, Status>,
+    /// The names bound by patterns around the current expression, innermost
+    /// last.
+    locals: Vec<Name>,
+    /// The declaration being validated, if any.
+    within: Option<Name>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Validator<'a> {
+    fn report(&mut self, message: String) {
+        let diagnostic = Diagnostic::new(message).within(self.within.clone());
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Check the type of the given id and everything it refers to, reporting
+    /// each problem once. Every type in the table is checked before any
+    /// declaration, so these problems are not reported within one.
+    fn check_type(&mut self, id: TypeId) -> Status {
+        if let Some(status) = self.types.get(&id) {
+            return *status;
+        }
+
+        let Some(ty) = self.program.types.try_get(&id) else {
+            self.types.insert(id, Status::Undefined);
+            return Status::Undefined;
+        };
+
+        self.types.insert(id, Status::Checking);
+        let parts: Vec<TypeId> = match ty {
+            Type::Arrow(from, into) => vec![*from, *into],
+            Type::Named(_, args) => args.clone(),
+            Type::Forall(_, ty) => vec![*ty],
+            Type::Bottom
+            | Type::Bool
+            | Type::Regex
+            | Type::Range(..)
+            | Type::String(_)
+            | Type::Error => Vec::new(),
+        };
+
+        let mut status = Status::Defined;
+        for part in parts {
+            match self.check_type(part) {
+                Status::Defined => {}
+                Status::Checking => {
+                    self.report(format!("the type `{}` refers to itself", display_id(id)));
+                    status = Status::Undefined;
+                }
+                Status::Undefined if !self.program.types.contains(&part) => {
+                    self.report(format!(
+                        "the type `{}` refers to `{}`, which is not defined",
+                        display_id(id),
+                        display_id(part)
+                    ));
+                    status = Status::Undefined;
+                }
+                Status::Undefined => status = Status::Undefined,
+            }
+        }
+
+        self.types.insert(id, status);
+        status
+    }
+
+    /// Check a type id used by a declaration, returning its type if it is
+    /// well-formed.
+    fn type_id(&mut self, id: TypeId) -> Option<&'a Type> {
+        if !self.program.types.contains(&id) {
+            self.report(format!("the type `{}` is not defined", display_id(id)));
+            return None;
+        }
+
+        match self.check_type(id) {
+            Status::Defined => self.program.types.try_get(&id),
+            _ => None,
+        }
+    }
+
+    /// The type of an id which has been checked, if it is well-formed.
+    fn checked(&self, id: TypeId) -> Option<&'a Type> {
+        match self.types.get(&id) {
+            Some(Status::Defined) => self.program.types.try_get(&id),
+            _ => None,
+        }
+    }
+
+    /// Whether a value of the given type can be called, which a polymorphic
+    /// function can be once it is instantiated.
+    fn is_function(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Arrow(..) => true,
+            Type::Forall(_, ty) => self.checked(*ty).is_some_and(|ty| self.is_function(ty)),
+            _ => false,
+        }
+    }
+
+    fn show(&self, id: TypeId) -> String {
+        self.program.types.display(id).to_string()
+    }
+
+    fn name(&mut self, name: &Name) {
+        if !self.locals.contains(name) && !self.program.context.contains_key(name) {
+            self.report(format!("`{name}` is not bound"));
+        }
+    }
+
+    /// Visit an expression with the names bound by a pattern in scope.
+    fn scoped(&mut self, pat: &Pat, expr: &Expr) {
+        let len = self.locals.len();
+        self.visit_pat(pat);
+        self.visit_expr(expr);
+        self.locals.truncate(len);
+    }
+}
+
+impl Visitor for Validator<'_> {
+    fn visit_type(&mut self, id: TypeId) {
+        self.type_id(id);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.node {
+            ExprNode::Fun(pat, body) => {
+                self.visit_type(expr.anno);
+                self.scoped(pat, body);
+            }
+
+            ExprNode::Let {
+                pat,
+                bound,
+                then,
+                elze,
+            } => {
+                self.visit_type(expr.anno);
+                self.visit_expr(bound);
+                self.scoped(pat, then);
+                self.visit_expr(elze);
+            }
+
+            ExprNode::Call(func, _) => {
+                walk_expr(self, expr);
+                if let Some(callee) = self.checked(func.anno) {
+                    if !self.is_function(callee) {
+                        self.report(format!(
+                            "a callee has the type `{}`, which is not a function type",
+                            self.show(func.anno)
+                        ));
+                    }
+                }
+            }
+
+            ExprNode::Lit(lit) => {
+                walk_expr(self, expr);
+                if let Some(ty) = self.checked(expr.anno) {
+                    if !has_type(lit, ty) {
+                        self.report(format!(
+                            "the literal `{}` does not have its annotated type `{}`",
+                            display_literal(lit),
+                            self.show(expr.anno)
+                        ));
+                    }
+                }
+            }
+
+            ExprNode::Name(name) => {
+                walk_expr(self, expr);
+                self.name(name);
+            }
+
+            ExprNode::Instantiated(name, args) => {
+                walk_expr(self, expr);
+                if self.locals.contains(name) {
+                    self.report(format!(
+                        "`{name}` is instantiated but is bound by a pattern"
+                    ));
+                    return;
+                }
+
+                match self.program.context.get(name) {
+                    None => self.report(format!("`{name}` is not bound")),
+                    Some(template) if template.params.is_empty() => self.report(format!(
+                        "`{name}` is instantiated but has no type parameters"
+                    )),
+                    Some(template) if template.params.len() != args.len() => self.report(format!(
+                        "`{name}` takes {} type arguments, but {} were given",
+                        template.params.len(),
+                        args.len()
+                    )),
+                    Some(_) => {}
+                }
+            }
+
+            _ => walk_expr(self, expr),
+        }
+    }
+
+    /// Check a pattern, binding the names it binds.
+    fn visit_pat(&mut self, pat: &Pat) {
+        match pat {
+            Pat::Constructor(name) => {
+                if !self.program.context.contains_key(name) {
+                    self.report(format!("the constructor `{name}` is not defined"));
+                }
+            }
+
+            Pat::Bind(name) => self.locals.push(name.clone()),
+            pat => walk_pat(self, pat),
+        }
+    }
+}
+
+/// Whether a literal is a value of the given type. Integer ranges exclude
+/// their upper bound.
+fn has_type(lit: &Literal, ty: &Type) -> bool {
+    match (lit, ty) {
+        (Literal::Boolean(_), Type::Bool) => true,
+        (Literal::Integer(v), Type::Range(lo, hi)) => lo <= v && v < hi,
+        (Literal::String(v), Type::String(pat)) => pat.is_match(v),
+        (Literal::Regex(_), Type::Regex) => true,
+        _ => false,
+    }
+}
+
+fn display_id(id: TypeId) -> String {
+    format!("#{}", id.0)
+}
+
+fn display_literal(lit: &Literal) -> String {
+    match lit {
+        Literal::Boolean(v) => v.to_string(),
+        Literal::Integer(v) => v.to_string(),
+        Literal::String(v) => format!("{v:?}"),
+        Literal::Regex(pat) => format!("/{}/", pat.as_str()),
+    }
+}
@@ -0,0 +1,229 @@
+//! Traversals of typed expressions and patterns, like those of
+//! [`hir::visit`](crate::hir::visit). Types are visited by id, and their
+//! structure is left to passes which have the [`Types`](crate::types::Types)
+//! they refer to.
+
+use super::{Expr, ExprNode, Literal, Pat};
+use crate::types::TypeId;
+
+/// A traversal which reads each node.
+pub trait Visitor {
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_pat(&mut self, pat: &Pat) {
+        walk_pat(self, pat);
+    }
+
+    fn visit_type(&mut self, _: TypeId) {}
+
+    fn visit_literal(&mut self, _: &Literal) {}
+}
+
+/// A traversal which may change each node in place.
+pub trait VisitorMut {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        walk_expr_mut(self, expr);
+    }
+
+    fn visit_pat_mut(&mut self, pat: &mut Pat) {
+        walk_pat_mut(self, pat);
+    }
+
+    fn visit_type_mut(&mut self, _: &mut TypeId) {}
+
+    fn visit_literal_mut(&mut self, _: &mut Literal) {}
+}
+
+/// A traversal which rebuilds each node from its folded children.
+pub trait Folder {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        walk_expr_fold(self, expr)
+    }
+
+    fn fold_pat(&mut self, pat: Pat) -> Pat {
+        walk_pat_fold(self, pat)
+    }
+
+    fn fold_type(&mut self, ty: TypeId) -> TypeId {
+        ty
+    }
+
+    fn fold_literal(&mut self, lit: Literal) -> Literal {
+        lit
+    }
+}
+
+/// Visit the type of an expression, and then its children.
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    visitor.visit_type(expr.anno);
+    match &expr.node {
+        ExprNode::Fun(pat, body) => {
+            visitor.visit_pat(pat);
+            visitor.visit_expr(body);
+        }
+
+        ExprNode::Let {
+            pat,
+            bound,
+            then,
+            elze,
+        } => {
+            visitor.visit_pat(pat);
+            visitor.visit_expr(bound);
+            visitor.visit_expr(then);
+            visitor.visit_expr(elze);
+        }
+
+        ExprNode::Tuple(exprs) => {
+            for expr in exprs.iter() {
+                visitor.visit_expr(expr);
+            }
+        }
+
+        ExprNode::Call(lhs, rhs) | ExprNode::Binary(_, lhs, rhs) => {
+            visitor.visit_expr(lhs);
+            visitor.visit_expr(rhs);
+        }
+
+        ExprNode::Lit(lit) => visitor.visit_literal(lit),
+
+        ExprNode::Instantiated(_, args) => {
+            for arg in args.iter() {
+                visitor.visit_type(*arg);
+            }
+        }
+
+        ExprNode::Name(_) | ExprNode::Impossible | ExprNode::Invalid => {}
+    }
+}
+
+pub fn walk_pat<V: Visitor + ?Sized>(visitor: &mut V, pat: &Pat) {
+    match pat {
+        Pat::Apply(ctor, arg) => {
+            visitor.visit_pat(ctor);
+            visitor.visit_pat(arg);
+        }
+
+        Pat::Lit(lit) => visitor.visit_literal(lit),
+        Pat::Constructor(_) | Pat::Bind(_) | Pat::Wildcard | Pat::Invalid => {}
+    }
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut Expr) {
+    visitor.visit_type_mut(&mut expr.anno);
+    match &mut expr.node {
+        ExprNode::Fun(pat, body) => {
+            visitor.visit_pat_mut(pat);
+            visitor.visit_expr_mut(body);
+        }
+
+        ExprNode::Let {
+            pat,
+            bound,
+            then,
+            elze,
+        } => {
+            visitor.visit_pat_mut(pat);
+            visitor.visit_expr_mut(bound);
+            visitor.visit_expr_mut(then);
+            visitor.visit_expr_mut(elze);
+        }
+
+        ExprNode::Tuple(exprs) => {
+            for expr in exprs.iter_mut() {
+                visitor.visit_expr_mut(expr);
+            }
+        }
+
+        ExprNode::Call(lhs, rhs) | ExprNode::Binary(_, lhs, rhs) => {
+            visitor.visit_expr_mut(lhs);
+            visitor.visit_expr_mut(rhs);
+        }
+
+        ExprNode::Lit(lit) => visitor.visit_literal_mut(lit),
+
+        ExprNode::Instantiated(_, args) => {
+            for arg in args.iter_mut() {
+                visitor.visit_type_mut(arg);
+            }
+        }
+
+        ExprNode::Name(_) | ExprNode::Impossible | ExprNode::Invalid => {}
+    }
+}
+
+pub fn walk_pat_mut<V: VisitorMut + ?Sized>(visitor: &mut V, pat: &mut Pat) {
+    match pat {
+        Pat::Apply(ctor, arg) => {
+            visitor.visit_pat_mut(ctor);
+            visitor.visit_pat_mut(arg);
+        }
+
+        Pat::Lit(lit) => visitor.visit_literal_mut(lit),
+        Pat::Constructor(_) | Pat::Bind(_) | Pat::Wildcard | Pat::Invalid => {}
+    }
+}
+
+pub fn walk_expr_fold<F: Folder + ?Sized>(folder: &mut F, expr: Expr) -> Expr {
+    let anno = folder.fold_type(expr.anno);
+    let node = match expr.node {
+        ExprNode::Fun(pat, body) => {
+            let pat = folder.fold_pat(pat);
+            ExprNode::Fun(pat, Box::new(folder.fold_expr(*body)))
+        }
+
+        ExprNode::Let {
+            pat,
+            bound,
+            then,
+            elze,
+        } => ExprNode::Let {
+            pat: folder.fold_pat(pat),
+            bound: Box::new(folder.fold_expr(*bound)),
+            then: Box::new(folder.fold_expr(*then)),
+            elze: Box::new(folder.fold_expr(*elze)),
+        },
+
+        ExprNode::Tuple(exprs) => ExprNode::Tuple(
+            exprs
+                .into_iter()
+                .map(|expr| folder.fold_expr(expr))
+                .collect(),
+        ),
+
+        ExprNode::Call(func, arg) => {
+            let func = Box::new(folder.fold_expr(*func));
+            ExprNode::Call(func, Box::new(folder.fold_expr(*arg)))
+        }
+
+        ExprNode::Binary(op, lhs, rhs) => {
+            let lhs = Box::new(folder.fold_expr(*lhs));
+            ExprNode::Binary(op, lhs, Box::new(folder.fold_expr(*rhs)))
+        }
+
+        ExprNode::Lit(lit) => ExprNode::Lit(folder.fold_literal(lit)),
+
+        ExprNode::Instantiated(name, args) => {
+            let args = args.into_iter().map(|arg| folder.fold_type(arg)).collect();
+            ExprNode::Instantiated(name, args)
+        }
+
+        node @ (ExprNode::Name(_) | ExprNode::Impossible | ExprNode::Invalid) => node,
+    };
+
+    Expr { node, anno }
+}
+
+pub fn walk_pat_fold<F: Folder + ?Sized>(folder: &mut F, pat: Pat) -> Pat {
+    match pat {
+        Pat::Apply(ctor, arg) => {
+            let ctor = Box::new(folder.fold_pat(*ctor));
+            Pat::Apply(ctor, Box::new(folder.fold_pat(*arg)))
+        }
+
+        Pat::Lit(lit) => Pat::Lit(folder.fold_literal(lit)),
+        pat @ (Pat::Constructor(_) | Pat::Bind(_) | Pat::Wildcard | Pat::Invalid) => pat,
+    }
+}
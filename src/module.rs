@@ -0,0 +1,433 @@
+//! Programs split across files. Every file is a module, named by its path
+//! relative to the directory of the root module, with `.` in place of `/` and
+//! without the extension, so `import data.list` refers to `data/list.il`.
+//!
+//! The modules of a program are linked into one set of declarations before
+//! checking, in which every name declared outside the root module is
+//! qualified by the name of its module.
+//!
+//! The linked declarations are checked in one pass rather than one module at
+//! a time. Checking still follows dependencies across modules, because
+//! definitions are ordered by their strongly connected components whatever
+//! module they come from, so a module is never checked before one it imports.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexSet;
+
+use crate::hir::visit::{walk_expr_mut, walk_pat_mut, walk_type_mut, VisitorMut};
+use crate::hir::{self, Decls, Expr, Pat, Type, TypeDef};
+use crate::mir::Name;
+use crate::{syntax, Diagnostic};
+
+/// The extension of source files.
+pub const EXTENSION: &str = "il";
+
+#[derive(Clone, Debug)]
+pub struct Module {
+    /// The name of the module, which is empty for the root module.
+    pub name: Name,
+    pub path: PathBuf,
+    pub decls: Decls,
+}
+
+/// Load the module in the given file along with every module it imports,
+/// directly or not. Each module comes after every module it imports, so the
+/// root module is last.
+pub fn load(path: &Path) -> Result<Vec<Module>, Vec<Diagnostic>> {
+    let mut loader = Loader {
+        root: path.parent().unwrap_or(Path::new("")).to_path_buf(),
+        modules: Vec::new(),
+        started: HashSet::new(),
+        stack: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+
+    loader.load(Name::new(), path.to_path_buf(), None);
+    match loader.diagnostics.is_empty() {
+        true => Ok(loader.modules),
+        false => Err(loader.diagnostics),
+    }
+}
+
+/// Link modules, in the order [`load`] returns them, into the declarations of
+/// one program. Every qualified name must refer to a public declaration of a
+/// module imported where it is used.
+pub fn link(modules: Vec<Module>) -> (Decls, Vec<Diagnostic>) {
+    let scopes: HashMap<Name, Scope> = modules
+        .iter()
+        .map(|module| (module.name.clone(), Scope::of(&module.decls)))
+        .collect();
+
+    let mut linked = Decls {
+        imports: Vec::new(),
+        exports: IndexSet::new(),
+        types: Default::default(),
+        classes: Default::default(),
+        instances: Vec::new(),
+        values: Default::default(),
+    };
+
+    let mut diagnostics = Vec::new();
+    for module in modules {
+        let mut linker = Linker {
+            module: &module.name,
+            scopes: &scopes,
+            imports: HashMap::new(),
+            bound: Vec::new(),
+            within: None,
+            diagnostics: &mut diagnostics,
+        };
+
+        linker.imports(&module.decls.imports);
+        linker.decls(module.decls, &mut linked);
+    }
+
+    (linked, diagnostics)
+}
+
+/// The name a declaration of the given module has once linked.
+fn qualify(module: &str, name: &str) -> Name {
+    match module.is_empty() {
+        true => name.to_string(),
+        false => format!("{module}.{name}"),
+    }
+}
+
+struct Loader {
+    /// The directory module names are relative to.
+    root: PathBuf,
+    /// The modules loaded so far, in dependency order.
+    modules: Vec<Module>,
+    /// The names of the modules which have been loaded or are being loaded.
+    started: HashSet<Name>,
+    /// The modules being loaded, each imported by the one before it.
+    stack: Vec<Name>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Loader {
+    /// Load a module after every module it imports, if it can be read and
+    /// parsed.
+    fn load(&mut self, name: Name, path: PathBuf, importer: Option<&Path>) {
+        self.started.insert(name.clone());
+        let src = match std::fs::read_to_string(&path) {
+            Ok(src) => src,
+            Err(err) => {
+                let diagnostic = match importer {
+                    None => Diagnostic::new(format!("cannot read `{}`: {err}", path.display())),
+                    Some(importer) => Diagnostic::new(format!("cannot find the module `{name}`"))
+                        .with_note(format!("looked for `{}`: {err}", path.display()))
+                        .with_note(format!("imported in `{}`", importer.display())),
+                };
+
+                self.diagnostics.push(diagnostic);
+                return;
+            }
+        };
+
+        let decls = match syntax::parse(&src) {
+            Ok(decls) => decls,
+            Err(diagnostic) => {
+                let diagnostic = diagnostic.with_note(format!("in `{}`", path.display()));
+                self.diagnostics.push(diagnostic);
+                return;
+            }
+        };
+
+        self.stack.push(name.clone());
+        for import in decls.imports.iter() {
+            if let Some(start) = self.stack.iter().position(|name| *name == import.module) {
+                let cycle: Vec<_> = self.stack[start..]
+                    .iter()
+                    .chain([&import.module])
+                    .map(String::as_str)
+                    .collect();
+                self.diagnostics.push(
+                    Diagnostic::new(format!(
+                        "modules import each other in a cycle: {}",
+                        cycle.join(" -> ")
+                    ))
+                    .with_note(format!("in `{}`", path.display())),
+                );
+            } else if !self.started.contains(&import.module) {
+                let file = self
+                    .root
+                    .join(import.module.replace('.', "/"))
+                    .with_extension(EXTENSION);
+                self.load(import.module.clone(), file, Some(&path));
+            }
+        }
+
+        self.stack.pop();
+        self.modules.push(Module { name, path, decls });
+    }
+}
+
+/// The top-level names declared by a module, each with whether it is public.
+struct Scope {
+    /// Values, constructors and methods.
+    values: HashMap<Name, bool>,
+    /// Types and classes.
+    types: HashMap<Name, bool>,
+}
+
+impl Scope {
+    fn of(decls: &Decls) -> Self {
+        let mut scope = Scope {
+            values: HashMap::new(),
+            types: HashMap::new(),
+        };
+
+        for (name, def) in decls.types.iter() {
+            let public = decls.exports.contains(name);
+            scope.types.insert(name.clone(), public);
+            match def {
+                TypeDef::Data(def) => {
+                    for ctor in def.ctors.iter() {
+                        scope.values.insert(ctor.name.clone(), public);
+                    }
+                }
+
+                TypeDef::Newtype(_) => {
+                    scope.values.insert(name.clone(), public);
+                }
+
+                TypeDef::Alias(_) => {}
+            }
+        }
+
+        for (name, def) in decls.classes.iter() {
+            let public = decls.exports.contains(name);
+            scope.types.insert(name.clone(), public);
+            for (method, _) in def.methods.iter() {
+                scope.values.insert(method.clone(), public);
+            }
+        }
+
+        for name in decls.values.keys() {
+            scope
+                .values
+                .insert(name.clone(), decls.exports.contains(name));
+        }
+
+        scope
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Namespace {
+    Value,
+    Type,
+}
+
+/// Renames the names used by one module to the names of the declarations they
+/// refer to once linked.
+struct Linker<'a> {
+    module: &'a Name,
+    scopes: &'a HashMap<Name, Scope>,
+    /// The module each alias in scope refers to.
+    imports: HashMap<Name, Name>,
+    /// The names bound by patterns around the current expression.
+    bound: Vec<Name>,
+    /// The declaration being linked, if any.
+    within: Option<Name>,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl Linker<'_> {
+    fn report(&mut self, message: String) {
+        let diagnostic = Diagnostic::new(message).within(self.within.clone());
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn imports(&mut self, imports: &[hir::Import]) {
+        for import in imports {
+            let alias = import.alias.as_ref().unwrap_or(&import.module);
+            if let Some(module) = self.imports.insert(alias.clone(), import.module.clone()) {
+                if module != import.module {
+                    self.report(format!("`{alias}` refers to more than one imported module"));
+                }
+            }
+        }
+    }
+
+    fn decls(&mut self, decls: Decls, linked: &mut Decls) {
+        let module = self.module;
+        let own = |name: &Name| qualify(module, name);
+        for name in decls.exports.iter() {
+            linked.exports.insert(own(name));
+        }
+
+        for (name, mut def) in decls.types {
+            let name = own(&name);
+            self.within = Some(name.clone());
+            match &mut def {
+                TypeDef::Alias(def) => self.visit_type_mut(&mut def.body),
+                TypeDef::Newtype(def) => self.visit_type_mut(&mut def.repr),
+                TypeDef::Data(def) => {
+                    for ctor in def.ctors.iter_mut() {
+                        ctor.name = own(&ctor.name);
+                        for field in ctor.fields.iter_mut() {
+                            self.visit_type_mut(field);
+                        }
+                    }
+                }
+            }
+
+            linked.types.insert(name, def);
+        }
+
+        for (name, mut def) in decls.classes {
+            let name = own(&name);
+            self.within = Some(name.clone());
+            for (method, ty) in def.methods.iter_mut() {
+                *method = own(method);
+                self.visit_type_mut(ty);
+            }
+
+            linked.classes.insert(name, def);
+        }
+
+        for mut def in decls.instances {
+            self.within = None;
+            self.params(&mut def.vars);
+            let class = self.name(&def.class, Namespace::Type);
+            def.class = class.unwrap_or(def.class);
+            self.visit_type_mut(&mut def.ty);
+
+            // Methods are named as they are declared by the class.
+            let class_module = def.class.rsplit_once('.').map_or("", |(module, _)| module);
+            for (method, body) in def.methods.iter_mut() {
+                *method = qualify(class_module, method);
+                self.visit_expr_mut(body);
+            }
+
+            linked.instances.push(def);
+        }
+
+        for (name, mut def) in decls.values {
+            let name = own(&name);
+            self.within = Some(name.clone());
+            self.params(&mut def.vars);
+            self.visit_type_mut(&mut def.anno);
+            self.visit_expr_mut(&mut def.body);
+            linked.values.insert(name, def);
+        }
+    }
+
+    fn params(&mut self, params: &mut [hir::TypeParam]) {
+        for param in params.iter_mut() {
+            for class in param.bounds.iter_mut() {
+                if let Some(name) = self.name(class, Namespace::Type) {
+                    *class = name;
+                }
+            }
+        }
+    }
+
+    /// The linked name of a global name used in this module, or `None` if it
+    /// is a qualified name which does not refer to a public declaration.
+    /// Unqualified names which this module does not declare are left as they
+    /// are.
+    fn name(&mut self, name: &Name, namespace: Namespace) -> Option<Name> {
+        let Some((alias, base)) = name.rsplit_once('.') else {
+            let scope = &self.scopes[self.module];
+            let declared = match namespace {
+                Namespace::Value => scope.values.contains_key(name),
+                Namespace::Type => scope.types.contains_key(name),
+            };
+
+            return Some(match declared {
+                true => qualify(self.module, name),
+                false => name.clone(),
+            });
+        };
+
+        let Some(module) = self.imports.get(alias) else {
+            self.report(format!("`{alias}` is not an imported module"));
+            return None;
+        };
+
+        let scope = &self.scopes[module];
+        let public = match namespace {
+            Namespace::Value => scope.values.get(base),
+            Namespace::Type => scope.types.get(base),
+        };
+
+        match public {
+            Some(true) => Some(qualify(module, base)),
+            Some(false) => {
+                let module = module.clone();
+                self.report(format!("`{base}` is private to the module `{module}`"));
+                None
+            }
+            None => {
+                let module = module.clone();
+                self.report(format!("the module `{module}` does not declare `{base}`"));
+                None
+            }
+        }
+    }
+
+    /// Link an expression within the scope of the names bound by a pattern.
+    fn scoped(&mut self, pat: &mut Pat, expr: &mut Expr) {
+        let len = self.bound.len();
+        self.visit_pat_mut(pat);
+        self.visit_expr_mut(expr);
+        self.bound.truncate(len);
+    }
+}
+
+impl VisitorMut for Linker<'_> {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Fun(pat, body) => self.scoped(pat, body),
+
+            Expr::Let {
+                pat,
+                bound,
+                then,
+                elze,
+            } => {
+                self.visit_expr_mut(bound);
+                self.scoped(pat, then);
+                self.visit_expr_mut(elze);
+            }
+
+            Expr::Name(name) | Expr::Instantiate(name, _) if !self.bound.contains(name) => {
+                match self.name(name, Namespace::Value) {
+                    Some(linked) => {
+                        *name = linked;
+                        walk_expr_mut(self, expr);
+                    }
+                    None => *expr = Expr::Invalid,
+                }
+            }
+
+            expr => walk_expr_mut(self, expr),
+        }
+    }
+
+    fn visit_pat_mut(&mut self, pat: &mut Pat) {
+        match pat {
+            Pat::Bind(name) => self.bound.push(name.clone()),
+            Pat::Constructor(name) => match self.name(name, Namespace::Value) {
+                Some(linked) => *name = linked,
+                None => *pat = Pat::Wildcard,
+            },
+            pat => walk_pat_mut(self, pat),
+        }
+    }
+
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        match ty {
+            Type::Named(name) => match self.name(name, Namespace::Type) {
+                Some(linked) => *name = linked,
+                None => *ty = Type::Invalid,
+            },
+            ty => walk_type_mut(self, ty),
+        }
+    }
+}
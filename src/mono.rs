@@ -0,0 +1,452 @@
+use std::collections::HashMap;
+
+use bimap::BiMap;
+use indexmap::IndexMap;
+use log::{debug, trace};
+
+use crate::mir::{DataDef, Decls, Expr, ExprNode, Name, Pat, Program, Template};
+use crate::types::{Type, TypeId};
+use crate::Diagnostic;
+
+/// Monomorphise the given program, starting from the given roots. The result
+/// contains a specialised copy of every value and data type reachable from the
+/// roots for every distinct set of type arguments it is used at, such that no
+/// definition has type parameters and no expression is
+/// [`ExprNode::Instantiated`].
+///
+/// Specialisations of a polymorphic definition `f` are named `f#0`, `f#1` and
+/// so on, while monomorphic definitions keep their names. The constructors of a
+/// specialised data type share its suffix. Roots must be monomorphic, and
+/// polymorphic roots are skipped since they have no single specialisation.
+///
+/// Builtins, which have no body, are specialised in the same way, as
+/// declarations without one. A constructor root specialises its data type.
+///
+/// Higher-rank types are left as they are, so a value used at a type bound by a
+/// `forall` is specialised to that (still abstract) type.
+///
+/// Fails if a constructor pattern does not belong to the data type of the
+/// value it matches, which the checker never produces.
+pub fn monomorphise(program: &Program, roots: &[Name]) -> Result<Program, Vec<Diagnostic>> {
+    let mut mono = Monomorphiser::new(program);
+
+    for root in roots {
+        match program.context.get(root) {
+            Some(template) if template.params.is_empty() => {
+                mono.lower_name(root, Vec::new());
+            }
+
+            _ => debug!("Skipping root `{root}` which is polymorphic or undefined"),
+        }
+    }
+
+    while let Some((name, spec, args)) = mono.worklist.pop() {
+        trace!("Specialising `{name}` as `{spec}`");
+        let template = &program.context[&name];
+        let subst = template.params.iter().cloned().zip(args).collect();
+        let uninst = mono.lower_type(template.uninst, &subst);
+        let body = mono.lower_expr(&program.decls.values[&name], &subst);
+
+        mono.context.insert(
+            spec.clone(),
+            Template {
+                params: Vec::new(),
+                uninst,
+            },
+        );
+
+        mono.values.insert(spec, body);
+    }
+
+    if !mono.diagnostics.is_empty() {
+        return Err(mono.diagnostics);
+    }
+
+    Ok(Program {
+        context: mono.context,
+        decls: Decls {
+            types: mono.datatypes,
+            values: mono.values,
+        },
+        types: mono.types.into_iter().collect(),
+    })
+}
+
+struct Monomorphiser<'a> {
+    program: &'a Program,
+    /// The data type each constructor belongs to.
+    ctors: HashMap<Name, Name>,
+    /// The data type each specialised data type was made from.
+    origins: HashMap<Name, Name>,
+    /// The names bound by patterns around the expression being lowered, which
+    /// shadow top-level names.
+    locals: Vec<Name>,
+
+    /// The name of every requested specialisation of a value or data type,
+    /// keyed by its name and (specialised) type arguments.
+    specs: HashMap<(Name, Vec<TypeId>), Name>,
+    /// The number of specialisations made of every polymorphic definition.
+    counts: HashMap<Name, usize>,
+    /// The values whose specialisations have been named but not yet made,
+    /// as their original name, specialised name and type arguments.
+    worklist: Vec<(Name, Name, Vec<TypeId>)>,
+
+    context: IndexMap<Name, Template>,
+    datatypes: IndexMap<Name, DataDef>,
+    values: IndexMap<Name, Expr>,
+    types: BiMap<TypeId, Type>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Monomorphiser<'a> {
+    fn new(program: &'a Program) -> Self {
+        let ctors = program
+            .decls
+            .types
+            .iter()
+            .flat_map(|(name, def)| def.ctors.iter().map(|ctor| (ctor.clone(), name.clone())))
+            .collect();
+
+        Self {
+            program,
+            ctors,
+            origins: HashMap::new(),
+            locals: Vec::new(),
+
+            specs: HashMap::new(),
+            counts: HashMap::new(),
+            worklist: Vec::new(),
+
+            context: IndexMap::new(),
+            datatypes: IndexMap::new(),
+            values: IndexMap::new(),
+            types: BiMap::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// The name of the specialisation of the given definition at the given
+    /// type arguments, and whether it was requested for the first time.
+    fn spec_name(&mut self, name: &Name, args: &[TypeId]) -> (Name, bool) {
+        let key = (name.clone(), args.to_vec());
+        if let Some(spec) = self.specs.get(&key) {
+            return (spec.clone(), false);
+        }
+
+        let spec = if args.is_empty() {
+            name.clone()
+        } else {
+            let count = self.counts.entry(name.clone()).or_default();
+            let spec = format!("{name}#{count}");
+            *count += 1;
+            spec
+        };
+
+        self.specs.insert(key, spec.clone());
+        (spec, true)
+    }
+
+    /// Get the name of the specialisation of the value `name` at the given
+    /// (specialised) type arguments, scheduling it to be made if needed.
+    fn value(&mut self, name: &Name, args: Vec<TypeId>) -> Name {
+        let (spec, fresh) = self.spec_name(name, &args);
+        if fresh {
+            self.worklist.push((name.clone(), spec.clone(), args));
+        }
+
+        spec
+    }
+
+    /// Get the name of the specialisation of the data type `name` at the given
+    /// (specialised) type arguments, making it and its constructors if needed.
+    fn datatype(&mut self, name: &Name, args: Vec<TypeId>) -> Name {
+        let (spec, fresh) = self.spec_name(name, &args);
+        if !fresh {
+            return spec;
+        }
+
+        trace!("Specialising data type `{name}` as `{spec}`");
+        self.origins.insert(spec.clone(), name.clone());
+
+        let def = &self.program.decls.types[name];
+        let suffix = &spec[name.len()..];
+        let subst = def.params.iter().cloned().zip(args).collect();
+
+        let mut ctors = Vec::with_capacity(def.ctors.len());
+        for ctor in def.ctors.iter() {
+            let ctor_spec = format!("{ctor}{suffix}");
+            let uninst = self.program.context[ctor].uninst;
+            let uninst = self.lower_type(uninst, &subst);
+
+            self.context.insert(
+                ctor_spec.clone(),
+                Template {
+                    params: Vec::new(),
+                    uninst,
+                },
+            );
+
+            ctors.push(ctor_spec);
+        }
+
+        self.datatypes.insert(
+            spec.clone(),
+            DataDef {
+                params: Vec::new(),
+                ctors,
+            },
+        );
+
+        spec
+    }
+
+    /// Get the name of the constructor `ctor` of the specialised data type
+    /// `data`, reporting an error if it does not belong to that type.
+    fn ctor(&mut self, ctor: &Name, data: &Name) -> Option<Name> {
+        let generic = &self.ctors[ctor];
+        if self.origins.get(data) != Some(generic) {
+            self.diagnostics.push(Diagnostic::new(format!(
+                "the constructor `{ctor}` does not belong to the type `{data}`"
+            )));
+            return None;
+        }
+
+        Some(format!("{ctor}{}", &data[generic.len()..]))
+    }
+
+    /// Lower an expression in the scope of the names bound by a pattern.
+    fn lower_scoped(&mut self, pat: &Pat, expr: &Expr, subst: &HashMap<Name, TypeId>) -> Expr {
+        let len = self.locals.len();
+        binders(pat, &mut self.locals);
+        let expr = self.lower_expr(expr, subst);
+        self.locals.truncate(len);
+        expr
+    }
+
+    fn lower_expr(&mut self, expr: &Expr, subst: &HashMap<Name, TypeId>) -> Expr {
+        let anno = self.lower_type(expr.anno, subst);
+        let node = match &expr.node {
+            ExprNode::Fun(pat, body) => {
+                let pat = match self.types.get_by_left(&anno) {
+                    Some(Type::Arrow(from, _)) => {
+                        let from = *from;
+                        self.lower_pat(pat, from)
+                    }
+                    _ => pat.clone(),
+                };
+
+                let body = Box::new(self.lower_scoped(&pat, body, subst));
+                ExprNode::Fun(pat, body)
+            }
+
+            ExprNode::Let {
+                pat,
+                bound,
+                then,
+                elze,
+            } => {
+                let bound = Box::new(self.lower_expr(bound, subst));
+                let pat = self.lower_pat(pat, bound.anno);
+                let then = Box::new(self.lower_scoped(&pat, then, subst));
+                let elze = Box::new(self.lower_expr(elze, subst));
+
+                ExprNode::Let {
+                    pat,
+                    bound,
+                    then,
+                    elze,
+                }
+            }
+
+            ExprNode::Tuple(exprs) => ExprNode::Tuple(
+                exprs
+                    .iter()
+                    .map(|expr| self.lower_expr(expr, subst))
+                    .collect(),
+            ),
+
+            ExprNode::Call(func, arg) => {
+                let func = Box::new(self.lower_expr(func, subst));
+                let arg = Box::new(self.lower_expr(arg, subst));
+                ExprNode::Call(func, arg)
+            }
+
+            ExprNode::Binary(op, lhs, rhs) => {
+                let lhs = Box::new(self.lower_expr(lhs, subst));
+                let rhs = Box::new(self.lower_expr(rhs, subst));
+                ExprNode::Binary(*op, lhs, rhs)
+            }
+
+            ExprNode::Lit(lit) => ExprNode::Lit(lit.clone()),
+
+            ExprNode::Name(name) => ExprNode::Name(self.lower_name(name, Vec::new())),
+
+            ExprNode::Instantiated(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.lower_type(*arg, subst))
+                    .collect();
+                ExprNode::Name(self.lower_name(name, args))
+            }
+
+            ExprNode::Impossible => ExprNode::Impossible,
+            ExprNode::Invalid => ExprNode::Invalid,
+        };
+
+        Expr { node, anno }
+    }
+
+    /// Get the name of the specialisation of the builtin `name` at the given
+    /// (specialised) type arguments, declaring it if needed.
+    fn builtin(&mut self, name: &Name, args: Vec<TypeId>) -> Name {
+        let (spec, fresh) = self.spec_name(name, &args);
+        if fresh {
+            trace!("Specialising builtin `{name}` as `{spec}`");
+            let template = &self.program.context[name];
+            let subst = template.params.iter().cloned().zip(args).collect();
+            let uninst = self.lower_type(template.uninst, &subst);
+            self.context.insert(
+                spec.clone(),
+                Template {
+                    params: Vec::new(),
+                    uninst,
+                },
+            );
+        }
+
+        spec
+    }
+
+    /// Get the specialised name of a use of `name` at the given type
+    /// arguments. Local names and names not bound at the top level are left as
+    /// they are.
+    fn lower_name(&mut self, name: &Name, args: Vec<TypeId>) -> Name {
+        if self.locals.contains(name) {
+            name.clone()
+        } else if let Some(data) = self.ctors.get(name) {
+            let data = data.clone();
+            let data = self.datatype(&data, args);
+            self.ctor(name, &data).unwrap_or_else(|| name.clone())
+        } else if self.program.decls.values.contains_key(name) {
+            self.value(name, args)
+        } else if self.program.context.contains_key(name) {
+            self.builtin(name, args)
+        } else {
+            name.clone()
+        }
+    }
+
+    /// Specialise the constructors in a pattern matching values of the given
+    /// (specialised) type.
+    fn lower_pat(&mut self, pat: &Pat, ty: TypeId) -> Pat {
+        match pat {
+            Pat::Constructor(_) | Pat::Apply(..) => {
+                let Some(Type::Named(data, _)) = self.types.get_by_left(&ty) else {
+                    return pat.clone();
+                };
+
+                let data = data.clone();
+                let (pat, _) = self.lower_ctor_pat(pat, &data);
+                pat
+            }
+
+            Pat::Bind(_) | Pat::Lit(_) | Pat::Wildcard | Pat::Invalid => pat.clone(),
+        }
+    }
+
+    /// Specialise a constructor pattern of the specialised data type `data`,
+    /// returning it along with the type of the remaining constructor fields.
+    fn lower_ctor_pat(&mut self, pat: &Pat, data: &Name) -> (Pat, Option<TypeId>) {
+        match pat {
+            Pat::Constructor(ctor) if self.ctors.contains_key(ctor) => {
+                match self.ctor(ctor, data) {
+                    Some(ctor) => {
+                        let ty = self.context.get(&ctor).map(|template| template.uninst);
+                        (Pat::Constructor(ctor), ty)
+                    }
+                    None => (pat.clone(), None),
+                }
+            }
+
+            Pat::Apply(ctor, arg) => {
+                let (ctor, ty) = self.lower_ctor_pat(ctor, data);
+                let (arg, ty) = match ty.and_then(|ty| self.types.get_by_left(&ty)) {
+                    Some(Type::Arrow(from, into)) => {
+                        let (from, into) = (*from, *into);
+                        (self.lower_pat(arg, from), Some(into))
+                    }
+                    _ => ((**arg).clone(), None),
+                };
+
+                (Pat::Apply(Box::new(ctor), Box::new(arg)), ty)
+            }
+
+            _ => (pat.clone(), None),
+        }
+    }
+
+    /// Specialise a type of the original program, replacing the type
+    /// parameters in `subst` and every applied data type with its
+    /// specialisation.
+    fn lower_type(&mut self, id: TypeId, subst: &HashMap<Name, TypeId>) -> TypeId {
+        let ty = match self.program.types.get(&id) {
+            Type::Named(name, args) if args.is_empty() && subst.contains_key(name) => {
+                return subst[name];
+            }
+
+            Type::Named(name, args) => {
+                let args: Vec<_> = args
+                    .iter()
+                    .map(|arg| self.lower_type(*arg, subst))
+                    .collect();
+                if self.program.decls.types.contains_key(name) {
+                    Type::Named(self.datatype(name, args), Vec::new())
+                } else {
+                    Type::Named(name.clone(), args)
+                }
+            }
+
+            Type::Arrow(from, into) => {
+                let from = self.lower_type(*from, subst);
+                let into = self.lower_type(*into, subst);
+                Type::Arrow(from, into)
+            }
+
+            Type::Forall(vars, ty) => {
+                let mut subst = subst.clone();
+                for var in vars.iter() {
+                    subst.remove(var);
+                }
+
+                Type::Forall(vars.clone(), self.lower_type(*ty, &subst))
+            }
+
+            ty @ (Type::Bottom
+            | Type::Bool
+            | Type::Regex
+            | Type::Range(..)
+            | Type::String(_)
+            | Type::Error) => ty.clone(),
+        };
+
+        if let Some(id) = self.types.get_by_right(&ty) {
+            *id
+        } else {
+            let id = TypeId(self.types.len());
+            self.types.insert(id, ty);
+            id
+        }
+    }
+}
+
+/// Collect the names bound by a pattern.
+fn binders(pat: &Pat, names: &mut Vec<Name>) {
+    match pat {
+        Pat::Bind(name) => names.push(name.clone()),
+        Pat::Apply(ctor, arg) => {
+            binders(ctor, names);
+            binders(arg, names);
+        }
+        Pat::Constructor(_) | Pat::Lit(_) | Pat::Wildcard | Pat::Invalid => {}
+    }
+}
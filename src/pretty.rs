@@ -0,0 +1,201 @@
+//! Pretty-printing of programs in concrete syntax. Documents are laid out
+//! within a maximum width, breaking groups onto several lines only where they
+//! do not fit on one.
+
+mod hir;
+mod mir;
+
+pub use self::hir::{decls as hir, expr as hir_expr, ty as hir_type};
+pub use self::mir::{expr as mir_expr, program as mir};
+
+/// The width programs are laid out within by default.
+pub const WIDTH: usize = 80;
+
+/// The number of spaces by which nested lines are indented.
+const INDENT: usize = 2;
+
+#[derive(Clone, Debug)]
+pub enum Doc {
+    Text(String),
+    /// A line break, or the given text where its group fits on one line.
+    Break(&'static str),
+    /// A line break which is never flattened.
+    HardBreak,
+    Concat(Vec<Doc>),
+    /// A document whose line breaks are indented further.
+    Nest(Box<Doc>),
+    /// A document laid out on one line if it fits.
+    Group(Box<Doc>),
+    /// A comment running to the end of the line, either on a line of its own
+    /// or after the text before it. The line always breaks after it, so any
+    /// group containing it is never laid out on one line.
+    Comment {
+        text: String,
+        own_line: bool,
+    },
+}
+
+impl Doc {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text(text.into())
+    }
+
+    /// A line break, or a space on one line.
+    pub fn line() -> Self {
+        Self::Break(" ")
+    }
+
+    /// A line break, or nothing on one line.
+    pub fn softline() -> Self {
+        Self::Break("")
+    }
+
+    pub fn concat(docs: impl IntoIterator<Item = Doc>) -> Self {
+        Self::Concat(docs.into_iter().collect())
+    }
+
+    /// The given documents separated by `sep`.
+    pub fn join(docs: impl IntoIterator<Item = Doc>, sep: Doc) -> Self {
+        let mut joined = Vec::new();
+        for doc in docs {
+            if !joined.is_empty() {
+                joined.push(sep.clone());
+            }
+
+            joined.push(doc);
+        }
+
+        Self::Concat(joined)
+    }
+
+    pub fn nest(self) -> Self {
+        Self::Nest(Box::new(self))
+    }
+
+    pub fn group(self) -> Self {
+        Self::Group(Box::new(self))
+    }
+
+    /// Lay out the document within the given width.
+    pub fn render(&self, width: usize) -> String {
+        let mut out = String::new();
+        let mut column = 0;
+        let mut stack = vec![(0, false, self)];
+
+        // Whether nothing but indentation is on the current line, and whether
+        // the line must break before any more text, after a comment.
+        let mut line_start = true;
+        let mut after_comment = false;
+
+        while let Some((indent, flat, doc)) = stack.pop() {
+            let empty = match doc {
+                Self::Text(text) => text.is_empty(),
+                Self::Concat(_) | Self::Nest(_) | Self::Group(_) => true,
+                _ => false,
+            };
+
+            if after_comment && !empty {
+                after_comment = false;
+                newline(&mut out, indent);
+                column = indent;
+                line_start = true;
+                if matches!(doc, Self::Break(_) | Self::HardBreak) {
+                    continue;
+                }
+            }
+
+            match doc {
+                Self::Text(text) => {
+                    out.push_str(text);
+                    column += text.chars().count();
+                    line_start &= text.is_empty();
+                }
+
+                Self::Break(text) if flat => {
+                    out.push_str(text);
+                    column += text.len();
+                    line_start &= text.is_empty();
+                }
+
+                Self::Break(_) | Self::HardBreak => {
+                    newline(&mut out, indent);
+                    column = indent;
+                    line_start = true;
+                }
+
+                Self::Comment { text, own_line } => {
+                    if *own_line && !line_start {
+                        newline(&mut out, indent);
+                    } else if !own_line && !line_start {
+                        out.push(' ');
+                    }
+
+                    out.push_str(text);
+                    after_comment = true;
+                }
+
+                Self::Concat(docs) => {
+                    stack.extend(docs.iter().rev().map(|doc| (indent, flat, doc)));
+                }
+
+                Self::Nest(doc) => stack.push((indent + INDENT, flat, doc)),
+
+                Self::Group(doc) => {
+                    let flat = flat || fits(doc, &stack, width.saturating_sub(column));
+                    stack.push((indent, flat, doc));
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// End the line, without trailing spaces, and indent the next.
+fn newline(out: &mut String, indent: usize) {
+    while out.ends_with(' ') {
+        out.pop();
+    }
+
+    out.push('\n');
+    out.extend(std::iter::repeat_n(' ', indent));
+}
+
+/// Whether the given document fits in the remaining width when laid out on
+/// one line, along with the rest of the line after it. Since the line breaks
+/// after a comment, a document only fits with a comment at its end.
+fn fits(doc: &Doc, rest: &[(usize, bool, &Doc)], width: usize) -> bool {
+    let mut remaining = width as isize;
+    // Documents to measure, and whether each is within the given one.
+    let mut stack = vec![(true, true, doc)];
+    let mut rest = rest.iter().rev();
+    let mut commented = false;
+
+    while let Some((flat, within, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(text) if commented && !text.is_empty() => return false,
+            Doc::Break(text) if commented && flat && !text.is_empty() => return false,
+            Doc::Text(text) => remaining -= text.chars().count() as isize,
+            Doc::Break(text) if flat => remaining -= text.len() as isize,
+            Doc::Break(_) | Doc::HardBreak => return remaining >= 0,
+            Doc::Comment { own_line, .. } => match within && !own_line {
+                true => commented = true,
+                false => return !within && remaining >= 0,
+            },
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (flat, within, doc))),
+            Doc::Nest(doc) | Doc::Group(doc) => stack.push((flat, within, doc)),
+        }
+
+        if remaining < 0 {
+            return false;
+        }
+
+        if stack.is_empty() && !commented {
+            if let Some((_, flat, doc)) = rest.next() {
+                stack.push((*flat, false, doc));
+            }
+        }
+    }
+
+    remaining >= 0
+}
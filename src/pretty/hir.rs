@@ -0,0 +1,352 @@
+use super::Doc;
+use crate::hir::{BinOp, Decls, Expr, Literal, Pat, Type, TypeDef, TypeParam};
+
+/// Print declarations in the syntax they are parsed from. Declarations are
+/// grouped by kind, since the order of different kinds is not kept.
+pub fn decls(decls: &Decls, width: usize) -> String {
+    let mut docs = Vec::new();
+
+    if !decls.imports.is_empty() {
+        let imports = decls.imports.iter().map(|import| match &import.alias {
+            Some(alias) => Doc::text(format!("import {} as {alias}", import.module)),
+            None => Doc::text(format!("import {}", import.module)),
+        });
+        docs.push(Doc::join(imports, Doc::HardBreak));
+    }
+
+    let public = |name: &str| match decls.exports.contains(name) {
+        true => "pub ",
+        false => "",
+    };
+
+    for (name, def) in decls.types.iter() {
+        let (keyword, vars) = match def {
+            TypeDef::Alias(def) => ("type", &def.vars),
+            TypeDef::Data(def) => ("data", &def.vars),
+            TypeDef::Newtype(def) => ("newtype", &def.vars),
+        };
+
+        let head = Doc::text(format!(
+            "{}{keyword} {}",
+            public(name),
+            with_vars(name, vars)
+        ));
+        let body = match def {
+            TypeDef::Alias(def) => Doc::text(ty(&def.body)),
+            TypeDef::Newtype(def) => Doc::text(ty(&def.repr)),
+            TypeDef::Data(def) => {
+                let ctors = def.ctors.iter().map(|ctor| {
+                    let fields = ctor.fields.iter().map(|field| format!(" {}", aty(field)));
+                    Doc::text(format!("{}{}", ctor.name, fields.collect::<String>()))
+                });
+                Doc::join(ctors, Doc::concat([Doc::line(), Doc::text("| ")]))
+            }
+        };
+
+        docs.push(definition(head, body));
+    }
+
+    for (name, def) in decls.classes.iter() {
+        let methods = def
+            .methods
+            .iter()
+            .map(|(name, method)| Doc::text(format!("{name} : {}", ty(method))));
+        let head = Doc::text(format!("{}class {name} {} ", public(name), def.var));
+        docs.push(Doc::concat([head, braced(methods)]).group());
+    }
+
+    for def in decls.instances.iter() {
+        let bounds: Vec<_> = def
+            .vars
+            .iter()
+            .flat_map(|var| {
+                var.bounds
+                    .iter()
+                    .map(move |class| format!("{class} {}", var.name))
+            })
+            .collect();
+
+        let bounds = match bounds.len() {
+            0 => String::new(),
+            1 => format!("{} => ", bounds[0]),
+            _ => format!("({}) => ", bounds.join(", ")),
+        };
+
+        let methods = def.methods.iter().map(|(name, body)| {
+            let head = Doc::text(format!("{name} ="));
+            Doc::concat([head, Doc::concat([Doc::line(), expr_doc(body)]).nest()]).group()
+        });
+
+        let head = Doc::text(format!("instance {bounds}{} {} ", def.class, aty(&def.ty)));
+        docs.push(Doc::concat([head, braced(methods)]).group());
+    }
+
+    for (name, def) in decls.values.iter() {
+        let mut head = format!("{}val {name}{}", public(name), type_params(&def.vars));
+        if !matches!(def.anno, Type::Wildcard) {
+            head.push_str(&format!(" : {}", ty(&def.anno)));
+        }
+
+        docs.push(definition(Doc::text(head), expr_doc(&def.body)));
+    }
+
+    let mut out = Doc::join(docs, Doc::concat([Doc::HardBreak, Doc::HardBreak])).render(width);
+    if !out.is_empty() {
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Print an expression in the syntax it is parsed from.
+pub fn expr(expr: &Expr, width: usize) -> String {
+    expr_doc(expr).render(width)
+}
+
+/// Print a type in the syntax of annotations.
+pub fn ty(ty: &Type) -> String {
+    type_at(ty, Prec::Top)
+}
+
+/// A definition of the form `head = body`, with the body on the next line if
+/// it does not fit.
+pub(super) fn definition(head: Doc, body: Doc) -> Doc {
+    let body = Doc::concat([Doc::line(), body]).nest();
+    Doc::concat([head, Doc::text(" ="), body]).group()
+}
+
+/// Items separated by commas in braces, on one line if they fit.
+pub(super) fn braced(items: impl IntoIterator<Item = Doc>) -> Doc {
+    let items = Doc::join(items, Doc::concat([Doc::text(","), Doc::line()]));
+    let items = Doc::concat([Doc::line(), items]).nest();
+    Doc::concat([Doc::text("{"), items, Doc::line(), Doc::text("}")]).group()
+}
+
+fn with_vars(name: &str, vars: &[String]) -> String {
+    let mut head = name.to_string();
+    for var in vars {
+        head.push(' ');
+        head.push_str(var);
+    }
+
+    head
+}
+
+/// The type parameters of a value definition, such as `[a: Show + Eq, b]`.
+fn type_params(vars: &[TypeParam]) -> String {
+    if vars.is_empty() {
+        return String::new();
+    }
+
+    let vars: Vec<_> = vars
+        .iter()
+        .map(|var| match var.bounds.is_empty() {
+            true => var.name.clone(),
+            false => format!("{}: {}", var.name, var.bounds.join(" + ")),
+        })
+        .collect();
+    format!(" [{}]", vars.join(", "))
+}
+
+/// How tightly a type or expression binds, loosest first.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(super) enum Prec {
+    Top,
+    Arrow,
+    Concat,
+    Apply,
+    Atom,
+}
+
+fn aty(ty: &Type) -> String {
+    type_at(ty, Prec::Atom)
+}
+
+fn type_at(ty: &Type, prec: Prec) -> String {
+    let (text, own) = match ty {
+        Type::Bool => ("Bool".into(), Prec::Atom),
+        Type::Regex => ("Regex".into(), Prec::Atom),
+        Type::Range(lo, hi) => (format!("{lo}..{hi}"), Prec::Atom),
+        Type::String(pat) => (regex(pat), Prec::Atom),
+        Type::Named(name) => (name.clone(), Prec::Atom),
+        Type::Wildcard => ("_".into(), Prec::Atom),
+        Type::Invalid => ("<invalid>".into(), Prec::Atom),
+
+        Type::Arrow(from, into) => (
+            format!(
+                "{} -> {}",
+                type_at(from, Prec::Apply),
+                type_at(into, Prec::Arrow)
+            ),
+            Prec::Arrow,
+        ),
+
+        Type::Apply(func, arg) => (
+            format!("{} {}", type_at(func, Prec::Apply), aty(arg)),
+            Prec::Apply,
+        ),
+
+        Type::Forall(vars, ty) => (
+            format!("forall {}. {}", vars.join(" "), type_at(ty, Prec::Top)),
+            Prec::Top,
+        ),
+    };
+
+    parens(text, own < prec)
+}
+
+fn parens(text: String, needed: bool) -> String {
+    match needed {
+        true => format!("({text})"),
+        false => text,
+    }
+}
+
+fn expr_doc(expr: &Expr) -> Doc {
+    expr_at(expr, Prec::Top)
+}
+
+fn expr_at(expr: &Expr, prec: Prec) -> Doc {
+    let (doc, own) = match expr {
+        Expr::Anno(expr, anno) => {
+            let doc = Doc::concat([expr_doc(expr), Doc::text(format!(" : {}", ty(anno)))]);
+            return Doc::concat([Doc::text("("), doc, Doc::text(")")]);
+        }
+
+        Expr::Fun(pat, body) => (function(pat_doc(pat), expr_doc(body)), Prec::Top),
+
+        Expr::Let {
+            pat,
+            bound,
+            then,
+            elze,
+        } => {
+            let elze = match elze.as_ref() {
+                Expr::Impossible => None,
+                elze => Some(expr_doc(elze)),
+            };
+
+            (
+                binding(pat_doc(pat), expr_doc(bound), expr_doc(then), elze),
+                Prec::Top,
+            )
+        }
+
+        Expr::Call(..) => {
+            let mut args = Vec::new();
+            let mut func = expr;
+            while let Expr::Call(inner, arg) = func {
+                args.push(expr_at(arg, Prec::Atom));
+                func = inner;
+            }
+
+            args.reverse();
+            (application(expr_at(func, Prec::Apply), args), Prec::Apply)
+        }
+
+        Expr::Binary(BinOp::Concat, lhs, rhs) => (
+            concat(expr_at(lhs, Prec::Concat), expr_at(rhs, Prec::Apply)),
+            Prec::Concat,
+        ),
+
+        Expr::Lit(lit) => (Doc::text(literal(lit)), Prec::Atom),
+        Expr::Name(name) => (Doc::text(name), Prec::Atom),
+
+        Expr::Instantiate(name, args) => {
+            let args = args.iter().map(|arg| format!(" @{}", aty(arg)));
+            (
+                Doc::text(format!("{name}{}", args.collect::<String>())),
+                Prec::Atom,
+            )
+        }
+
+        Expr::Impossible => (Doc::text("impossible"), Prec::Atom),
+        Expr::Invalid => (Doc::text("<invalid>"), Prec::Atom),
+    };
+
+    paren_doc(doc, own < prec)
+}
+
+pub(super) fn paren_doc(doc: Doc, needed: bool) -> Doc {
+    match needed {
+        true => Doc::concat([Doc::text("("), doc, Doc::text(")")]),
+        false => doc,
+    }
+}
+
+/// `fun pat => body`
+pub(super) fn function(pat: Doc, body: Doc) -> Doc {
+    let body = Doc::concat([Doc::line(), body]).nest();
+    Doc::concat([Doc::text("fun "), pat, Doc::text(" =>"), body]).group()
+}
+
+/// `let pat = bound in then else elze`
+pub(super) fn binding(pat: Doc, bound: Doc, then: Doc, elze: Option<Doc>) -> Doc {
+    let bound = Doc::concat([Doc::line(), bound]).nest();
+    let head = Doc::concat([Doc::text("let "), pat, Doc::text(" ="), bound]).group();
+    let mut doc = vec![head, Doc::text(" in"), Doc::line(), then];
+    if let Some(elze) = elze {
+        let elze = Doc::concat([Doc::line(), elze]).nest();
+        doc.extend([Doc::line(), Doc::text("else"), elze.group()]);
+    }
+
+    Doc::concat(doc).group()
+}
+
+/// A function applied to arguments, which are indented if they do not fit.
+pub(super) fn application(func: Doc, args: Vec<Doc>) -> Doc {
+    let args = args.into_iter().map(|arg| Doc::concat([Doc::line(), arg]));
+    Doc::concat([func, Doc::concat(args).nest()]).group()
+}
+
+/// `lhs ++ rhs`
+pub(super) fn concat(lhs: Doc, rhs: Doc) -> Doc {
+    Doc::concat([lhs, Doc::line(), Doc::text("++ "), rhs]).group()
+}
+
+fn pat_doc(pat: &Pat) -> Doc {
+    Doc::text(pat_at(pat, Prec::Apply))
+}
+
+fn pat_at(pat: &Pat, prec: Prec) -> String {
+    match pat {
+        Pat::Constructor(name) | Pat::Bind(name) => name.clone(),
+        Pat::Apply(ctor, arg) => parens(
+            format!("{} {}", pat_at(ctor, Prec::Apply), pat_at(arg, Prec::Atom)),
+            prec > Prec::Apply,
+        ),
+        Pat::Lit(lit) => literal(lit),
+        Pat::Wildcard => "_".into(),
+    }
+}
+
+fn literal(lit: &Literal) -> String {
+    match lit {
+        Literal::Boolean(v) => v.to_string(),
+        Literal::Integer(v) => v.to_string(),
+        Literal::String(v) => string(v),
+        Literal::Regex(pat) => regex(pat),
+    }
+}
+
+/// A string literal with the escapes the lexer understands.
+pub(super) fn string(text: &str) -> String {
+    let mut out = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+/// A regex literal, with slashes escaped.
+pub(super) fn regex(pat: &str) -> String {
+    format!("/{}/", pat.replace('/', "\\/"))
+}
@@ -0,0 +1,173 @@
+use super::hir::{
+    application, binding, concat, definition, function, paren_doc, regex, string, Prec,
+};
+use super::Doc;
+use crate::mir::{BinOp, Expr, ExprNode, Literal, Pat, Program};
+use crate::types::{Type, TypeId, Types};
+
+/// Print a typed program in concrete syntax. Data types are printed with the
+/// fields of their constructors, and values with their types.
+pub fn program(program: &Program, width: usize) -> String {
+    let types = &program.types;
+    let mut docs = Vec::new();
+
+    for (name, def) in program.decls.types.iter() {
+        let mut head = name.clone();
+        for param in def.params.iter() {
+            head.push(' ');
+            head.push_str(param);
+        }
+
+        let ctors = def.ctors.iter().map(|ctor| {
+            let mut text = ctor.clone();
+            if let Some(template) = program.context.get(ctor) {
+                let mut ty = template.uninst;
+                while let Type::Arrow(field, rest) = types.get(&ty) {
+                    text.push(' ');
+                    text.push_str(&aty(types, *field));
+                    ty = *rest;
+                }
+            }
+
+            Doc::text(text)
+        });
+
+        let body = Doc::join(ctors, Doc::concat([Doc::line(), Doc::text("| ")]));
+        docs.push(definition(Doc::text(format!("data {head}")), body));
+    }
+
+    for (name, body) in program.decls.values.iter() {
+        let mut head = format!("val {name}");
+        if let Some(template) = program.context.get(name) {
+            if !template.params.is_empty() {
+                head.push_str(&format!(" [{}]", template.params.join(", ")));
+            }
+
+            head.push_str(&format!(" : {}", types.display(template.uninst)));
+        }
+
+        docs.push(definition(Doc::text(head), expr_doc(types, body)));
+    }
+
+    let mut out = Doc::join(docs, Doc::concat([Doc::HardBreak, Doc::HardBreak])).render(width);
+    if !out.is_empty() {
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Print a typed expression in concrete syntax.
+pub fn expr(types: &Types, expr: &Expr, width: usize) -> String {
+    expr_doc(types, expr).render(width)
+}
+
+/// Display a type, in parentheses unless it is atomic.
+fn aty(types: &Types, id: TypeId) -> String {
+    let atomic = match types.get(&id) {
+        Type::Arrow(..) | Type::Forall(..) => false,
+        Type::Named(_, args) => args.is_empty(),
+        _ => true,
+    };
+
+    match atomic {
+        true => types.display(id).to_string(),
+        false => format!("({})", types.display(id)),
+    }
+}
+
+fn expr_doc(types: &Types, expr: &Expr) -> Doc {
+    expr_at(types, expr, Prec::Top)
+}
+
+fn expr_at(types: &Types, expr: &Expr, prec: Prec) -> Doc {
+    let (doc, own) = match &expr.node {
+        ExprNode::Fun(pat, body) => (
+            function(Doc::text(pat_at(pat, Prec::Apply)), expr_doc(types, body)),
+            Prec::Top,
+        ),
+
+        ExprNode::Let {
+            pat,
+            bound,
+            then,
+            elze,
+        } => {
+            let elze = match &elze.node {
+                ExprNode::Impossible => None,
+                _ => Some(expr_doc(types, elze)),
+            };
+
+            let pat = Doc::text(pat_at(pat, Prec::Apply));
+            let doc = binding(pat, expr_doc(types, bound), expr_doc(types, then), elze);
+            (doc, Prec::Top)
+        }
+
+        ExprNode::Tuple(exprs) => {
+            let exprs = exprs.iter().map(|expr| expr_doc(types, expr));
+            let exprs = Doc::join(exprs, Doc::concat([Doc::text(","), Doc::line()]));
+            let doc = Doc::concat([Doc::text("("), exprs.nest(), Doc::text(")")]);
+            (doc.group(), Prec::Atom)
+        }
+
+        ExprNode::Call(..) => {
+            let mut args = Vec::new();
+            let mut func = expr;
+            while let ExprNode::Call(inner, arg) = &func.node {
+                args.push(expr_at(types, arg, Prec::Atom));
+                func = inner;
+            }
+
+            args.reverse();
+            let func = expr_at(types, func, Prec::Apply);
+            (application(func, args), Prec::Apply)
+        }
+
+        ExprNode::Binary(BinOp::Concat, lhs, rhs) => (
+            concat(
+                expr_at(types, lhs, Prec::Concat),
+                expr_at(types, rhs, Prec::Apply),
+            ),
+            Prec::Concat,
+        ),
+
+        ExprNode::Lit(lit) => (Doc::text(literal(lit)), Prec::Atom),
+        ExprNode::Name(name) => (Doc::text(name), Prec::Atom),
+
+        ExprNode::Instantiated(name, args) => {
+            let args = args.iter().map(|arg| format!(" @{}", aty(types, *arg)));
+            let doc = Doc::text(format!("{name}{}", args.collect::<String>()));
+            (doc, Prec::Atom)
+        }
+
+        ExprNode::Impossible => (Doc::text("impossible"), Prec::Atom),
+        ExprNode::Invalid => (Doc::text("<invalid>"), Prec::Atom),
+    };
+
+    paren_doc(doc, own < prec)
+}
+
+fn pat_at(pat: &Pat, prec: Prec) -> String {
+    match pat {
+        Pat::Constructor(name) | Pat::Bind(name) => name.clone(),
+        Pat::Apply(ctor, arg) => {
+            let text = format!("{} {}", pat_at(ctor, Prec::Apply), pat_at(arg, Prec::Atom));
+            match prec > Prec::Apply {
+                true => format!("({text})"),
+                false => text,
+            }
+        }
+        Pat::Lit(lit) => literal(lit),
+        Pat::Wildcard => "_".into(),
+        Pat::Invalid => "<invalid>".into(),
+    }
+}
+
+fn literal(lit: &Literal) -> String {
+    match lit {
+        Literal::Boolean(v) => v.to_string(),
+        Literal::Integer(v) => v.to_string(),
+        Literal::String(v) => string(v),
+        Literal::Regex(pat) => regex(pat.as_str()),
+    }
+}
@@ -1,35 +1,243 @@
+use std::fmt;
 use std::hash::{Hash, Hasher};
 
+use regex_syntax::hir::{
+    Anchor, Group, GroupKind, Hir, HirKind, Literal, Repetition, RepetitionKind, RepetitionRange,
+};
+
 /// A wrapper type around [`regex::Regex`] that admits equality tests and
 /// hashing by checking whether the string representations of the two regexes
 /// are the same.
-#[derive(Clone, Debug)]
-pub struct Regex(regex::Regex);
+///
+/// A regex always matches against the *whole* text, such that a string type
+/// `String(r)` contains exactly the strings matched by `r`.
+#[derive(Clone)]
+pub struct Regex {
+    pattern: String,
+    anchored: regex::Regex,
+}
 
 impl Regex {
+    /// Compile the given pattern, failing with the syntax error if it is not a
+    /// valid regex.
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        regex::Regex::new(pattern)?;
+        let hir = Self::parse(pattern);
+        Self::compile(pattern.into(), hir)
+    }
+
+    /// A regex matching exactly the given text.
+    ///
+    /// This and the other combinators fail if the resulting regex exceeds the
+    /// size limit of the regex engine.
+    pub fn literal(text: &str) -> Result<Self, regex::Error> {
+        let hir = Hir::concat(
+            text.chars()
+                .map(|c| Hir::literal(Literal::Unicode(c)))
+                .collect(),
+        );
+
+        Self::from_hir(hir)
+    }
+
+    /// A regex matching any text matched by `self` followed by any text
+    /// matched by `other`.
+    pub fn concat(&self, other: &Self) -> Result<Self, regex::Error> {
+        let hir = Hir::concat(vec![self.operand(), other.operand()]);
+        Self::from_hir(hir)
+    }
+
+    /// A regex matching any text matched by either `self` or `other`.
+    pub fn alt(&self, other: &Self) -> Result<Self, regex::Error> {
+        let hir = Hir::alternation(vec![self.to_hir(), other.to_hir()]);
+        Self::from_hir(hir)
+    }
+
+    /// A regex matching zero or more repetitions of `self`.
+    pub fn star(&self) -> Result<Self, regex::Error> {
+        self.repetition(RepetitionKind::ZeroOrMore)
+    }
+
+    /// A regex matching between `min` and `max` (inclusive) repetitions of
+    /// `self`.
+    pub fn repeat(&self, min: u32, max: u32) -> Result<Self, regex::Error> {
+        self.repetition(RepetitionKind::Range(RepetitionRange::Bounded(min, max)))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
     pub fn is_match(&self, text: &str) -> bool {
-        self.0.is_match(text)
+        self.anchored.is_match(text)
+    }
+
+    /// Whether this regex asserts where the start or end of the text is, which
+    /// is meaningless for a type matching the whole text.
+    pub fn has_anchors(&self) -> bool {
+        has_anchors(&self.to_hir())
+    }
+
+    /// The least and greatest number of characters in a text matched by this
+    /// regex, where the greatest is `None` if there is no limit.
+    pub fn lengths(&self) -> (u64, Option<u64>) {
+        lengths(&self.to_hir())
+    }
+
+    fn repetition(&self, kind: RepetitionKind) -> Result<Self, regex::Error> {
+        let hir = Hir::repetition(Repetition {
+            kind,
+            greedy: true,
+            hir: Box::new(self.atom()),
+        });
+
+        Self::from_hir(hir)
+    }
+
+    /// Build a regex from a syntax tree produced by combining valid regexes.
+    /// The printed form of such a tree is always a valid pattern, but it may
+    /// exceed the size limit of the regex engine.
+    fn from_hir(hir: Hir) -> Result<Self, regex::Error> {
+        let pattern = hir.to_string();
+        Self::compile(pattern, hir)
+    }
+
+    fn compile(pattern: String, hir: Hir) -> Result<Self, regex::Error> {
+        let anchored = Hir::concat(vec![
+            Hir::anchor(Anchor::StartText),
+            group(hir),
+            Hir::anchor(Anchor::EndText),
+        ]);
+
+        let anchored = regex::Regex::new(&anchored.to_string())?;
+        Ok(Self { pattern, anchored })
+    }
+
+    fn parse(pattern: &str) -> Hir {
+        regex_syntax::Parser::new()
+            .parse(pattern)
+            .expect("pattern accepted by the regex engine")
+    }
+
+    fn to_hir(&self) -> Hir {
+        Self::parse(&self.pattern)
+    }
+
+    /// The syntax tree of this regex, grouped such that it may be an operand
+    /// of a concatenation.
+    fn operand(&self) -> Hir {
+        let hir = self.to_hir();
+        match hir.kind() {
+            HirKind::Alternation(_) => group(hir),
+            _ => hir,
+        }
+    }
+
+    /// The syntax tree of this regex, grouped such that it may be an operand
+    /// of a repetition.
+    fn atom(&self) -> Hir {
+        let hir = self.to_hir();
+        match hir.kind() {
+            HirKind::Literal(_) | HirKind::Class(_) | HirKind::Group(_) => hir,
+
+            HirKind::Empty
+            | HirKind::Anchor(_)
+            | HirKind::WordBoundary(_)
+            | HirKind::Repetition(_)
+            | HirKind::Concat(_)
+            | HirKind::Alternation(_) => group(hir),
+        }
+    }
+}
+
+fn lengths(hir: &Hir) -> (u64, Option<u64>) {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Anchor(_) | HirKind::WordBoundary(_) => (0, Some(0)),
+        HirKind::Literal(_) | HirKind::Class(_) => (1, Some(1)),
+        HirKind::Group(group) => lengths(&group.hir),
+
+        HirKind::Repetition(repetition) => {
+            let (min, max) = lengths(&repetition.hir);
+            let (lo, hi) = match &repetition.kind {
+                RepetitionKind::ZeroOrOne => (0, Some(1)),
+                RepetitionKind::ZeroOrMore => (0, None),
+                RepetitionKind::OneOrMore => (1, None),
+                RepetitionKind::Range(RepetitionRange::Exactly(n)) => (*n, Some(*n)),
+                RepetitionKind::Range(RepetitionRange::AtLeast(n)) => (*n, None),
+                RepetitionKind::Range(RepetitionRange::Bounded(m, n)) => (*m, Some(*n)),
+            };
+
+            // Any number of repetitions of the empty text is still empty.
+            let max = match (max, hi) {
+                (Some(0), _) | (_, Some(0)) => Some(0),
+                (Some(max), Some(hi)) => Some(max.saturating_mul(hi.into())),
+                _ => None,
+            };
+
+            (min.saturating_mul(lo.into()), max)
+        }
+
+        HirKind::Concat(hirs) => {
+            hirs.iter()
+                .map(lengths)
+                .fold((0, Some(0)), |(min, max), (lo, hi)| {
+                    let max = max.zip(hi).map(|(max, hi)| max.saturating_add(hi));
+                    (min.saturating_add(lo), max)
+                })
+        }
+
+        HirKind::Alternation(hirs) => {
+            let (mins, maxes): (Vec<_>, Vec<_>) = hirs.iter().map(lengths).unzip();
+            let min = mins.into_iter().min().unwrap_or(0);
+            let max = maxes.into_iter().collect::<Option<Vec<_>>>();
+            (min, max.and_then(|maxes| maxes.into_iter().max()))
+        }
     }
 }
 
+fn has_anchors(hir: &Hir) -> bool {
+    match hir.kind() {
+        HirKind::Anchor(_) => true,
+        HirKind::Empty | HirKind::Literal(_) | HirKind::Class(_) | HirKind::WordBoundary(_) => {
+            false
+        }
+        HirKind::Group(group) => has_anchors(&group.hir),
+        HirKind::Repetition(repetition) => has_anchors(&repetition.hir),
+        HirKind::Concat(hirs) | HirKind::Alternation(hirs) => hirs.iter().any(has_anchors),
+    }
+}
+
+fn group(hir: Hir) -> Hir {
+    Hir::group(Group {
+        kind: GroupKind::NonCapturing,
+        hir: Box::new(hir),
+    })
+}
+
 impl From<regex::Regex> for Regex {
     fn from(v: regex::Regex) -> Self {
-        Self(v)
+        let pattern = v.as_str().to_string();
+        let hir = Self::parse(&pattern);
+        Self::compile(pattern, hir).unwrap()
     }
 }
 
-impl Eq for Regex {
-    fn assert_receiver_is_total_eq(&self) {}
+impl fmt::Debug for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Regex").field(&self.pattern).finish()
+    }
 }
 
+impl Eq for Regex {}
+
 impl PartialEq for Regex {
     fn eq(&self, other: &Self) -> bool {
-        self.0.as_str() == other.0.as_str()
+        self.as_str() == other.as_str()
     }
 }
 
 impl Hash for Regex {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.0.as_str().hash(state)
+        self.as_str().hash(state)
     }
 }
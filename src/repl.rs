@@ -0,0 +1,183 @@
+//! An interactive session, which accumulates declarations and evaluates
+//! expressions against them.
+
+use std::path::Path;
+
+use crate::eval::Interpreter;
+use crate::mir::Program;
+use crate::{hir, module, syntax, Diagnostic, Session};
+
+/// The name given to an expression entered at the prompt while it is checked
+/// and evaluated. It cannot be written in source, so never clashes with a
+/// declaration.
+const INPUT: &str = "$input";
+
+const HELP: &str = "\
+<declaration>    add a declaration, replacing any of the same name
+<expression>     evaluate an expression and print its value and type
+:type <expr>     print the type of an expression
+:load <file>     add the declarations in a file and the modules it imports
+:reset           forget every declaration
+:quit            end the session";
+
+/// The declarations entered so far in a session. Every input is checked along
+/// with all of them, so earlier definitions stay in scope, but only the new
+/// input is checked unless it replaces an earlier declaration.
+pub struct Repl {
+    session: Session,
+}
+
+/// The response to one input.
+pub enum Reply {
+    /// Text to print, which may be empty.
+    Output(String),
+    /// Problems with the input, which was otherwise ignored.
+    Errors(Vec<Diagnostic>),
+    /// The input ended early, and continues on the next line.
+    Incomplete,
+    Quit,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self {
+            session: Session::new(),
+        }
+    }
+
+    /// Handle a line of input, along with any lines it continues.
+    pub fn input(&mut self, input: &str) -> Reply {
+        let input = input.trim();
+        if let Some(command) = input.strip_prefix(':') {
+            let (command, arg) = command
+                .split_once(char::is_whitespace)
+                .unwrap_or((command, ""));
+            return self.command(command, arg.trim());
+        }
+
+        if input.is_empty() {
+            return Reply::Output(String::new());
+        }
+
+        let first = input.split_whitespace().next().unwrap_or_default();
+        let parsed = match first {
+            "data" | "type" | "newtype" | "class" | "instance" | "val" | "pub" | "import" => {
+                syntax::parse_input(input).map(|decls| self.define(decls))
+            }
+            _ => syntax::parse_expr(input).map(|expr| self.evaluate(expr)),
+        };
+
+        match parsed {
+            Ok(reply) => reply,
+            Err(syntax::Error::Incomplete(_)) => Reply::Incomplete,
+            Err(syntax::Error::Invalid(diagnostic)) => Reply::Errors(vec![diagnostic]),
+        }
+    }
+
+    fn command(&mut self, command: &str, arg: &str) -> Reply {
+        match command {
+            "type" | "t" => match syntax::parse_expr(arg) {
+                Ok(expr) => match self.check_input(expr, |program| type_of(program, INPUT)) {
+                    Ok(ty) => Reply::Output(ty),
+                    Err(diagnostics) => Reply::Errors(diagnostics),
+                },
+                Err(error) => Reply::Errors(vec![error.into()]),
+            },
+
+            "load" | "l" => match module::load(Path::new(arg)).map(module::link) {
+                Ok((decls, diagnostics)) if diagnostics.is_empty() => self.define(decls),
+                Ok((_, diagnostics)) | Err(diagnostics) => Reply::Errors(diagnostics),
+            },
+
+            "reset" => {
+                self.session = Session::new();
+                Reply::Output(String::new())
+            }
+
+            "help" | "h" | "?" => Reply::Output(HELP.into()),
+            "quit" | "q" => Reply::Quit,
+
+            command => Reply::Errors(vec![Diagnostic::new(format!(
+                "unknown command `:{command}`"
+            ))
+            .with_note("try `:help`")]),
+        }
+    }
+
+    /// Add the given declarations, if they check along with the existing
+    /// ones, and print the types of the new values.
+    fn define(&mut self, new: hir::Decls) -> Reply {
+        if !new.imports.is_empty() {
+            return Reply::Errors(vec![Diagnostic::new(
+                "modules can only be imported by files, loaded with `:load`",
+            )]);
+        }
+
+        let names: Vec<_> = new.values.keys().cloned().collect();
+        if let Err(diagnostics) = self.session.add(new) {
+            return Reply::Errors(diagnostics);
+        }
+
+        let program = self.session.program();
+        let lines: Vec<_> = names
+            .iter()
+            .map(|name| format!("{name} : {}", type_of(program, name)))
+            .collect();
+        Reply::Output(lines.join("\n"))
+    }
+
+    /// Evaluate an expression, printing its value and type.
+    fn evaluate(&mut self, expr: hir::Expr) -> Reply {
+        let reply = self.check_input(expr, |program| {
+            match Interpreter::new(program).global(INPUT) {
+                Ok(value) => Reply::Output(format!("{value} : {}", type_of(program, INPUT))),
+                Err(diagnostic) => Reply::Errors(vec![diagnostic.within(None)]),
+            }
+        });
+
+        reply.unwrap_or_else(Reply::Errors)
+    }
+
+    /// Check an expression along with the declarations so far, and give the
+    /// program including it to `f`.
+    fn check_input<T>(
+        &mut self,
+        expr: hir::Expr,
+        f: impl FnOnce(&Program) -> T,
+    ) -> Result<T, Vec<Diagnostic>> {
+        let def = hir::ValueDef {
+            vars: Vec::new(),
+            anno: hir::Type::Wildcard,
+            body: expr,
+        };
+
+        self.session
+            .with_value(INPUT.into(), def, f)
+            .map_err(|diagnostics| {
+                let input = Some(INPUT.to_string());
+                diagnostics
+                    .into_iter()
+                    .map(|diagnostic| match diagnostic.within == input {
+                        true => diagnostic.within(None),
+                        false => diagnostic,
+                    })
+                    .collect()
+            })
+    }
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The type of a top-level name, with its type parameters.
+fn type_of(program: &Program, name: &str) -> String {
+    let template = &program.context[name];
+    let ty = program.types.display(template.uninst);
+    match template.params.is_empty() {
+        true => ty.to_string(),
+        false => format!("forall {}. {ty}", template.params.join(" ")),
+    }
+}
@@ -0,0 +1,77 @@
+//! The surface syntax of programs, parsed into [`hir`](crate::hir).
+
+pub mod cst;
+mod format;
+mod lexer;
+mod parser;
+
+pub use format::format;
+pub use lexer::TokenKind;
+
+use crate::hir;
+use crate::Diagnostic;
+
+use parser::Parser;
+
+/// A failure to parse source text entered at a prompt.
+#[derive(Debug)]
+pub enum Error {
+    /// The text ended before what it started was complete, so it may continue
+    /// on the next line.
+    Incomplete(Diagnostic),
+    /// The text is invalid however it continues.
+    Invalid(Diagnostic),
+}
+
+impl From<Error> for Diagnostic {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Incomplete(diagnostic) | Error::Invalid(diagnostic) => diagnostic,
+        }
+    }
+}
+
+/// Parse the declarations of a program from source text.
+pub fn parse(src: &str) -> Result<hir::Decls, Diagnostic> {
+    Parser::new(src).decls()
+}
+
+/// Parse declarations entered at a prompt, telling whether they are
+/// incomplete if they do not parse.
+pub fn parse_input(src: &str) -> Result<hir::Decls, Error> {
+    let mut parser = Parser::new(src);
+    parser
+        .decls()
+        .map_err(|diagnostic| parser.failure(diagnostic))
+}
+
+/// Parse a source file into its concrete syntax tree, which keeps comments and
+/// layout.
+pub fn parse_cst(src: &str) -> Result<cst::Node, Diagnostic> {
+    let mut parser = Parser::new(src);
+    parser.decls()?;
+    Ok(parser.into_cst())
+}
+
+/// Parse a single expression from source text, such as a line entered at a
+/// prompt, telling whether it is incomplete if it does not parse.
+pub fn parse_expr(src: &str) -> Result<hir::Expr, Error> {
+    let mut parser = Parser::new(src);
+    parser
+        .whole_expr()
+        .map_err(|diagnostic| parser.failure(diagnostic))
+}
+
+/// The line and column of the given byte offset in the source, both counting
+/// from one.
+pub(crate) fn position(src: &str, offset: usize) -> (usize, usize) {
+    let before = &src[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rfind('\n')
+        .map_or(before, |newline| &before[newline + 1..])
+        .chars()
+        .count()
+        + 1;
+    (line, column)
+}
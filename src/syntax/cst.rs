@@ -0,0 +1,164 @@
+//! The concrete syntax tree of a source file. Unlike [`hir`](crate::hir) it
+//! keeps every token along with the whitespace and comments before it, so the
+//! source can be reproduced exactly.
+
+use super::lexer::TokenKind;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NodeKind {
+    /// A whole source file, ending with the end of input token.
+    File,
+
+    /// `import data.list as list`
+    ImportDecl,
+    /// `data List a = Nil | Cons a (List a)`
+    DataDecl,
+    /// `Cons a (List a)`
+    Ctor,
+    /// `type Port = 1..65536`
+    AliasDecl,
+    /// `newtype UserId = 0..1000000`
+    NewtypeDecl,
+    /// `class Eq a { eq : a -> a -> Bool }`
+    ClassDecl,
+    /// `eq : a -> a -> Bool`
+    Signature,
+    /// `instance Eq a => Eq (List a) { eq = ... }`
+    InstanceDecl,
+    /// `Eq a =>` or `(Eq a, Show b) =>`
+    Bounds,
+    /// `eq = ...`
+    Method,
+    /// `val f [a: Show] : a -> a = ...`
+    ValDecl,
+    /// `[a: Show, b]`
+    TypeParams,
+    /// `a: Show + Eq`
+    TypeParam,
+
+    /// `forall a. a -> a`
+    ForallType,
+    /// `a -> b`
+    ArrowType,
+    /// `List a`
+    ApplyType,
+    /// `0..10`
+    RangeType,
+    /// `(a -> b)`
+    ParenType,
+
+    /// `fun x => x`
+    FunExpr,
+    /// `let p = e in e else e`
+    LetExpr,
+    /// `a ++ b`
+    ConcatExpr,
+    /// `f x`
+    CallExpr,
+    /// `(e)` or `(e : T)`
+    ParenExpr,
+    /// `f @(0..10)`
+    InstantiateExpr,
+
+    /// `Cons x xs`
+    ApplyPat,
+    /// `(Cons x xs)`
+    ParenPat,
+}
+
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub children: Vec<Child>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Child {
+    Node(Node),
+    Token(Token),
+}
+
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// The whitespace and comments before the token.
+    pub trivia: String,
+    pub text: String,
+}
+
+impl Node {
+    /// The source text this node was parsed from.
+    pub fn source(&self) -> String {
+        let mut out = String::new();
+        self.write_source(&mut out);
+        out
+    }
+
+    fn write_source(&self, out: &mut String) {
+        for child in self.children.iter() {
+            match child {
+                Child::Node(node) => node.write_source(out),
+                Child::Token(token) => {
+                    out.push_str(&token.trivia);
+                    out.push_str(&token.text);
+                }
+            }
+        }
+    }
+}
+
+/// Builds a tree as its tokens are parsed, in the same order.
+#[derive(Debug, Default)]
+pub struct Builder {
+    /// The nodes being built, innermost last, along with their children so
+    /// far.
+    stack: Vec<(NodeKind, Vec<Child>)>,
+}
+
+/// A point in the children of the innermost node, from which the following
+/// children may later be wrapped in a node of their own.
+#[derive(Clone, Copy, Debug)]
+pub struct Checkpoint(usize);
+
+impl Builder {
+    pub fn new() -> Self {
+        Self {
+            stack: vec![(NodeKind::File, Vec::new())],
+        }
+    }
+
+    pub fn token(&mut self, token: Token) {
+        self.children().push(Child::Token(token));
+    }
+
+    /// Start a node, which contains every token and node added until it is
+    /// finished.
+    pub fn start(&mut self, kind: NodeKind) {
+        self.stack.push((kind, Vec::new()));
+    }
+
+    pub fn finish(&mut self) {
+        let (kind, children) = self.stack.pop().unwrap();
+        self.children().push(Child::Node(Node { kind, children }));
+    }
+
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        Checkpoint(self.children().len())
+    }
+
+    /// Wrap every child added since the checkpoint in a node.
+    pub fn wrap(&mut self, checkpoint: Checkpoint, kind: NodeKind) {
+        let children = self.children().split_off(checkpoint.0);
+        self.children().push(Child::Node(Node { kind, children }));
+    }
+
+    /// The root of the finished tree.
+    pub fn build(mut self) -> Node {
+        let (kind, children) = self.stack.swap_remove(0);
+        Node { kind, children }
+    }
+
+    fn children(&mut self) -> &mut Vec<Child> {
+        &mut self.stack.last_mut().unwrap().1
+    }
+}
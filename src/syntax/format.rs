@@ -0,0 +1,443 @@
+//! Canonical layout of source files. Every comment is kept, either on a line
+//! of its own or after the token it followed, while whitespace is replaced by
+//! the layout of the [pretty-printer](crate::pretty).
+
+use super::cst::{Child, Node, NodeKind, Token};
+use super::{parse_cst, TokenKind};
+use crate::pretty::Doc;
+use crate::Diagnostic;
+
+/// Lay out a source file canonically within the given width, keeping its
+/// comments.
+pub fn format(src: &str, width: usize) -> Result<String, Diagnostic> {
+    let file = parse_cst(src)?;
+    let mut formatter = Formatter {
+        comments: comments(&file),
+        next: 0,
+    };
+
+    // Declarations are separated by blank lines, except for consecutive
+    // imports.
+    let mut decls = Vec::new();
+    let mut previous = None;
+    let mut end = Doc::text("");
+    for child in file.children.iter() {
+        match child {
+            Child::Node(node) => {
+                match previous {
+                    Some(NodeKind::ImportDecl) if node.kind == NodeKind::ImportDecl => {
+                        decls.push(Doc::HardBreak)
+                    }
+                    Some(_) => decls.extend([Doc::HardBreak, Doc::HardBreak]),
+                    None => {}
+                }
+
+                decls.push(formatter.node(node));
+                previous = Some(node.kind);
+            }
+            Child::Token(token) => end = formatter.token(token),
+        }
+    }
+
+    let mut out = Doc::concat([Doc::concat(decls), end]).render(width);
+    if !out.is_empty() {
+        out.push('\n');
+    }
+
+    Ok(out)
+}
+
+/// The comments around one token.
+#[derive(Default)]
+struct Comments {
+    /// Comments on lines of their own before the token.
+    before: Vec<String>,
+    /// A comment on the same line after the token.
+    after: Option<String>,
+}
+
+struct Formatter {
+    /// The comments of every token, in order.
+    comments: Vec<Comments>,
+    /// The index of the next token to be laid out.
+    next: usize,
+}
+
+/// Find the comments around each token of a file. A comment is kept with the
+/// token before it when it is on the same line, and otherwise with the token
+/// after it.
+fn comments(file: &Node) -> Vec<Comments> {
+    let mut tokens = Vec::new();
+    collect_tokens(file, &mut tokens);
+
+    let mut comments: Vec<Comments> = Vec::new();
+    for token in tokens {
+        let mut lines = token.trivia.split('\n');
+        let mut before = Vec::new();
+        if let Some(previous) = comments.last_mut() {
+            let first = lines.next().unwrap_or_default();
+            previous.after = comment(first);
+        }
+
+        before.extend(lines.filter_map(comment));
+        comments.push(Comments {
+            before,
+            after: None,
+        });
+    }
+
+    comments
+}
+
+fn collect_tokens<'a>(node: &'a Node, tokens: &mut Vec<&'a Token>) {
+    for child in node.children.iter() {
+        match child {
+            Child::Node(node) => collect_tokens(node, tokens),
+            Child::Token(token) => tokens.push(token),
+        }
+    }
+}
+
+/// The comment on a line of trivia, if any.
+fn comment(line: &str) -> Option<String> {
+    let start = line.find("--")?;
+    Some(line[start..].trim_end().to_string())
+}
+
+impl Formatter {
+    /// A token along with its comments.
+    fn token(&mut self, token: &Token) -> Doc {
+        let mut docs = self.comments_before();
+        docs.push(Doc::text(&token.text));
+        docs.extend(self.comment_after());
+        Doc::concat(docs)
+    }
+
+    /// The comments of a token which is left out of the layout, such as a
+    /// trailing comma.
+    fn skip(&mut self) -> Doc {
+        let mut docs = self.comments_before();
+        docs.extend(self.comment_after());
+        Doc::concat(docs)
+    }
+
+    fn comments_before(&mut self) -> Vec<Doc> {
+        let comments = &mut self.comments[self.next];
+        comments
+            .before
+            .drain(..)
+            .map(|text| Doc::Comment {
+                text,
+                own_line: true,
+            })
+            .collect()
+    }
+
+    fn comment_after(&mut self) -> Option<Doc> {
+        let comment = self.comments[self.next].after.take();
+        self.next += 1;
+        comment.map(|text| Doc::Comment {
+            text,
+            own_line: false,
+        })
+    }
+
+    fn child(&mut self, child: &Child) -> Doc {
+        match child {
+            Child::Node(node) => self.node(node),
+            Child::Token(token) => self.token(token),
+        }
+    }
+
+    /// Children laid out one after another, separated by spaces.
+    fn spaced(&mut self, children: &[Child]) -> Doc {
+        let docs: Vec<_> = children.iter().map(|child| self.child(child)).collect();
+        Doc::join(docs, Doc::text(" "))
+    }
+
+    /// Children laid out one after another, without spaces.
+    fn tight(&mut self, children: &[Child]) -> Doc {
+        Doc::concat(
+            children
+                .iter()
+                .map(|child| self.child(child))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// A node, preceded by the comments on lines before it so that they do
+    /// not break its groups.
+    fn node(&mut self, node: &Node) -> Doc {
+        let mut docs = self.comments_before();
+        docs.push(self.layout(node));
+        Doc::concat(docs)
+    }
+
+    fn layout(&mut self, node: &Node) -> Doc {
+        let children = &node.children[..];
+        match node.kind {
+            NodeKind::File => self.tight(children),
+
+            NodeKind::DataDecl => {
+                let equals = position(children, TokenKind::Equals);
+                let head = self.spaced(&children[..equals]);
+                let equals_doc = self.child(&children[equals]);
+                let mut body = Vec::new();
+                for child in children[equals + 1..].iter() {
+                    match child {
+                        Child::Token(bar) => {
+                            body.extend([Doc::line(), self.token(bar), Doc::text(" ")]);
+                        }
+                        Child::Node(ctor) => body.push(self.node(ctor)),
+                    }
+                }
+
+                definition(head, equals_doc, Doc::concat(body))
+            }
+
+            NodeKind::AliasDecl | NodeKind::NewtypeDecl | NodeKind::ValDecl => {
+                let equals = position(children, TokenKind::Equals);
+                let head = self.spaced(&children[..equals]);
+                let equals_doc = self.child(&children[equals]);
+                let body = self.child(&children[equals + 1]);
+                definition(head, equals_doc, body)
+            }
+
+            NodeKind::ClassDecl | NodeKind::InstanceDecl => {
+                let brace = position(children, TokenKind::LeftBrace);
+                let head = self.spaced(&children[..brace]);
+                let body = self.braced(&children[brace..]);
+                Doc::concat([head, Doc::text(" "), body]).group()
+            }
+
+            NodeKind::Signature => {
+                let name = self.child(&children[0]);
+                let colon = self.child(&children[1]);
+                let ty = self.child(&children[2]);
+                Doc::concat([name, Doc::text(" "), colon, Doc::text(" "), ty])
+            }
+
+            NodeKind::Bounds => self.bounds(children),
+
+            NodeKind::Method => {
+                let name = self.child(&children[0]);
+                let equals = self.child(&children[1]);
+                let body = self.child(&children[2]);
+                definition(name, equals, body)
+            }
+
+            NodeKind::TypeParams => {
+                let mut docs = Vec::new();
+                for child in children.iter() {
+                    docs.push(self.child(child));
+                    if is_token(child, TokenKind::Comma) {
+                        docs.push(Doc::text(" "));
+                    }
+                }
+
+                Doc::concat(docs)
+            }
+
+            NodeKind::TypeParam => {
+                let mut docs = Vec::new();
+                for child in children.iter() {
+                    if is_token(child, TokenKind::Plus) {
+                        docs.push(Doc::text(" "));
+                    }
+
+                    docs.push(self.child(child));
+                    if is_token(child, TokenKind::Colon) || is_token(child, TokenKind::Plus) {
+                        docs.push(Doc::text(" "));
+                    }
+                }
+
+                Doc::concat(docs)
+            }
+
+            NodeKind::ForallType => {
+                let dot = position(children, TokenKind::Dot);
+                let vars = self.spaced(&children[..dot]);
+                let dot_doc = self.child(&children[dot]);
+                let body = self.child(&children[dot + 1]);
+                Doc::concat([vars, dot_doc, Doc::text(" "), body])
+            }
+
+            NodeKind::ImportDecl
+            | NodeKind::ArrowType
+            | NodeKind::Ctor
+            | NodeKind::ApplyType
+            | NodeKind::ApplyPat => self.spaced(children),
+
+            NodeKind::RangeType
+            | NodeKind::ParenType
+            | NodeKind::ParenPat
+            | NodeKind::InstantiateExpr => {
+                let mut docs = Vec::new();
+                for child in children.iter() {
+                    if is_token(child, TokenKind::At) {
+                        docs.push(Doc::text(" "));
+                    }
+
+                    docs.push(self.child(child));
+                }
+
+                Doc::concat(docs)
+            }
+
+            NodeKind::ParenExpr => {
+                let mut docs = Vec::new();
+                for child in children.iter() {
+                    let colon = is_token(child, TokenKind::Colon);
+                    if colon {
+                        docs.push(Doc::text(" "));
+                    }
+
+                    docs.push(self.child(child));
+                    if colon {
+                        docs.push(Doc::text(" "));
+                    }
+                }
+
+                Doc::concat(docs)
+            }
+
+            // `fun pat => body`
+            NodeKind::FunExpr => {
+                let fun = self.child(&children[0]);
+                let pat = self.child(&children[1]);
+                let arrow = self.child(&children[2]);
+                let body = self.child(&children[3]);
+                let body = Doc::concat([Doc::line(), body]).nest();
+                Doc::concat([fun, Doc::text(" "), pat, Doc::text(" "), arrow, body]).group()
+            }
+
+            // `let pat = bound in then else elze`
+            NodeKind::LetExpr => {
+                let docs: Vec<_> = children.iter().map(|child| self.child(child)).collect();
+                let mut docs = docs.into_iter();
+                let mut next = || docs.next().unwrap();
+
+                let (keyword, pat, equals) = (next(), next(), next());
+                let bound = Doc::concat([Doc::line(), next()]).nest();
+                let head =
+                    Doc::concat([keyword, Doc::text(" "), pat, Doc::text(" "), equals, bound]);
+                let (within, then) = (next(), next());
+                let mut doc = vec![head.group(), Doc::text(" "), within, Doc::line(), then];
+                if children.len() > 6 {
+                    let (keyword, elze) = (next(), next());
+                    let elze = Doc::concat([Doc::line(), elze]).nest().group();
+                    doc.extend([Doc::line(), keyword, elze]);
+                }
+
+                Doc::concat(doc).group()
+            }
+
+            // `lhs ++ rhs`
+            NodeKind::ConcatExpr => {
+                let lhs = self.child(&children[0]);
+                let op = self.child(&children[1]);
+                let rhs = self.child(&children[2]);
+                Doc::concat([lhs, Doc::line(), op, Doc::text(" "), rhs]).group()
+            }
+
+            // Calls are nested to the left, and laid out as one application.
+            NodeKind::CallExpr => {
+                let mut calls = vec![node];
+                while let [Child::Node(inner), _] = &calls.last().unwrap().children[..] {
+                    match inner.kind {
+                        NodeKind::CallExpr => calls.push(inner),
+                        _ => break,
+                    }
+                }
+
+                let func = self.child(&calls.last().unwrap().children[0]);
+                let args = calls
+                    .iter()
+                    .rev()
+                    .map(|call| self.child(&call.children[1]))
+                    .collect();
+                application(func, args)
+            }
+        }
+    }
+
+    /// Items separated by commas in braces, on one line if they fit. A
+    /// trailing comma is left out.
+    fn braced(&mut self, children: &[Child]) -> Doc {
+        let open = self.child(&children[0]);
+        let mut items = Vec::new();
+        let mut close = Doc::text("");
+        for (i, child) in children.iter().enumerate().skip(1) {
+            let trailing = i + 2 == children.len();
+            match child {
+                Child::Node(item) => items.push(self.node(item)),
+                // Comments before the closing brace stay with the items.
+                Child::Token(token) if token.kind == TokenKind::RightBrace => {
+                    items.extend(self.comments_before());
+                    close = self.token(token);
+                }
+                Child::Token(_) if trailing => items.push(self.skip()),
+                Child::Token(comma) => items.extend([self.token(comma), Doc::line()]),
+            }
+        }
+
+        let items = Doc::concat([Doc::line(), Doc::concat(items)]).nest();
+        Doc::concat([open, items, Doc::line(), close]).group()
+    }
+
+    /// `Eq a =>` or `(Eq a, Show b) =>`, with parentheses only around more
+    /// than one bound.
+    fn bounds(&mut self, children: &[Child]) -> Doc {
+        let count = children
+            .iter()
+            .filter(|child| {
+                is_token(child, TokenKind::Upper) || is_token(child, TokenKind::QualifiedUpper)
+            })
+            .count();
+
+        let mut docs = Vec::new();
+        for child in children.iter() {
+            let paren =
+                is_token(child, TokenKind::LeftParen) || is_token(child, TokenKind::RightParen);
+            if paren && count == 1 {
+                docs.push(self.skip());
+                continue;
+            }
+
+            if is_token(child, TokenKind::Lower) || is_token(child, TokenKind::FatArrow) {
+                docs.push(Doc::text(" "));
+            }
+
+            docs.push(self.child(child));
+            if is_token(child, TokenKind::Comma) {
+                docs.push(Doc::text(" "));
+            }
+        }
+
+        Doc::concat(docs)
+    }
+}
+
+/// `head = body`, with the body on the next line if it does not fit.
+fn definition(head: Doc, equals: Doc, body: Doc) -> Doc {
+    let body = Doc::concat([Doc::line(), body]).nest();
+    Doc::concat([head, Doc::text(" "), equals, body]).group()
+}
+
+/// A function applied to arguments, which are indented if they do not fit.
+fn application(func: Doc, args: Vec<Doc>) -> Doc {
+    let args = args.into_iter().map(|arg| Doc::concat([Doc::line(), arg]));
+    Doc::concat([func, Doc::concat(args.collect::<Vec<_>>()).nest()]).group()
+}
+
+fn is_token(child: &Child, kind: TokenKind) -> bool {
+    matches!(child, Child::Token(token) if token.kind == kind)
+}
+
+/// The index of the first token of the given kind among the children.
+fn position(children: &[Child], kind: TokenKind) -> usize {
+    children
+        .iter()
+        .position(|child| is_token(child, kind))
+        .unwrap()
+}
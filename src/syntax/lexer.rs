@@ -0,0 +1,252 @@
+use std::ops::Range;
+
+/// A token of source text. The text between consecutive tokens is trivia:
+/// whitespace and comments.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// The byte range of the token in the source.
+    pub span: Range<usize>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TokenKind {
+    /// A name starting with a lowercase letter or an underscore.
+    Lower,
+    /// A name starting with an uppercase letter.
+    Upper,
+    /// A name qualified by a module, such as `list.map`, whose last part
+    /// starts with a lowercase letter or an underscore.
+    QualifiedLower,
+    /// A name qualified by a module, such as `list.Cons`, whose last part
+    /// starts with an uppercase letter.
+    QualifiedUpper,
+    Integer,
+    String,
+    Regex,
+
+    As,
+    Class,
+    Data,
+    Else,
+    False,
+    Forall,
+    Fun,
+    Impossible,
+    Import,
+    In,
+    Instance,
+    Let,
+    Newtype,
+    Pub,
+    True,
+    Type,
+    Val,
+
+    At,
+    Arrow,
+    Bar,
+    Colon,
+    Comma,
+    Concat,
+    Dot,
+    DotDot,
+    Equals,
+    FatArrow,
+    LeftBrace,
+    LeftBracket,
+    LeftParen,
+    Plus,
+    RightBrace,
+    RightBracket,
+    RightParen,
+    Underscore,
+
+    /// A character which does not start any token.
+    Unknown,
+    /// A string or regex missing its closing delimiter.
+    Unterminated,
+    Eof,
+}
+
+/// Split the given source into tokens, ending with [`TokenKind::Eof`].
+pub fn lex(src: &str) -> Vec<Token> {
+    let mut lexer = Lexer { src, pos: 0 };
+    let mut tokens = Vec::new();
+
+    loop {
+        lexer.skip_trivia();
+        let start = lexer.pos;
+        let kind = lexer.token();
+        tokens.push(Token {
+            kind,
+            span: start..lexer.pos,
+        });
+
+        if kind == TokenKind::Eof {
+            return tokens;
+        }
+    }
+}
+
+struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl Lexer<'_> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.src[self.pos..].chars().nth(1)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat_while(&mut self, f: impl Fn(char) -> bool) {
+        while self.peek().is_some_and(&f) {
+            self.bump();
+        }
+    }
+
+    /// Skip whitespace and `--` line comments.
+    fn skip_trivia(&mut self) {
+        loop {
+            match (self.peek(), self.peek_second()) {
+                (Some(c), _) if c.is_whitespace() => {
+                    self.bump();
+                }
+
+                (Some('-'), Some('-')) => self.eat_while(|c| c != '\n'),
+
+                _ => return,
+            }
+        }
+    }
+
+    fn token(&mut self) -> TokenKind {
+        let Some(c) = self.bump() else {
+            return TokenKind::Eof;
+        };
+
+        match c {
+            c if c.is_alphabetic() || c == '_' => self.name(c),
+
+            '0'..='9' => self.integer(),
+            '-' if self.peek().is_some_and(|c| c.is_ascii_digit()) => self.integer(),
+
+            '"' => self.delimited('"'),
+            '/' => self.delimited('/'),
+
+            '-' if self.peek() == Some('>') => self.then(TokenKind::Arrow),
+            '=' if self.peek() == Some('>') => self.then(TokenKind::FatArrow),
+            '+' if self.peek() == Some('+') => self.then(TokenKind::Concat),
+            '.' if self.peek() == Some('.') => self.then(TokenKind::DotDot),
+
+            '@' => TokenKind::At,
+            '|' => TokenKind::Bar,
+            ':' => TokenKind::Colon,
+            ',' => TokenKind::Comma,
+            '.' => TokenKind::Dot,
+            '=' => TokenKind::Equals,
+            '{' => TokenKind::LeftBrace,
+            '[' => TokenKind::LeftBracket,
+            '(' => TokenKind::LeftParen,
+            '+' => TokenKind::Plus,
+            '}' => TokenKind::RightBrace,
+            ']' => TokenKind::RightBracket,
+            ')' => TokenKind::RightParen,
+
+            _ => TokenKind::Unknown,
+        }
+    }
+
+    /// Lex a name, which is qualified if it starts with a lowercase module
+    /// name directly followed by a `.` and another name.
+    fn name(&mut self, first: char) -> TokenKind {
+        let start = self.pos - first.len_utf8();
+        let is_name = |c: char| c.is_alphanumeric() || c == '_' || c == '\'';
+        self.eat_while(is_name);
+
+        let mut last = first;
+        let mut qualified = false;
+        while !first.is_uppercase()
+            && self.peek() == Some('.')
+            && self.peek_second().is_some_and(char::is_alphabetic)
+        {
+            self.bump();
+            last = self.bump().unwrap();
+            self.eat_while(is_name);
+            qualified = true;
+        }
+
+        match (qualified, last.is_uppercase()) {
+            (true, true) => TokenKind::QualifiedUpper,
+            (true, false) => TokenKind::QualifiedLower,
+            (false, true) => keyword(&self.src[start..self.pos]).unwrap_or(TokenKind::Upper),
+            (false, false) => keyword(&self.src[start..self.pos]).unwrap_or(TokenKind::Lower),
+        }
+    }
+
+    fn then(&mut self, kind: TokenKind) -> TokenKind {
+        self.bump();
+        kind
+    }
+
+    fn integer(&mut self) -> TokenKind {
+        self.eat_while(|c| c.is_ascii_digit());
+        TokenKind::Integer
+    }
+
+    /// Lex a string or regex up to its closing delimiter, skipping escaped
+    /// characters.
+    fn delimited(&mut self, close: char) -> TokenKind {
+        loop {
+            match self.bump() {
+                Some('\\') => {
+                    self.bump();
+                }
+
+                Some('\n') | None => return TokenKind::Unterminated,
+                Some(c) if c == close => {
+                    return match close {
+                        '"' => TokenKind::String,
+                        _ => TokenKind::Regex,
+                    }
+                }
+
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+fn keyword(name: &str) -> Option<TokenKind> {
+    Some(match name {
+        "as" => TokenKind::As,
+        "class" => TokenKind::Class,
+        "data" => TokenKind::Data,
+        "else" => TokenKind::Else,
+        "false" => TokenKind::False,
+        "forall" => TokenKind::Forall,
+        "fun" => TokenKind::Fun,
+        "impossible" => TokenKind::Impossible,
+        "import" => TokenKind::Import,
+        "in" => TokenKind::In,
+        "instance" => TokenKind::Instance,
+        "let" => TokenKind::Let,
+        "newtype" => TokenKind::Newtype,
+        "pub" => TokenKind::Pub,
+        "true" => TokenKind::True,
+        "type" => TokenKind::Type,
+        "val" => TokenKind::Val,
+        "_" => TokenKind::Underscore,
+        _ => return None,
+    })
+}
@@ -0,0 +1,833 @@
+use indexmap::{IndexMap, IndexSet};
+
+use super::cst::{Builder, Node, NodeKind, Token};
+use super::lexer::{lex, TokenKind};
+use crate::hir;
+use crate::hir::visit::{walk_type, Visitor};
+use crate::mir::Name;
+use crate::Diagnostic;
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+/// A recursive descent parser, which stops at the first syntax error. The
+/// concrete syntax tree is built alongside the declarations.
+pub struct Parser<'a> {
+    src: &'a str,
+    tokens: Vec<super::lexer::Token>,
+    pos: usize,
+    builder: Builder,
+    /// Whether the end of input has been added to the syntax tree.
+    at_end: bool,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            tokens: lex(src),
+            pos: 0,
+            builder: Builder::new(),
+            at_end: false,
+        }
+    }
+
+    /// The concrete syntax tree of everything parsed so far.
+    pub fn into_cst(self) -> Node {
+        self.builder.build()
+    }
+
+    fn peek(&self) -> TokenKind {
+        self.tokens[self.pos].kind
+    }
+
+    fn text(&self) -> &'a str {
+        &self.src[self.tokens[self.pos].span.clone()]
+    }
+
+    /// Move past the current token, adding it to the syntax tree along with
+    /// the trivia before it. The end of input is only added once.
+    fn bump(&mut self) -> &'a str {
+        let text = self.text();
+        let kind = self.peek();
+        let previous = match self.pos {
+            0 => 0,
+            pos => self.tokens[pos - 1].span.end,
+        };
+
+        let trivia = &self.src[previous..self.tokens[self.pos].span.start];
+        if kind != TokenKind::Eof || !self.at_end {
+            self.builder.token(Token {
+                kind,
+                trivia: trivia.into(),
+                text: text.into(),
+            });
+        }
+
+        match kind {
+            TokenKind::Eof => self.at_end = true,
+            _ => self.pos += 1,
+        }
+
+        text
+    }
+
+    fn eat(&mut self, kind: TokenKind) -> bool {
+        let found = self.peek() == kind;
+        if found {
+            self.bump();
+        }
+
+        found
+    }
+
+    fn expect(&mut self, kind: TokenKind, what: &str) -> Result<&'a str> {
+        if self.peek() == kind {
+            Ok(self.bump())
+        } else {
+            Err(self.error(what))
+        }
+    }
+
+    /// A diagnostic for the current token, which is not what was expected.
+    fn error(&self, expected: &str) -> Diagnostic {
+        let found = match self.peek() {
+            TokenKind::Eof => "the end of the input".to_string(),
+            TokenKind::Unterminated => "an unterminated literal".to_string(),
+            _ => format!("`{}`", self.text()),
+        };
+
+        self.error_here(format!("expected {expected}, found {found}"))
+    }
+
+    /// Classify a diagnostic of this parser, which is incomplete if it was
+    /// reported at the end of the input.
+    pub fn failure(&self, diagnostic: Diagnostic) -> super::Error {
+        match self.peek() {
+            TokenKind::Eof => super::Error::Incomplete(diagnostic),
+            _ => super::Error::Invalid(diagnostic),
+        }
+    }
+
+    fn error_here(&self, message: String) -> Diagnostic {
+        let (line, column) = super::position(self.src, self.tokens[self.pos].span.start);
+        Diagnostic::new(message).with_note(format!("at line {line}, column {column}"))
+    }
+
+    pub fn decls(&mut self) -> Result<hir::Decls> {
+        let mut decls = hir::Decls {
+            imports: Vec::new(),
+            exports: IndexSet::new(),
+            types: IndexMap::new(),
+            classes: IndexMap::new(),
+            instances: Vec::new(),
+            values: IndexMap::new(),
+        };
+
+        loop {
+            let start = self.pos;
+            let public = self.peek() == TokenKind::Pub;
+            let keyword = self.tokens[self.pos + usize::from(public)].kind;
+            let named = matches!(
+                keyword,
+                TokenKind::Data
+                    | TokenKind::Type
+                    | TokenKind::Newtype
+                    | TokenKind::Class
+                    | TokenKind::Val
+            );
+
+            if public && !named {
+                self.pos += 1;
+                return Err(self.error("a named declaration after `pub`"));
+            }
+
+            let (name, defined) = match keyword {
+                TokenKind::Import => {
+                    decls.imports.push(self.import()?);
+                    continue;
+                }
+
+                TokenKind::Data => {
+                    let (name, def) = self.data_def()?;
+                    let defined = decls.types.insert(name.clone(), def).is_some();
+                    (name, defined)
+                }
+
+                TokenKind::Type => {
+                    let (name, def) = self.alias_def()?;
+                    let defined = decls.types.insert(name.clone(), def).is_some();
+                    (name, defined)
+                }
+
+                TokenKind::Newtype => {
+                    let (name, def) = self.newtype_def()?;
+                    let defined = decls.types.insert(name.clone(), def).is_some();
+                    (name, defined)
+                }
+
+                TokenKind::Class => {
+                    let (name, def) = self.class_def()?;
+                    let defined = decls.classes.insert(name.clone(), def).is_some();
+                    (name, defined)
+                }
+
+                TokenKind::Instance => {
+                    decls.instances.push(self.instance_def()?);
+                    continue;
+                }
+
+                TokenKind::Val => {
+                    let (name, def) = self.value_def()?;
+                    let defined = decls.values.insert(name.clone(), def).is_some();
+                    (name, defined)
+                }
+
+                TokenKind::Eof => {
+                    self.bump();
+                    return Ok(decls);
+                }
+                _ => return Err(self.error("a declaration")),
+            };
+
+            if defined {
+                self.pos = start + usize::from(public) + 1;
+                return Err(self.error_here(format!("`{name}` is defined more than once")));
+            }
+
+            if public {
+                decls.exports.insert(name);
+            }
+        }
+    }
+
+    /// `import data.list as list`, where the alias is optional.
+    fn import(&mut self) -> Result<hir::Import> {
+        self.builder.start(NodeKind::ImportDecl);
+        self.bump();
+        let module = match self.peek() {
+            TokenKind::Lower | TokenKind::QualifiedLower => self.bump().to_string(),
+            _ => return Err(self.error("a module name")),
+        };
+
+        let alias = match self.eat(TokenKind::As) {
+            true => Some(self.expect(TokenKind::Lower, "a module alias")?.to_string()),
+            false => None,
+        };
+
+        self.builder.finish();
+        Ok(hir::Import { module, alias })
+    }
+
+    /// Move past the keyword starting a declaration, along with `pub` before
+    /// it.
+    fn keyword(&mut self) {
+        self.eat(TokenKind::Pub);
+        self.bump();
+    }
+
+    /// The name of a class, which may be qualified by a module.
+    fn class_name(&mut self) -> Result<Name> {
+        match self.peek() {
+            TokenKind::Upper | TokenKind::QualifiedUpper => Ok(self.bump().to_string()),
+            _ => Err(self.error("a class name")),
+        }
+    }
+
+    /// `data List a = Nil | Cons a (List a)`
+    fn data_def(&mut self) -> Result<(Name, hir::TypeDef)> {
+        self.builder.start(NodeKind::DataDecl);
+        self.keyword();
+        let name = self.expect(TokenKind::Upper, "a type name")?.to_string();
+        let vars = self.type_vars();
+        self.expect(TokenKind::Equals, "`=`")?;
+
+        let mut ctors = Vec::new();
+        loop {
+            self.builder.start(NodeKind::Ctor);
+            let name = self
+                .expect(TokenKind::Upper, "a constructor name")?
+                .to_string();
+            let mut fields = Vec::new();
+            while self.starts_atype() {
+                fields.push(self.atype()?);
+            }
+
+            self.builder.finish();
+            ctors.push(hir::Constructor { name, fields });
+            if !self.eat(TokenKind::Bar) {
+                break;
+            }
+        }
+
+        self.builder.finish();
+        Ok((name, hir::TypeDef::Data(hir::DataDef { vars, ctors })))
+    }
+
+    /// `type Port = 1..65536`
+    fn alias_def(&mut self) -> Result<(Name, hir::TypeDef)> {
+        self.builder.start(NodeKind::AliasDecl);
+        self.keyword();
+        let name = self.expect(TokenKind::Upper, "a type name")?.to_string();
+        let vars = self.type_vars();
+        self.expect(TokenKind::Equals, "`=`")?;
+        let body = self.ty()?;
+        self.builder.finish();
+        Ok((name, hir::TypeDef::Alias(hir::AliasDef { vars, body })))
+    }
+
+    /// `newtype UserId = 0..1000000`
+    fn newtype_def(&mut self) -> Result<(Name, hir::TypeDef)> {
+        self.builder.start(NodeKind::NewtypeDecl);
+        self.keyword();
+        let name = self.expect(TokenKind::Upper, "a type name")?.to_string();
+        let vars = self.type_vars();
+        self.expect(TokenKind::Equals, "`=`")?;
+        let repr = self.ty()?;
+        self.builder.finish();
+        Ok((name, hir::TypeDef::Newtype(hir::NewtypeDef { vars, repr })))
+    }
+
+    fn type_vars(&mut self) -> Vec<Name> {
+        let mut vars = Vec::new();
+        while self.peek() == TokenKind::Lower {
+            vars.push(self.bump().to_string());
+        }
+
+        vars
+    }
+
+    /// `class Eq a { eq : a -> a -> Bool }`
+    fn class_def(&mut self) -> Result<(Name, hir::ClassDef)> {
+        self.builder.start(NodeKind::ClassDecl);
+        self.keyword();
+        let name = self.expect(TokenKind::Upper, "a class name")?.to_string();
+        let var = self
+            .expect(TokenKind::Lower, "a type variable")?
+            .to_string();
+        let methods = self.braced(|this| {
+            this.builder.start(NodeKind::Signature);
+            let name = this.expect(TokenKind::Lower, "a method name")?.to_string();
+            this.expect(TokenKind::Colon, "`:`")?;
+            let ty = this.ty()?;
+            this.builder.finish();
+            Ok((name, ty))
+        })?;
+
+        self.builder.finish();
+        Ok((name, hir::ClassDef { var, methods }))
+    }
+
+    /// `instance Eq a => Eq (List a) { eq = ... }`
+    fn instance_def(&mut self) -> Result<hir::InstanceDef> {
+        self.builder.start(NodeKind::InstanceDecl);
+        self.bump();
+
+        // There are bounds if there is a `=>` before the methods.
+        let has_bounds = self.tokens[self.pos..]
+            .iter()
+            .take_while(|token| !matches!(token.kind, TokenKind::LeftBrace | TokenKind::Eof))
+            .any(|token| token.kind == TokenKind::FatArrow);
+        let bounds = match has_bounds {
+            true => self.bounds()?,
+            false => Vec::new(),
+        };
+
+        let class = self.class_name()?;
+        let ty = self.atype()?;
+        let methods = self.braced(|this| {
+            this.builder.start(NodeKind::Method);
+            let name = this.expect(TokenKind::Lower, "a method name")?.to_string();
+            this.expect(TokenKind::Equals, "`=`")?;
+            let body = this.expr()?;
+            this.builder.finish();
+            Ok((name, body))
+        })?;
+
+        self.builder.finish();
+
+        let mut names = Vec::new();
+        type_vars_of(&ty, &mut names);
+        let vars = names
+            .into_iter()
+            .map(|name| {
+                let bounds = bounds
+                    .iter()
+                    .filter(|(_, var)| *var == name)
+                    .map(|(class, _)| class.clone())
+                    .collect();
+                hir::TypeParam { name, bounds }
+            })
+            .collect();
+
+        Ok(hir::InstanceDef {
+            vars,
+            class,
+            ty,
+            methods,
+        })
+    }
+
+    /// `Eq a =>` or `(Eq a, Show b) =>`, as pairs of a class and a type
+    /// variable.
+    fn bounds(&mut self) -> Result<Vec<(Name, Name)>> {
+        self.builder.start(NodeKind::Bounds);
+        let parens = self.eat(TokenKind::LeftParen);
+        let mut bounds = Vec::new();
+        loop {
+            let class = self.class_name()?;
+            let var = self
+                .expect(TokenKind::Lower, "a type variable")?
+                .to_string();
+            bounds.push((class, var));
+
+            if !parens || !self.eat(TokenKind::Comma) {
+                break;
+            }
+        }
+
+        if parens {
+            self.expect(TokenKind::RightParen, "`)`")?;
+        }
+
+        self.expect(TokenKind::FatArrow, "`=>`")?;
+        self.builder.finish();
+        Ok(bounds)
+    }
+
+    /// `val f [a: Show + Eq, b] : a -> b -> String = ...`, where the type
+    /// parameters and annotation are optional.
+    fn value_def(&mut self) -> Result<(Name, hir::ValueDef)> {
+        self.builder.start(NodeKind::ValDecl);
+        self.keyword();
+        let name = self.expect(TokenKind::Lower, "a value name")?.to_string();
+
+        let mut vars = Vec::new();
+        if self.peek() == TokenKind::LeftBracket {
+            self.builder.start(NodeKind::TypeParams);
+            self.bump();
+            loop {
+                self.builder.start(NodeKind::TypeParam);
+                let name = self
+                    .expect(TokenKind::Lower, "a type variable")?
+                    .to_string();
+                let mut bounds = Vec::new();
+                if self.eat(TokenKind::Colon) {
+                    loop {
+                        bounds.push(self.class_name()?);
+                        if !self.eat(TokenKind::Plus) {
+                            break;
+                        }
+                    }
+                }
+
+                self.builder.finish();
+                vars.push(hir::TypeParam { name, bounds });
+                if !self.eat(TokenKind::Comma) {
+                    break;
+                }
+            }
+
+            self.expect(TokenKind::RightBracket, "`]`")?;
+            self.builder.finish();
+        }
+
+        let anno = if self.eat(TokenKind::Colon) {
+            self.ty()?
+        } else {
+            hir::Type::Wildcard
+        };
+
+        self.expect(TokenKind::Equals, "`=`")?;
+        let body = self.expr()?;
+        self.builder.finish();
+        Ok((name, hir::ValueDef { vars, anno, body }))
+    }
+
+    /// A comma separated list of items in braces, which may end with a comma.
+    fn braced<T>(&mut self, mut item: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        self.expect(TokenKind::LeftBrace, "`{`")?;
+        let mut items = Vec::new();
+        while !self.eat(TokenKind::RightBrace) {
+            items.push(item(self)?);
+            if !self.eat(TokenKind::Comma) {
+                self.expect(TokenKind::RightBrace, "`,` or `}`")?;
+                break;
+            }
+        }
+
+        Ok(items)
+    }
+
+    fn ty(&mut self) -> Result<hir::Type> {
+        let checkpoint = self.builder.checkpoint();
+        if self.eat(TokenKind::Forall) {
+            let mut vars = vec![self
+                .expect(TokenKind::Lower, "a type variable")?
+                .to_string()];
+            vars.extend(self.type_vars());
+            self.expect(TokenKind::Dot, "`.`")?;
+            let body = self.ty()?;
+            self.builder.wrap(checkpoint, NodeKind::ForallType);
+            return Ok(hir::Type::Forall(vars, Box::new(body)));
+        }
+
+        let mut ty = self.atype()?;
+        while self.starts_atype() {
+            let arg = self.atype()?;
+            self.builder.wrap(checkpoint, NodeKind::ApplyType);
+            ty = hir::Type::Apply(Box::new(ty), Box::new(arg));
+        }
+
+        if self.eat(TokenKind::Arrow) {
+            let into = self.ty()?;
+            self.builder.wrap(checkpoint, NodeKind::ArrowType);
+            ty = hir::Type::Arrow(Box::new(ty), Box::new(into));
+        }
+
+        Ok(ty)
+    }
+
+    fn starts_atype(&self) -> bool {
+        matches!(
+            self.peek(),
+            TokenKind::Upper
+                | TokenKind::QualifiedUpper
+                | TokenKind::Lower
+                | TokenKind::Integer
+                | TokenKind::Regex
+                | TokenKind::Underscore
+                | TokenKind::LeftParen
+        )
+    }
+
+    fn atype(&mut self) -> Result<hir::Type> {
+        Ok(match self.peek() {
+            TokenKind::Upper => match self.bump() {
+                "Bool" => hir::Type::Bool,
+                "Regex" => hir::Type::Regex,
+                name => hir::Type::Named(name.to_string()),
+            },
+
+            TokenKind::Lower | TokenKind::QualifiedUpper => {
+                hir::Type::Named(self.bump().to_string())
+            }
+
+            TokenKind::Integer => {
+                self.builder.start(NodeKind::RangeType);
+                let lo = self.integer()?;
+                self.expect(TokenKind::DotDot, "`..`")?;
+                if self.peek() != TokenKind::Integer {
+                    return Err(self.error("an integer"));
+                }
+
+                let hi = self.integer()?;
+                self.builder.finish();
+                hir::Type::Range(lo, hi)
+            }
+
+            TokenKind::Regex => hir::Type::String(regex(self.bump())),
+
+            TokenKind::Underscore => {
+                self.bump();
+                hir::Type::Wildcard
+            }
+
+            TokenKind::LeftParen => {
+                self.builder.start(NodeKind::ParenType);
+                self.bump();
+                let ty = self.ty()?;
+                self.expect(TokenKind::RightParen, "`)`")?;
+                self.builder.finish();
+                ty
+            }
+
+            _ => return Err(self.error("a type")),
+        })
+    }
+
+    fn integer(&mut self) -> Result<i64> {
+        let text = self.text();
+        let v = text
+            .parse()
+            .map_err(|_| self.error_here(format!("the integer `{text}` is too large")))?;
+        self.bump();
+        Ok(v)
+    }
+
+    /// Parse an expression which makes up the whole input.
+    pub fn whole_expr(&mut self) -> Result<hir::Expr> {
+        let expr = self.expr()?;
+        self.expect(TokenKind::Eof, "the end of the input")?;
+        Ok(expr)
+    }
+
+    fn expr(&mut self) -> Result<hir::Expr> {
+        match self.peek() {
+            TokenKind::Fun => {
+                self.builder.start(NodeKind::FunExpr);
+                self.bump();
+                let pat = self.pat()?;
+                self.expect(TokenKind::FatArrow, "`=>`")?;
+                let body = self.expr()?;
+                self.builder.finish();
+                Ok(hir::Expr::Fun(pat, Box::new(body)))
+            }
+
+            // A `let` without an else branch asserts that its pattern always
+            // matches.
+            TokenKind::Let => {
+                self.builder.start(NodeKind::LetExpr);
+                self.bump();
+                let pat = self.pat()?;
+                self.expect(TokenKind::Equals, "`=`")?;
+                let bound = self.expr()?;
+                self.expect(TokenKind::In, "`in`")?;
+                let then = self.expr()?;
+                let elze = if self.eat(TokenKind::Else) {
+                    self.expr()?
+                } else {
+                    hir::Expr::Impossible
+                };
+
+                self.builder.finish();
+                Ok(hir::Expr::Let {
+                    pat,
+                    bound: Box::new(bound),
+                    then: Box::new(then),
+                    elze: Box::new(elze),
+                })
+            }
+
+            _ => {
+                let checkpoint = self.builder.checkpoint();
+                let mut expr = self.app_expr()?;
+                while self.eat(TokenKind::Concat) {
+                    let rhs = self.app_expr()?;
+                    self.builder.wrap(checkpoint, NodeKind::ConcatExpr);
+                    expr = hir::Expr::Binary(hir::BinOp::Concat, Box::new(expr), Box::new(rhs));
+                }
+
+                Ok(expr)
+            }
+        }
+    }
+
+    fn app_expr(&mut self) -> Result<hir::Expr> {
+        let checkpoint = self.builder.checkpoint();
+        let mut expr = self.atom()?;
+        while self.starts_atom() {
+            let arg = self.atom()?;
+            self.builder.wrap(checkpoint, NodeKind::CallExpr);
+            expr = hir::Expr::Call(Box::new(expr), Box::new(arg));
+        }
+
+        Ok(expr)
+    }
+
+    fn starts_atom(&self) -> bool {
+        matches!(
+            self.peek(),
+            TokenKind::Lower
+                | TokenKind::Upper
+                | TokenKind::QualifiedLower
+                | TokenKind::QualifiedUpper
+                | TokenKind::Integer
+                | TokenKind::String
+                | TokenKind::Regex
+                | TokenKind::True
+                | TokenKind::False
+                | TokenKind::Impossible
+                | TokenKind::LeftParen
+        )
+    }
+
+    fn atom(&mut self) -> Result<hir::Expr> {
+        Ok(match self.peek() {
+            TokenKind::Lower
+            | TokenKind::Upper
+            | TokenKind::QualifiedLower
+            | TokenKind::QualifiedUpper => {
+                let checkpoint = self.builder.checkpoint();
+                let name = self.bump().to_string();
+                let mut args = Vec::new();
+                while self.eat(TokenKind::At) {
+                    args.push(self.atype()?);
+                }
+
+                if args.is_empty() {
+                    hir::Expr::Name(name)
+                } else {
+                    self.builder.wrap(checkpoint, NodeKind::InstantiateExpr);
+                    hir::Expr::Instantiate(name, args)
+                }
+            }
+
+            TokenKind::Impossible => {
+                self.bump();
+                hir::Expr::Impossible
+            }
+
+            TokenKind::LeftParen => {
+                self.builder.start(NodeKind::ParenExpr);
+                self.bump();
+                let mut expr = self.expr()?;
+                if self.eat(TokenKind::Colon) {
+                    let ty = self.ty()?;
+                    expr = hir::Expr::Anno(Box::new(expr), ty);
+                }
+
+                self.expect(TokenKind::RightParen, "`)`")?;
+                self.builder.finish();
+                expr
+            }
+
+            _ => match self.literal()? {
+                Some(lit) => hir::Expr::Lit(lit),
+                None => return Err(self.error("an expression")),
+            },
+        })
+    }
+
+    fn literal(&mut self) -> Result<Option<hir::Literal>> {
+        Ok(Some(match self.peek() {
+            TokenKind::True => {
+                self.bump();
+                hir::Literal::Boolean(true)
+            }
+
+            TokenKind::False => {
+                self.bump();
+                hir::Literal::Boolean(false)
+            }
+
+            TokenKind::Integer => hir::Literal::Integer(self.integer()?),
+            TokenKind::String => hir::Literal::String(string(self.bump())),
+            TokenKind::Regex => hir::Literal::Regex(regex(self.bump())),
+            _ => return Ok(None),
+        }))
+    }
+
+    /// A pattern, where constructors take their arguments.
+    fn pat(&mut self) -> Result<hir::Pat> {
+        let checkpoint = self.builder.checkpoint();
+        let mut pat = self.apat()?;
+        if let hir::Pat::Constructor(_) = pat {
+            while self.starts_apat() {
+                let arg = self.apat()?;
+                self.builder.wrap(checkpoint, NodeKind::ApplyPat);
+                pat = hir::Pat::Apply(Box::new(pat), Box::new(arg));
+            }
+        }
+
+        Ok(pat)
+    }
+
+    fn starts_apat(&self) -> bool {
+        matches!(
+            self.peek(),
+            TokenKind::Lower
+                | TokenKind::Upper
+                | TokenKind::QualifiedUpper
+                | TokenKind::Underscore
+                | TokenKind::Integer
+                | TokenKind::String
+                | TokenKind::Regex
+                | TokenKind::True
+                | TokenKind::False
+                | TokenKind::LeftParen
+        )
+    }
+
+    fn apat(&mut self) -> Result<hir::Pat> {
+        Ok(match self.peek() {
+            TokenKind::Lower => hir::Pat::Bind(self.bump().to_string()),
+            TokenKind::Upper | TokenKind::QualifiedUpper => {
+                hir::Pat::Constructor(self.bump().to_string())
+            }
+
+            TokenKind::Underscore => {
+                self.bump();
+                hir::Pat::Wildcard
+            }
+
+            TokenKind::LeftParen => {
+                self.builder.start(NodeKind::ParenPat);
+                self.bump();
+                let pat = self.pat()?;
+                self.expect(TokenKind::RightParen, "`)`")?;
+                self.builder.finish();
+                pat
+            }
+
+            _ => match self.literal()? {
+                Some(lit) => hir::Pat::Lit(lit),
+                None => return Err(self.error("a pattern")),
+            },
+        })
+    }
+}
+
+/// The type variables occuring in the given type, in order.
+fn type_vars_of(ty: &hir::Type, vars: &mut Vec<Name>) {
+    struct TypeVars<'a> {
+        vars: &'a mut Vec<Name>,
+        /// The variables bound by the `forall` types around the current one.
+        bound: Vec<Name>,
+    }
+
+    impl Visitor for TypeVars<'_> {
+        fn visit_type(&mut self, ty: &hir::Type) {
+            match ty {
+                hir::Type::Named(name) => {
+                    let is_var =
+                        !name.contains('.') && name.starts_with(|c: char| !c.is_uppercase());
+                    if is_var && !self.bound.contains(name) && !self.vars.contains(name) {
+                        self.vars.push(name.clone());
+                    }
+                }
+
+                hir::Type::Forall(bound, ty) => {
+                    let len = self.bound.len();
+                    self.bound.extend(bound.iter().cloned());
+                    self.visit_type(ty);
+                    self.bound.truncate(len);
+                }
+
+                ty => walk_type(self, ty),
+            }
+        }
+    }
+
+    let mut visitor = TypeVars {
+        vars,
+        bound: Vec::new(),
+    };
+    visitor.visit_type(ty);
+}
+
+/// The contents of a string literal, with escapes replaced.
+fn string(text: &str) -> String {
+    let mut chars = text[1..text.len() - 1].chars();
+    let mut value = String::new();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some(c) => value.push(c),
+            None => {}
+        }
+    }
+
+    value
+}
+
+/// The pattern of a regex literal. Escapes are left for the regex, except for
+/// escaped slashes.
+fn regex(text: &str) -> String {
+    text[1..text.len() - 1].replace("\\/", "/")
+}
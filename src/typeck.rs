@@ -1,153 +1,506 @@
 mod assign;
 mod bind;
 mod check;
+mod class;
 mod context;
+mod data;
 mod infer;
 mod lower;
+mod order;
+mod pattern;
+mod prelude;
+mod provenance;
+mod resolve;
 mod solve;
+mod subst;
 mod substitute;
+mod trace;
 mod tween;
 mod types;
 
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 
 use bimap::BiMap;
+use indexmap::IndexMap;
 use log::{debug, trace};
 
-use self::solve::Constraint;
+use self::solve::Worklist;
+use self::subst::Subst;
 use self::tween::Mutability;
-use self::types::{Type, TypeVar};
+use self::types::{Type, TypeVar, VarNames};
 use crate::hir;
 use crate::mir;
 use crate::types as varless;
+use crate::{Diagnostic, Regex};
 
-use context::Template;
+use class::{Class, Instance};
+use context::{Bound, Template};
+use data::TypeDef;
+use provenance::{Rule, Solution};
+use trace::ConstraintId;
 
-pub fn typeck(prog: hir::Decls) -> mir::Program {
-    debug!("Declaring");
+pub use trace::{Event, Step, Trace};
+
+/// Type check the given declarations, producing a typed program along with any
+/// problems found in it.
+pub fn typeck(prog: hir::Decls) -> (mir::Program, Vec<Diagnostic>) {
+    let (prog, diagnostics, _) = check(prog, None);
+    (prog, diagnostics)
+}
+
+/// Type check the given declarations like [`typeck`], also recording how each
+/// constraint was generated and solved.
+pub fn typeck_traced(prog: hir::Decls) -> (mir::Program, Vec<Diagnostic>, Trace) {
+    let (prog, diagnostics, trace) = check(prog, Some(Trace::new()));
+    (prog, diagnostics, trace.unwrap())
+}
+
+fn check(prog: hir::Decls, trace: Option<Trace>) -> (mir::Program, Vec<Diagnostic>, Option<Trace>) {
     let mut checker = Checker::new();
-    for (name, item) in prog.values.iter() {
-        checker.declare(name.clone(), item.vars.clone(), &item.anno);
+    checker.trace = trace;
+    let prog = checker.check_program(prog);
+    let diagnostics = std::mem::take(&mut checker.diagnostics);
+    if let Some(trace) = &mut checker.trace {
+        trace.solutions = checker.solutions;
     }
 
-    trace!("Declared types {:?}", checker.context);
+    (prog, diagnostics, checker.trace)
+}
+
+/// A program checked a batch of declarations at a time, such as those entered
+/// at a prompt. Each batch is checked along with the declarations added before
+/// it, without checking those again.
+pub struct Session {
+    checker: Checker,
+    /// Every declaration added so far.
+    decls: hir::Decls,
+    program: mir::Program,
+}
 
-    debug!("Defining & solving");
-    let mut values = HashMap::with_capacity(prog.values.len());
-    for (name, item) in prog.values {
-        let body = checker.define(&name, item.body);
-        values.insert(name, body);
+impl Session {
+    pub fn new() -> Self {
+        let (session, _) = Self::start(hir::Decls::default());
+        session
     }
 
-    if !checker.solve_constraints() {
-        trace!("Unsolved constraints {:?}", checker.worklist);
-        trace!("Types {:?}", checker.context);
-        panic!("unsolved constraints!");
+    /// Start a session with the given declarations, along with any problems
+    /// found in them.
+    fn start(decls: hir::Decls) -> (Self, Vec<Diagnostic>) {
+        let mut checker = Checker::new();
+        let program = checker.check_program(decls.clone());
+        let diagnostics = std::mem::take(&mut checker.diagnostics);
+        let session = Self {
+            checker,
+            decls,
+            program,
+        };
+
+        (session, diagnostics)
     }
 
-    debug!("Substituting & memoizing");
-    let context = checker.subst_ctx();
-    let values = values
-        .into_iter()
-        .map(|(name, expr)| {
-            let expr = checker.substitute(expr);
-            (name, expr)
-        })
-        .collect();
-    let types = checker.lower.into_iter().collect();
+    /// The typed program of every declaration added so far.
+    pub fn program(&self) -> &mir::Program {
+        &self.program
+    }
+
+    /// Add the given declarations if they check along with the earlier ones,
+    /// or give the problems found in them. A declaration of a name which was
+    /// already declared replaces the earlier one, which means checking every
+    /// declaration again, since others may depend on it.
+    pub fn add(&mut self, decls: hir::Decls) -> Result<(), Vec<Diagnostic>> {
+        if self.redeclares(&decls) {
+            let mut all = self.decls.clone();
+            all.merge(decls);
+            let (session, diagnostics) = Self::start(all);
+            if !diagnostics.is_empty() {
+                return Err(diagnostics);
+            }
+
+            *self = session;
+            return Ok(());
+        }
 
-    mir::Program {
-        context,
-        types,
-        decls: mir::Decls { values },
+        let checker = self.checker.clone();
+        let batch = self.checker.check_batch(decls.clone());
+        let diagnostics = std::mem::take(&mut self.checker.diagnostics);
+        if !diagnostics.is_empty() {
+            self.checker = checker;
+            return Err(diagnostics);
+        }
+
+        extend(&mut self.program, batch);
+        self.decls.merge(decls);
+        Ok(())
+    }
+
+    /// Check a value which is not yet declared along with the declarations so
+    /// far, and give the program including it to `f`. The value is not added
+    /// to the session.
+    pub fn with_value<T>(
+        &mut self,
+        name: mir::Name,
+        def: hir::ValueDef,
+        f: impl FnOnce(&mir::Program) -> T,
+    ) -> Result<T, Vec<Diagnostic>> {
+        assert!(
+            !self.checker.context.contains_key(&name),
+            "`{name}` is already declared"
+        );
+
+        let mut decls = hir::Decls::default();
+        decls.values.insert(name.clone(), def);
+        let checker = self.checker.clone();
+        let batch = self.checker.check_batch(decls);
+        let diagnostics = std::mem::take(&mut self.checker.diagnostics);
+        if !diagnostics.is_empty() {
+            self.checker = checker;
+            return Err(diagnostics);
+        }
+
+        // The types the value needed are kept, since they may be shared with
+        // later declarations.
+        extend(&mut self.program, batch);
+        let result = f(&self.program);
+        self.checker.context.shift_remove(&name);
+        self.program.context.shift_remove(&name);
+        self.program.decls.values.shift_remove(&name);
+        Ok(result)
+    }
+
+    /// Whether any of the given declarations declares a name again, including
+    /// a name of the prelude.
+    fn redeclares(&self, decls: &hir::Decls) -> bool {
+        let checker = &self.checker;
+        let declared = |name: &mir::Name| {
+            checker.context.contains_key(name)
+                || checker.types.contains_key(name)
+                || checker.classes.contains_key(name)
+        };
+
+        decls.types.keys().any(declared)
+            || decls.classes.keys().any(declared)
+            || decls.values.keys().any(declared)
+            || decls
+                .types
+                .iter()
+                .any(|(name, def)| prelude::ctors(name, def).any(declared))
+            || decls
+                .classes
+                .values()
+                .any(|def| def.methods.iter().any(|(name, _)| declared(name)))
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-#[derive(Debug)]
+/// Add the declarations of a batch to the program checked before it.
+fn extend(program: &mut mir::Program, batch: mir::Program) {
+    program.context.extend(batch.context);
+    program.decls.types.extend(batch.decls.types);
+    program.decls.values.extend(batch.decls.values);
+    for (id, ty) in batch.types.iter() {
+        program.types.add(id, ty.clone());
+    }
+}
+
+#[derive(Clone, Debug)]
 struct Checker {
-    context: HashMap<mir::Name, Template>,
-    subst: HashMap<TypeVar, Type>,
+    /// The type of every global name, in the order they were declared.
+    context: IndexMap<mir::Name, Template>,
+    /// The builtins of the prelude in scope, which are not hidden by a
+    /// declaration of the program.
+    builtins: HashSet<mir::Name>,
+    /// The type of every name bound by a pattern in the declaration being
+    /// checked. Binders are resolved to distinct identifiers beforehand, so
+    /// these never shadow each other.
+    locals: HashMap<mir::Name, Type>,
+    subst: Subst,
+    /// The name of the type variable each rigid type variable was
+    /// skolemised from.
+    skolems: HashMap<TypeVar, mir::Name>,
+
+    /// Every declared type alias and data type.
+    types: HashMap<mir::Name, TypeDef>,
+    /// The data type each constructor belongs to.
+    ctors: HashMap<mir::Name, mir::Name>,
+
+    classes: HashMap<mir::Name, Class>,
+    /// The instances of every class.
+    instances: HashMap<mir::Name, Vec<Instance>>,
+    /// The bounds whose dictionaries are in scope.
+    given: Vec<Bound>,
+    /// The instance each dictionary resolved to.
+    dicts: Vec<Option<tween::Expr>>,
 
     lower: BiMap<varless::TypeId, varless::Type>,
 
-    curr_tyvar: TypeVar,
-    worklist: Vec<Constraint>,
+    worklist: Worklist,
+    /// The typing rule currently producing constraints.
+    rule: Rule,
+    /// Every solution of a type variable, in the order they were made.
+    solutions: Vec<Solution>,
+    /// The number of constraints generated so far.
+    constraints: usize,
+    /// The constraint currently being solved, if any.
+    solving: Option<ConstraintId>,
+    /// Whether the constraint currently being solved has been blocked again.
+    deferred: bool,
+    /// The events recorded so far, if tracing is enabled.
+    trace: Option<Trace>,
+
+    /// Every successfully compiled regex, keyed by its source pattern.
+    regexes: HashMap<String, Regex>,
+
+    /// The names given to type variables in the diagnostic being built.
+    var_names: RefCell<VarNames>,
+
+    /// The top-level declaration currently being checked.
+    current: Option<mir::Name>,
+    /// The expression currently being checked, printed, if tracing is
+    /// enabled.
+    origin: Option<String>,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl Checker {
     pub fn new() -> Self {
         Self {
-            context: HashMap::new(),
-            subst: HashMap::new(),
+            context: IndexMap::new(),
+            builtins: HashSet::new(),
+            locals: HashMap::new(),
+            subst: Subst::new(),
+            skolems: HashMap::new(),
+
+            types: HashMap::new(),
+            ctors: HashMap::new(),
+
+            classes: HashMap::new(),
+            instances: HashMap::new(),
+            given: Vec::new(),
+            dicts: Vec::new(),
 
             lower: BiMap::new(),
 
-            curr_tyvar: TypeVar(0),
-            worklist: Vec::new(),
+            worklist: Worklist::new(),
+            rule: Rule::Definition,
+            solutions: Vec::new(),
+            constraints: 0,
+            solving: None,
+            deferred: false,
+            trace: None,
+
+            regexes: HashMap::new(),
+
+            var_names: RefCell::new(VarNames::default()),
+
+            current: None,
+            origin: None,
+            diagnostics: Vec::new(),
         }
     }
 
-    pub fn declare(&mut self, name: mir::Name, vars: Vec<mir::Name>, ty: &hir::Type) {
+    pub fn declare(&mut self, name: mir::Name, vars: Vec<hir::TypeParam>, ty: &hir::Type) {
+        self.current = Some(name.clone());
         let ty = self.lower_type(ty, Mutability::Immutable);
-        self.context.insert(
+        let bounds = self.lower_bounds(&vars);
+        self.declare_name(
             name,
             Template {
-                params: vars,
+                params: vars.into_iter().map(|var| var.name).collect(),
+                bounds,
                 uninst: ty,
             },
         );
+        self.current = None;
     }
 
-    pub fn define(&mut self, name: &mir::Name, expr: hir::Expr) -> tween::Expr {
-        let ty = self.context.get(name).unwrap();
+    /// Check the body of a value against its type, under the rule of either
+    /// its declared type or, without an annotation, its inferred type.
+    pub fn define(&mut self, name: &mir::Name, expr: hir::Expr, rule: Rule) -> tween::Expr {
+        let ty = self.context.get(name).unwrap().clone();
 
         // NOTE: we don't instantiate here, because then the generics wouldn't work!
-        let ty = ty.uninst.clone().make_mutable();
-        let item = self.check_expr(expr, ty);
+        let bounds = ty.bounds;
+        let ty = ty.uninst.make_mutable();
+        self.current = Some(name.clone());
+        self.given = bounds.clone();
+        let expr = self.resolve_names(expr);
+        let item = self.with_rule(rule, |this| this.check_expr(expr, ty));
         self.solve_constraints(); // solve while vars are still mut
+        self.given = Vec::new();
+        self.locals.clear();
+        self.current = None;
 
-        item
+        self.abstract_dicts(item, &bounds)
     }
 
-    /// Solve as many constraints as possible. Returns `false` if this was
-    /// unsuccessful - i.e. there are unsolved constraints.
-    pub fn solve_constraints(&mut self) -> bool {
-        loop {
-            let worklist: Vec<_> = self.worklist.drain(..).collect();
-            let prev = worklist.len();
+    /// Check the given program after declaring the prelude it does not hide.
+    fn check_program(&mut self, prog: hir::Decls) -> mir::Program {
+        let prelude = prelude::visible(&prog);
+        self.builtins = prelude.values.keys().cloned().collect();
+        let mut program = self.check_batch(prelude);
+        extend(&mut program, self.check_batch(prog));
+        program
+    }
+
+    /// Check declarations which may refer to those of earlier batches,
+    /// producing the part of the typed program they add.
+    fn check_batch(&mut self, mut prog: hir::Decls) -> mir::Program {
+        debug!("Declaring");
+        let first_name = self.context.len();
+        let first_type = self.lower.len();
+        for (name, def) in prog.types.iter() {
+            self.declare_type(name.clone(), def);
+        }
+
+        for (name, def) in prog.classes.iter() {
+            self.declare_class(name.clone(), def);
+        }
 
-            if prev == 0 {
-                trace!("No more constraints");
-                return true;
+        let mut datatypes = IndexMap::with_capacity(prog.types.len() + prog.classes.len());
+        for (name, def) in prog.types.iter() {
+            if let Some(def) = self.define_type(name, def) {
+                datatypes.insert(name.clone(), def);
             }
+        }
+
+        let mut values = IndexMap::with_capacity(prog.values.len());
+        for (name, def) in prog.classes.iter() {
+            let (def, selectors) = self.define_class(name, def);
+            datatypes.insert(name.clone(), def);
+            values.extend(selectors);
+        }
+
+        for (name, item) in prog.values.iter() {
+            self.declare(name.clone(), item.vars.clone(), &item.anno);
+        }
+
+        // The builtins are only declared, since the interpreter implements
+        // them.
+        prog.values.retain(|name, _| !self.builtins.contains(name));
+        let components = order::components(&prog.values);
+
+        let instances: Vec<_> = prog
+            .instances
+            .into_iter()
+            .filter_map(|def| self.declare_instance(def))
+            .collect();
+
+        trace!("Declared types {:?}", self.context);
 
-            trace!("Solve loop; {} constraints to solve", prev);
+        debug!("Defining & solving");
+        let names: Vec<_> = prog.values.keys().cloned().collect();
+        let mut bodies: HashMap<_, _> = prog
+            .values
+            .into_iter()
+            .map(|(name, item)| {
+                let rule = match item.anno {
+                    hir::Type::Wildcard => Rule::Inferred,
+                    _ => Rule::Definition,
+                };
+                (name, (item.body, rule))
+            })
+            .collect();
+        let mut defined = HashMap::with_capacity(bodies.len());
 
-            for ctr in worklist {
-                self.solve(ctr);
+        for component in components {
+            trace!("Defining {component:?}");
+            for name in component.iter() {
+                let (body, rule) = bodies.remove(name).unwrap();
+                defined.insert(name.clone(), self.define(name, body, rule));
             }
 
-            if self.worklist.len() >= prev {
-                return false;
+            self.generalise(&component);
+            for name in component.iter() {
+                self.instantiate_recursive(&component, defined.get_mut(name).unwrap());
             }
         }
+
+        for name in names {
+            let body = defined.remove(&name).unwrap();
+            values.insert(name, body);
+        }
+
+        for (name, body) in instances {
+            let body = self.define(&name, body, Rule::Definition);
+            values.insert(name, body);
+        }
+
+        if !self.solve_constraints() {
+            self.report_stuck();
+        }
+
+        debug!("Substituting & memoizing");
+        let values = values
+            .into_iter()
+            .map(|(name, expr)| {
+                self.current = Some(name.clone());
+                let expr = self.substitute(expr);
+                (name, expr)
+            })
+            .collect();
+        let context = self.subst_ctx(first_name);
+        let types = (first_type..self.lower.len())
+            .map(|id| {
+                let id = varless::TypeId(id);
+                (id, self.lower.get_by_left(&id).unwrap().clone())
+            })
+            .collect();
+
+        mir::Program {
+            context,
+            types,
+            decls: mir::Decls {
+                types: datatypes,
+                values,
+            },
+        }
     }
 
-    pub fn subst_ctx(&mut self) -> HashMap<mir::Name, mir::Template> {
+    /// Substitute the types of the global names declared since the first
+    /// given one.
+    pub fn subst_ctx(&mut self, first: usize) -> IndexMap<mir::Name, mir::Template> {
         debug!("Substituting type context");
-        let ctx: HashMap<_, _> = self.context.drain().collect();
-        ctx.into_iter()
+        let ctx: Vec<_> = self
+            .context
+            .iter()
+            .skip(first)
+            .map(|(name, ty)| (name.clone(), ty.clone()))
+            .collect();
+        let ctx = ctx
+            .into_iter()
             .map(|(name, ty)| {
+                self.current = Some(name.clone());
+                let uninst = self.subst_type(ty.elaborated());
                 let params = ty.params;
-                let uninst = self.subst_type(ty.uninst);
                 (name, mir::Template { params, uninst })
             })
-            .collect()
+            .collect();
+
+        self.current = None;
+        ctx
+    }
+
+    /// Display a type in a diagnostic, with its type variables named
+    /// consistently with the rest of the diagnostic.
+    fn show(&self, ty: Type) -> String {
+        self.apply_subst(ty).display(&self.var_names).to_string()
+    }
+
+    /// Report a problem in the declaration currently being checked.
+    fn report(&mut self, diagnostic: Diagnostic) {
+        let diagnostic = diagnostic.within(self.current.clone());
+        debug!("Reporting {diagnostic:?}");
+        self.diagnostics.push(diagnostic);
+        self.var_names.take();
     }
 
     fn fresh_tyvar(&mut self) -> TypeVar {
-        let v = self.curr_tyvar;
-        self.curr_tyvar = TypeVar(self.curr_tyvar.0 + 1);
-        v
+        self.subst.fresh()
     }
 }
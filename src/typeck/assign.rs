@@ -1,87 +1,130 @@
 use log::trace;
 
+use super::provenance::Rule;
 use super::solve::Constraint;
 use super::tween::Mutability;
 use super::types::{Type, TypeVar};
 use super::Checker;
-use crate::mir::Literal;
+use crate::mir::{BinOp, Literal};
+use crate::Diagnostic;
 
 impl Checker {
     pub fn check_assignable(&mut self, into: Type, from: Type) {
+        if !self.assignable(into.clone(), from.clone()) {
+            let mut vars = Vec::new();
+            into.free_vars(&mut vars);
+            from.free_vars(&mut vars);
+
+            let into = self.show(into);
+            let from = self.show(from);
+            let mut diagnostic = Diagnostic::new(format!(
+                "mismatched types: expected `{into}`, found `{from}`"
+            ));
+            for note in self.explain_notes(&vars) {
+                diagnostic = diagnostic.with_note(note);
+            }
+
+            self.report(diagnostic);
+        }
+    }
+
+    /// Check that values of type `from` can be assigned to `into`, returning
+    /// `false` if they definitely cannot.
+    fn assignable(&mut self, into: Type, from: Type) -> bool {
         trace!("Assign check {into:?} <- {from:?}");
 
         if into == from {
-            return;
+            return true;
         }
 
         match (into, from) {
-            (_, Type::Bottom) => (),
-            (Type::Bool, Type::Bool) => (),
-            (Type::Regex, Type::Regex) => (),
-            (Type::Range(lo1, hi1), Type::Range(lo2, hi2)) => {
-                assert_eq!(lo1, lo2);
-                assert_eq!(hi1, hi2);
+            (_, Type::Bottom) => true,
+            (Type::Bool, Type::Bool) => true,
+            (Type::Regex, Type::Regex) => true,
+            (Type::Range(lo1, hi1), Type::Range(lo2, hi2)) => lo1 == lo2 && hi1 == hi2,
+            (Type::String(pat1), Type::String(pat2)) => pat1 == pat2,
+
+            (Type::Forall(vars, into), from) => {
+                let into = self.skolemise(&vars, *into);
+                self.assignable(into, from)
             }
-            (Type::String(pat1), Type::String(pat2)) => {
-                assert_eq!(pat1, pat2);
+
+            (into, Type::Forall(vars, from)) => {
+                let from = self.instantiate_forall(&vars, *from);
+                self.assignable(into, from)
             }
 
             (Type::Var(_, v), from) if self.subst.contains_key(&v) => {
                 trace!("Unify {v:?} and {from:?}");
                 let into = self.subst.get(&v).unwrap().clone();
-                self.check_assignable(into, from)
+                self.assignable(into, from)
             }
 
             (into, Type::Var(_, w)) if self.subst.contains_key(&w) => {
                 trace!("Unify {into:?} and {w:?}");
                 let from = self.subst.get(&w).unwrap().clone();
-                self.check_assignable(into, from)
+                self.assignable(into, from)
             }
 
             (Type::Var(Mutability::Mutable, v), from) => {
                 trace!("Unify {v:?} and {from:?}");
-                if self.occurs(&v, &from) {
-                    trace!("Recursive types - {v:?} <- {from:?}");
-                    trace!("Context {:?}", self.context);
-                    panic!("recursive type!");
-                }
-                self.subst.insert(v, from);
+                self.unify_var(v, from);
+                true
             }
 
             (into, Type::Var(Mutability::Mutable, w)) => {
                 trace!("Unify {into:?} and {w:?}");
-                if self.occurs(&w, &into) {
-                    trace!("Recursive types - {into:?} <- {w:?}");
-                    trace!("Context {:?}", self.context);
-                    panic!("recursive type!");
-                }
-                self.subst.insert(w, into);
+                self.unify_var(w, into);
+                true
             }
 
-            (into @ Type::Var(Mutability::Immutable, _), from)
-            | (into, from @ Type::Var(Mutability::Immutable, _)) => {
-                self.worklist.push(Constraint::Assignable(into, from));
+            // Type variables are solved to an alias itself, so that it is
+            // shown by name.
+            (Type::Alias(_, _, into), from) => self.assignable(*into, from),
+            (into, Type::Alias(_, _, from)) => self.assignable(into, *from),
+
+            (Type::Error, _) | (_, Type::Error) => true,
+
+            (Type::Var(Mutability::Immutable, v), _) | (_, Type::Var(Mutability::Immutable, v))
+                if self.skolems.contains_key(&v) =>
+            {
+                trace!("Rigid type variable {v:?}");
+                false
             }
 
+            (into @ Type::Var(Mutability::Immutable, v), from)
+            | (into, from @ Type::Var(Mutability::Immutable, v)) => {
+                self.block(Constraint::Assignable(into, from), vec![v]);
+                true
+            }
+
+            // Parameters are contravariant and results are covariant.
             (Type::Arrow(t1, u1), Type::Arrow(t2, u2)) => {
                 let (t1, u1) = (*t1, *u1);
                 let (t2, u2) = (*t2, *u2);
-                self.check_assignable(t1, t2);
-                self.check_assignable(u2, u1);
+                let from = self.assignable(t2, t1);
+                let into = self.assignable(u1, u2);
+                from && into
             }
 
-            (Type::Named(n), Type::Named(m)) => {
-                if n != m {
+            (Type::Named(n, args1), Type::Named(m, args2)) => {
+                if n != m || args1.len() != args2.len() {
                     trace!("Inequal types - {n:?} <- {m:?}");
-                    panic!("inequal types");
+                    return false;
                 }
+
+                // Type arguments are invariant.
+                let mut ok = true;
+                for (arg1, arg2) in args1.into_iter().zip(args2) {
+                    ok &= self.assignable(arg1.clone(), arg2.clone());
+                    ok &= self.assignable(arg2, arg1);
+                }
+                ok
             }
 
-            (Type::Error, _) | (_, Type::Error) => (),
             (into, from) => {
                 trace!("Inequal types - {into:?} <- {from:?}");
-                trace!("Context {:?}", self.context);
-                panic!("inequal types")
+                false
             }
         }
     }
@@ -89,32 +132,121 @@ impl Checker {
     pub fn as_fun_ty(&mut self, ty: Type) -> (Type, Type) {
         match ty {
             Type::Arrow(from, into) => (*from, *into),
+            Type::Alias(_, _, ty) => self.as_fun_ty(*ty),
+            Type::Forall(vars, ty) => {
+                let ty = self.instantiate_forall(&vars, *ty);
+                self.as_fun_ty(ty)
+            }
             Type::Var(..) => {
                 let from = self.fresh_type(Mutability::Mutable);
                 let into = self.fresh_type(Mutability::Mutable);
                 let fun_ty = self.fun_type(from.clone(), into.clone());
-                self.worklist.push(Constraint::Assignable(fun_ty, ty));
+                self.constrain(Constraint::Assignable(fun_ty, ty));
                 (from, into)
             }
-            _ => panic!("not a function type"),
+            Type::Error => (Type::Error, Type::Error),
+            ty => {
+                let ty = self.show(ty);
+                self.report(Diagnostic::new(format!(
+                    "expected a function type, found `{ty}`"
+                )));
+                (Type::Error, Type::Error)
+            }
         }
     }
 
     pub fn check_lit(&mut self, lit: Literal, ty: Type) {
-        self.worklist.push(Constraint::FromLit(lit, ty));
+        self.with_rule(Rule::Literal, |this| {
+            this.constrain(Constraint::FromLit(lit, ty));
+        });
+    }
+
+    pub fn check_binary(&mut self, op: BinOp, into: Type, lhs: Type, rhs: Type) {
+        self.with_rule(Rule::Binary(op), |this| {
+            this.constrain(Constraint::Binary(op, into, lhs, rhs));
+        });
+    }
+
+    /// Solve the type variable `v` to `ty`, unless that would produce an
+    /// infinite type.
+    fn unify_var(&mut self, v: TypeVar, ty: Type) {
+        if let Type::Var(_, w) = &ty {
+            if self.subst.same(&v, w) {
+                return;
+            }
+        }
+
+        if self.occurs(&v, &ty) {
+            trace!("Recursive types - {v:?} <- {ty:?}");
+            let var = self.show(Type::Var(Mutability::Mutable, v));
+            let ty = self.show(ty);
+            self.report(
+                Diagnostic::new("cannot construct an infinite type")
+                    .with_note(format!("{var} = {ty}")),
+            );
+            self.solve_var(v, Type::Error);
+            return;
+        }
+
+        if let Some(skolem) = self.escaping_skolem(self.subst.oldest(v), &ty) {
+            trace!("Escaping skolem - {v:?} <- {ty:?}");
+            let var = self.show(Type::Var(Mutability::Mutable, v));
+            let ty = self.show(ty);
+            self.report(
+                Diagnostic::new(format!(
+                    "the type variable `{skolem}` would escape the scope it is bound in"
+                ))
+                .with_note(format!("{var} = {ty}")),
+            );
+            self.solve_var(v, Type::Error);
+            return;
+        }
+
+        self.solve_var(v, ty);
+    }
+
+    /// The name of a rigid type variable in `ty` which was created after `v`,
+    /// so is bound in a scope `v` is outside of, if there is one.
+    fn escaping_skolem(&self, v: TypeVar, ty: &Type) -> Option<String> {
+        match ty {
+            Type::Var(_, w) if w.0 > v.0 && self.skolems.contains_key(w) => {
+                self.skolems.get(w).cloned()
+            }
+            Type::Var(_, w) => match self.subst.get(w) {
+                Some(ty) => self.escaping_skolem(v, ty),
+                None => None,
+            },
+            Type::Arrow(t, u) => self
+                .escaping_skolem(v, t)
+                .or_else(|| self.escaping_skolem(v, u)),
+            Type::Named(_, args) => args.iter().find_map(|arg| self.escaping_skolem(v, arg)),
+            Type::Alias(_, _, ty) | Type::Forall(_, ty) => self.escaping_skolem(v, ty),
+
+            Type::Bottom
+            | Type::Bool
+            | Type::Regex
+            | Type::Range(..)
+            | Type::String(..)
+            | Type::Error => None,
+        }
     }
 
     fn occurs(&self, v: &TypeVar, ty: &Type) -> bool {
         match ty {
-            Type::Var(_, w) => v == w,
+            Type::Var(_, w) if self.subst.same(v, w) => true,
+            Type::Var(_, w) => match self.subst.get(w) {
+                Some(ty) => self.occurs(v, ty),
+                None => false,
+            },
             Type::Arrow(t, u) => self.occurs(v, t) || self.occurs(v, u),
+            Type::Named(_, args) => args.iter().any(|arg| self.occurs(v, arg)),
+            Type::Alias(_, _, ty) | Type::Forall(_, ty) => self.occurs(v, ty),
 
             Type::Bottom
             | Type::Bool
             | Type::Regex
             | Type::Range(..)
             | Type::String(..)
-            | Type::Named(..)
             | Type::Error => false,
         }
     }
@@ -1,21 +1,23 @@
-use super::context::Template;
 use super::tween;
 use super::types::Type;
 use super::Checker;
 use crate::hir;
+use crate::Diagnostic;
 
 impl Checker {
     pub fn bind(&mut self, pat: hir::Pat, ty: Type) -> tween::Pat {
         match pat {
-            hir::Pat::Constructor(..) => todo!(),
+            pat @ (hir::Pat::Constructor(..) | hir::Pat::Apply(..)) => {
+                let (pat, pat_ty) = self.bind_ctor(pat);
+                self.check_assignable(pat_ty, ty);
+                pat
+            }
 
             hir::Pat::Bind(name) => {
-                self.context.insert(name.clone(), Template::mono(ty));
+                self.locals.insert(name.clone(), ty);
                 tween::Pat::Bind(name)
             }
 
-            hir::Pat::Apply(..) => todo!(),
-
             hir::Pat::Lit(hir::Literal::Boolean(v)) => {
                 self.check_lit(tween::Literal::Boolean(v), ty);
                 tween::Pat::Lit(tween::Literal::Boolean(v))
@@ -26,10 +28,13 @@ impl Checker {
                 tween::Pat::Lit(tween::Literal::Integer(v))
             }
 
-            hir::Pat::Lit(hir::Literal::Regex(v)) => {
-                self.check_lit(tween::Literal::Regex(v.clone()), ty);
-                tween::Pat::Lit(tween::Literal::Regex(v))
-            }
+            hir::Pat::Lit(hir::Literal::Regex(v)) => match self.compile_regex(&v) {
+                Some(v) => {
+                    self.check_lit(tween::Literal::Regex(v.clone()), ty);
+                    tween::Pat::Lit(tween::Literal::Regex(v))
+                }
+                None => tween::Pat::Invalid,
+            },
 
             hir::Pat::Lit(hir::Literal::String(v)) => {
                 self.check_lit(tween::Literal::String(v.clone()), ty);
@@ -39,4 +44,59 @@ impl Checker {
             hir::Pat::Wildcard => tween::Pat::Wildcard,
         }
     }
+
+    /// Bind a (possibly partially applied) constructor pattern, returning the
+    /// type of the values it matches.
+    fn bind_ctor(&mut self, pat: hir::Pat) -> (tween::Pat, Type) {
+        match pat {
+            hir::Pat::Constructor(name) => {
+                let ty = match self.ctors.contains_key(&name) {
+                    true => self.context.get(&name).cloned(),
+                    false => None,
+                };
+
+                match ty {
+                    Some(ty) => {
+                        let ty = self.instantiate(&ty);
+                        (tween::Pat::Constructor(name), ty)
+                    }
+
+                    None => {
+                        self.report(Diagnostic::new(format!("unknown constructor `{name}`")));
+                        (tween::Pat::Invalid, self.error_type())
+                    }
+                }
+            }
+
+            hir::Pat::Apply(ctor, arg) => {
+                let (ctor, ctor_ty) = self.bind_ctor(*ctor);
+                match self.resolve(ctor_ty) {
+                    Type::Arrow(arg_ty, ret_ty) => {
+                        let arg = self.bind(*arg, *arg_ty);
+                        (tween::Pat::Apply(Box::new(ctor), Box::new(arg)), *ret_ty)
+                    }
+
+                    Type::Error => {
+                        self.bind(*arg, Type::Error);
+                        (tween::Pat::Invalid, Type::Error)
+                    }
+
+                    ty => {
+                        self.report(Diagnostic::new(format!(
+                            "constructor pattern of type `{ty}` applied to too many arguments"
+                        )));
+                        self.bind(*arg, Type::Error);
+                        (tween::Pat::Invalid, Type::Error)
+                    }
+                }
+            }
+
+            _ => {
+                self.report(Diagnostic::new(
+                    "only constructors can be applied in patterns",
+                ));
+                (tween::Pat::Invalid, self.error_type())
+            }
+        }
+    }
 }
@@ -1,3 +1,4 @@
+use super::provenance::Rule;
 use super::tween;
 use super::types::Type;
 use super::Checker;
@@ -5,10 +6,23 @@ use crate::hir;
 
 impl Checker {
     pub fn check_expr(&mut self, expr: hir::Expr, ty: Type) -> tween::Expr {
+        self.with_origin(expr, |this, expr| this.check_expr_inner(expr, ty))
+    }
+
+    fn check_expr_inner(&mut self, expr: hir::Expr, ty: Type) -> tween::Expr {
+        if let Type::Forall(vars, body) = self.resolve(ty.clone()) {
+            let body = self.skolemise(&vars, *body);
+            let expr = self.check_expr(expr, body);
+            return tween::Expr {
+                node: expr.node,
+                anno: ty,
+            };
+        }
+
         let node = match expr {
             hir::Expr::Fun(bind, body) => {
                 let (bind_ty, body_ty) = self.as_fun_ty(ty.clone());
-                let bind = self.bind(bind, bind_ty);
+                let bind = self.with_rule(Rule::Pattern, |this| this.bind(bind, bind_ty));
                 let body = self.check_expr(*body, body_ty);
                 tween::ExprNode::Fun(bind, Box::new(body))
             }
@@ -20,9 +34,10 @@ impl Checker {
                 elze,
             } => {
                 let bound = Box::new(self.infer_expr(*bound));
-                let pat = self.bind(pat, bound.anno.clone());
+                let pat = self.with_rule(Rule::Pattern, |this| this.bind(pat, bound.anno.clone()));
                 let then = Box::new(self.check_expr(*then, ty.clone()));
-                let elze = Box::new(self.check_expr(*elze, ty.clone()));
+                let elze = self.with_rule(Rule::Else, |this| this.check_expr(*elze, ty.clone()));
+                let elze = Box::new(elze);
 
                 tween::ExprNode::Let {
                     pat,
@@ -0,0 +1,475 @@
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+use log::trace;
+
+use super::context::{Bound, Template};
+use super::provenance::Rule;
+use super::solve::Constraint;
+use super::tween::{self, Mutability, Name};
+use super::types::Type;
+use super::Checker;
+use crate::hir;
+use crate::mir;
+use crate::Diagnostic;
+
+/// Identifies a dictionary that is yet to be resolved to an instance.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct DictId(pub usize);
+
+#[derive(Clone, Debug)]
+pub struct Class {
+    pub methods: Vec<Name>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Instance {
+    /// The name of the value holding the dictionary of this instance.
+    pub name: Name,
+    pub params: Vec<Name>,
+    pub head: Type,
+}
+
+/// The result of matching a type against the head of an instance.
+enum Match {
+    Yes,
+    No,
+    /// The type is not solved enough to tell.
+    Unknown,
+}
+
+impl Match {
+    fn and(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::No, _) | (_, Self::No) => Self::No,
+            (Self::Unknown, _) | (_, Self::Unknown) => Self::Unknown,
+            (Self::Yes, Self::Yes) => Self::Yes,
+        }
+    }
+}
+
+impl Checker {
+    /// Declare a class as a data type of dictionaries, such that method types
+    /// and instances may refer to it.
+    pub fn declare_class(&mut self, name: mir::Name, def: &hir::ClassDef) {
+        let data = hir::TypeDef::Data(hir::DataDef {
+            vars: vec![def.var.clone()],
+            ctors: Vec::new(),
+        });
+
+        self.declare_type(name.clone(), &data);
+        self.classes.insert(
+            name,
+            Class {
+                methods: def.methods.iter().map(|(name, _)| name.clone()).collect(),
+            },
+        );
+    }
+
+    /// Define the data type of dictionaries of the given class, which has a
+    /// single constructor of the same name with a field for every method.
+    /// Every method is declared as a value with a bound on the class, and
+    /// defined as a selector projecting it out of the dictionary.
+    pub fn define_class(
+        &mut self,
+        name: &mir::Name,
+        def: &hir::ClassDef,
+    ) -> (mir::DataDef, Vec<(mir::Name, tween::Expr)>) {
+        let data = hir::DataDef {
+            vars: vec![def.var.clone()],
+            ctors: vec![hir::Constructor {
+                name: name.clone(),
+                fields: def.methods.iter().map(|(_, ty)| ty.clone()).collect(),
+            }],
+        };
+
+        let data = self.define_data(name, &data);
+
+        let mut fields = Vec::with_capacity(def.methods.len());
+        let mut ty = self.context.get(name).unwrap().uninst.clone();
+        while let Type::Arrow(field, rest) = ty {
+            fields.push(*field);
+            ty = *rest;
+        }
+
+        let bound = Bound {
+            class: name.clone(),
+            var: def.var.clone(),
+        };
+
+        let mut selectors = Vec::with_capacity(def.methods.len());
+        for (index, (method, _)) in def.methods.iter().enumerate() {
+            self.current = Some(method.clone());
+            let template = Template {
+                params: vec![def.var.clone()],
+                bounds: vec![bound.clone()],
+                uninst: fields[index].clone(),
+            };
+
+            self.declare_name(method.clone(), template);
+            self.current = None;
+
+            let selector = self.selector(&bound, method, index, &fields);
+            selectors.push((method.clone(), selector));
+        }
+
+        (data, selectors)
+    }
+
+    /// Declare an instance as a value holding its dictionary, returning the
+    /// name and body of that value.
+    pub fn declare_instance(&mut self, def: hir::InstanceDef) -> Option<(mir::Name, hir::Expr)> {
+        let Some(class) = self.classes.get(&def.class).cloned() else {
+            self.report(Diagnostic::new(format!("unknown class `{}`", def.class)));
+            return None;
+        };
+
+        let head = self.lower_type(&def.ty, Mutability::Immutable);
+        let params: Vec<_> = def.vars.iter().map(|var| var.name.clone()).collect();
+        if self.overlaps(&def.class, &params, &head) {
+            let ty = self.show(head);
+            self.report(Diagnostic::new(format!(
+                "more than one instance of `{}` for `{ty}`",
+                def.class
+            )));
+            return None;
+        }
+
+        let name = format!("${}.{head}", def.class);
+        self.current = Some(name.clone());
+
+        let anno = hir::Type::Apply(
+            Box::new(hir::Type::Named(def.class.clone())),
+            Box::new(def.ty),
+        );
+
+        self.declare(name.clone(), def.vars, &anno);
+        self.instances
+            .entry(def.class.clone())
+            .or_default()
+            .push(Instance {
+                name: name.clone(),
+                params,
+                head,
+            });
+
+        let mut methods: IndexMap<_, _> = def.methods.into_iter().collect();
+        let mut body = hir::Expr::Name(def.class.clone());
+        for method in class.methods.iter() {
+            let arg = methods.shift_remove(method).unwrap_or_else(|| {
+                self.report(Diagnostic::new(format!(
+                    "instance is missing method `{method}`"
+                )));
+                hir::Expr::Invalid
+            });
+
+            body = hir::Expr::Call(Box::new(body), Box::new(arg));
+        }
+
+        for method in methods.into_keys() {
+            self.report(Diagnostic::new(format!(
+                "`{method}` is not a method of class `{}`",
+                def.class
+            )));
+        }
+
+        self.current = None;
+        Some((name, body))
+    }
+
+    /// Lower the bounds on the given type parameters.
+    pub fn lower_bounds(&mut self, vars: &[hir::TypeParam]) -> Vec<Bound> {
+        let mut bounds = Vec::new();
+        for var in vars {
+            for class in var.bounds.iter() {
+                if !self.classes.contains_key(class) {
+                    self.report(Diagnostic::new(format!("unknown class `{class}`")));
+                    continue;
+                }
+
+                bounds.push(Bound {
+                    class: class.clone(),
+                    var: var.name.clone(),
+                });
+            }
+        }
+        bounds
+    }
+
+    /// Elaborate a use of a name with bounds, passing a dictionary for each
+    /// of them.
+    pub fn elaborate_use(
+        &mut self,
+        name: mir::Name,
+        ty: &Template,
+        args: Vec<Type>,
+    ) -> tween::Expr {
+        let (inst, args) = self.instantiate_args(ty, args);
+        let vars: HashMap<_, _> = ty.params.iter().zip(args.iter()).collect();
+        let given = self.given.clone();
+        let dicts: Vec<_> = ty
+            .bounds
+            .iter()
+            .map(|bound| self.fresh_dict(bound.class.clone(), vars[&bound.var].clone(), &given))
+            .collect();
+
+        let anno = dicts.iter().rev().fold(inst, |ty, dict| {
+            Type::Arrow(Box::new(dict.anno.clone()), Box::new(ty))
+        });
+
+        let node = tween::ExprNode::Instantiated(name, args);
+        let expr = tween::Expr { node, anno };
+        Self::apply_dicts(expr, dicts)
+    }
+
+    /// Create a dictionary for the given class and type, to be resolved once
+    /// the type is known.
+    pub fn fresh_dict(&mut self, class: Name, ty: Type, given: &[Bound]) -> tween::Expr {
+        let id = DictId(self.dicts.len());
+        self.dicts.push(None);
+
+        let anno = Type::Named(class.clone(), vec![ty.clone()]);
+        self.with_rule(Rule::Instance(class.clone()), |this| {
+            this.constrain(Constraint::Class(class, ty, id, given.to_vec()));
+        });
+
+        tween::Expr {
+            node: tween::ExprNode::Dict(id),
+            anno,
+        }
+    }
+
+    pub fn solve_class(&mut self, class: Name, ty: Type, dict: DictId, given: Vec<Bound>) {
+        let resolved = match self.resolve(ty.clone()) {
+            Type::Var(_, v) => {
+                self.block(Constraint::Class(class, ty, dict, given), vec![v]);
+                return;
+            }
+
+            Type::Error => {
+                self.invalid_dict(dict);
+                return;
+            }
+            ty => ty,
+        };
+
+        if let Type::Named(var, args) = &resolved {
+            let bound = Bound {
+                class: class.clone(),
+                var: var.clone(),
+            };
+
+            if args.is_empty() && given.contains(&bound) {
+                trace!("Dictionary {dict:?} is given as {}", bound.dict_name());
+                self.dicts[dict.0] = Some(tween::Expr {
+                    node: tween::ExprNode::Name(bound.dict_name()),
+                    anno: bound.dict_type(),
+                });
+                return;
+            }
+        }
+
+        let mut found = None;
+        let mut unknown = false;
+        for instance in self.instances.get(&class).into_iter().flatten() {
+            let mut vars = HashMap::new();
+            match self.match_head(&instance.params, &instance.head, &resolved, &mut vars) {
+                Match::Yes => {
+                    found = Some((instance.name.clone(), vars));
+                    break;
+                }
+                Match::Unknown => unknown = true,
+                Match::No => {}
+            }
+        }
+
+        let Some((name, mut vars)) = found else {
+            if unknown {
+                let mut vars = Vec::new();
+                self.apply_subst(resolved).free_vars(&mut vars);
+                self.block(Constraint::Class(class, ty, dict, given), vars);
+            } else {
+                let ty = self.show(ty);
+                self.report(Diagnostic::new(format!(
+                    "no instance of `{class}` for `{ty}`"
+                )));
+                self.invalid_dict(dict);
+            }
+            return;
+        };
+
+        trace!("Dictionary {dict:?} is {name}");
+        let template = self.context.get(&name).unwrap().clone();
+        for param in template.params.iter() {
+            if !vars.contains_key(param) {
+                let ty = self.fresh_type(Mutability::Mutable);
+                vars.insert(param.clone(), ty);
+            }
+        }
+
+        let dicts = template
+            .bounds
+            .iter()
+            .map(|bound| self.fresh_dict(bound.class.clone(), vars[&bound.var].clone(), &given))
+            .collect();
+
+        let node = match template.params.is_empty() {
+            true => tween::ExprNode::Name(name),
+            false => {
+                let args = template
+                    .params
+                    .iter()
+                    .map(|param| vars[param].clone())
+                    .collect();
+                tween::ExprNode::Instantiated(name, args)
+            }
+        };
+
+        let anno = self.inst_ty(template.elaborated(), &vars);
+        let expr = tween::Expr { node, anno };
+        self.dicts[dict.0] = Some(Self::apply_dicts(expr, dicts));
+    }
+
+    /// Resolve a dictionary to an invalid expression, once an error has been
+    /// reported for it.
+    pub fn invalid_dict(&mut self, dict: DictId) {
+        self.dicts[dict.0] = Some(tween::Expr {
+            node: tween::ExprNode::Invalid,
+            anno: Type::Error,
+        });
+    }
+
+    /// Apply the given expression to every dictionary in turn.
+    fn apply_dicts(expr: tween::Expr, dicts: Vec<tween::Expr>) -> tween::Expr {
+        dicts.into_iter().fold(expr, |func, dict| {
+            let anno = match &func.anno {
+                Type::Arrow(_, ret) => (**ret).clone(),
+                _ => unreachable!("dictionaries are passed to functions"),
+            };
+
+            let node = tween::ExprNode::Call(Box::new(func), Box::new(dict));
+            tween::Expr { node, anno }
+        })
+    }
+
+    /// Wrap the definition of a value with the given bounds in a function for
+    /// each of their dictionaries.
+    pub fn abstract_dicts(&mut self, body: tween::Expr, bounds: &[Bound]) -> tween::Expr {
+        bounds.iter().rev().fold(body, |body, bound| {
+            let anno = Type::Arrow(Box::new(bound.dict_type()), Box::new(body.anno.clone()));
+            let node = tween::ExprNode::Fun(tween::Pat::Bind(bound.dict_name()), Box::new(body));
+            tween::Expr { node, anno }
+        })
+    }
+
+    /// Build the selector projecting the method at `index` out of a
+    /// dictionary with the given fields.
+    fn selector(
+        &mut self,
+        bound: &Bound,
+        method: &Name,
+        index: usize,
+        fields: &[Type],
+    ) -> tween::Expr {
+        let mut pat = tween::Pat::Constructor(bound.class.clone());
+        for field in 0..fields.len() {
+            let arg = match field == index {
+                true => tween::Pat::Bind(method.clone()),
+                false => tween::Pat::Wildcard,
+            };
+
+            pat = tween::Pat::Apply(Box::new(pat), Box::new(arg));
+        }
+
+        let ty = fields[index].clone();
+        let dict = tween::Expr {
+            node: tween::ExprNode::Name(bound.dict_name()),
+            anno: bound.dict_type(),
+        };
+
+        let body = tween::Expr {
+            node: tween::ExprNode::Let {
+                pat,
+                bound: Box::new(dict),
+                then: Box::new(tween::Expr {
+                    node: tween::ExprNode::Name(method.clone()),
+                    anno: ty.clone(),
+                }),
+                elze: Box::new(tween::Expr {
+                    node: tween::ExprNode::Impossible,
+                    anno: Type::Bottom,
+                }),
+            },
+            anno: ty,
+        };
+
+        self.abstract_dicts(body, std::slice::from_ref(bound))
+    }
+
+    /// Whether an instance of the class with the given parameters and head
+    /// would overlap with one already declared, such that some type has more
+    /// than one instance.
+    fn overlaps(&self, class: &Name, params: &[Name], head: &Type) -> bool {
+        self.instances
+            .get(class)
+            .into_iter()
+            .flatten()
+            .any(|instance| {
+                let matches = |params, head, ty| {
+                    let mut vars = HashMap::new();
+                    matches!(self.match_head(params, head, ty, &mut vars), Match::Yes)
+                };
+
+                matches(&instance.params, &instance.head, head)
+                    || matches(params, head, &instance.head)
+            })
+    }
+
+    /// Match a type against the head of an instance with the given
+    /// parameters, binding the parameters in `vars`.
+    fn match_head(
+        &self,
+        params: &[Name],
+        head: &Type,
+        ty: &Type,
+        vars: &mut HashMap<Name, Type>,
+    ) -> Match {
+        let ty = self.resolve(ty.clone());
+        match (head, ty) {
+            (Type::Alias(_, _, head), ty) => self.match_head(params, head, &ty, vars),
+
+            (Type::Named(param, args), ty) if args.is_empty() && params.contains(param) => {
+                vars.insert(param.clone(), ty);
+                Match::Yes
+            }
+
+            (_, Type::Var(..)) => Match::Unknown,
+
+            (Type::Named(n, args1), Type::Named(m, args2)) => {
+                if *n != m || args1.len() != args2.len() {
+                    return Match::No;
+                }
+
+                args1
+                    .iter()
+                    .zip(args2.iter())
+                    .fold(Match::Yes, |result, (head, ty)| {
+                        result.and(self.match_head(params, head, ty, vars))
+                    })
+            }
+
+            (Type::Arrow(t1, u1), Type::Arrow(t2, u2)) => {
+                let from = self.match_head(params, t1, &t2, vars);
+                from.and(self.match_head(params, u1, &u2, vars))
+            }
+
+            (Type::Bool, Type::Bool) | (Type::Regex, Type::Regex) => Match::Yes,
+            (Type::Range(lo1, hi1), Type::Range(lo2, hi2)) if *lo1 == lo2 && *hi1 == hi2 => {
+                Match::Yes
+            }
+            (Type::String(pat1), Type::String(pat2)) if *pat1 == pat2 => Match::Yes,
+
+            _ => Match::No,
+        }
+    }
+}
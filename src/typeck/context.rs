@@ -1,38 +1,186 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use log::trace;
 
+use super::provenance::Rule;
 use super::solve::Constraint;
-use super::tween::{Mutability, Name};
-use super::types::Type;
+use super::tween::{self, Mutability, Name};
+use super::types::{param_name, Type, TypeVar};
 use super::Checker;
 
 #[derive(Clone, Debug)]
 pub struct Template {
     pub params: Vec<Name>,
+    /// Class constraints on the parameters, each of which is satisfied by
+    /// passing a dictionary argument.
+    pub bounds: Vec<Bound>,
     pub uninst: Type,
 }
 
+/// A constraint that the type variable `var` is an instance of `class`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Bound {
+    pub class: Name,
+    pub var: Name,
+}
+
 impl Template {
-    pub fn mono(ty: Type) -> Self {
-        Self {
-            params: Vec::new(),
-            uninst: ty,
-        }
+    /// The type of this template after elaboration, which takes a dictionary
+    /// argument for every bound before the arguments of the uninstantiated
+    /// type.
+    pub fn elaborated(&self) -> Type {
+        self.bounds
+            .iter()
+            .rev()
+            .fold(self.uninst.clone(), |ty, bound| {
+                Type::Arrow(Box::new(bound.dict_type()), Box::new(ty))
+            })
+    }
+}
+
+impl Bound {
+    /// The type of the dictionaries satisfying this bound.
+    pub fn dict_type(&self) -> Type {
+        let var = Type::Named(self.var.clone(), Vec::new());
+        Type::Named(self.class.clone(), vec![var])
+    }
+
+    /// The name of the parameter the dictionary satisfying this bound is
+    /// passed as.
+    pub fn dict_name(&self) -> Name {
+        format!("${}.{}", self.class, self.var)
     }
 }
 
 impl Checker {
     pub fn instantiate(&mut self, ty: &Template) -> Type {
-        trace!("Instantiating {ty:?}");
+        self.instantiate_args(ty, Vec::new()).0
+    }
 
-        let vars: HashMap<_, _> = ty
+    /// Instantiate the given template with the given types for its first
+    /// parameters and fresh type variables for the rest. Returns the
+    /// instantiated type along with the type of every parameter.
+    pub fn instantiate_args(&mut self, ty: &Template, args: Vec<Type>) -> (Type, Vec<Type>) {
+        trace!("Instantiating {ty:?} with {args:?}");
+
+        if ty.params.is_empty() {
+            return (ty.uninst.clone(), Vec::new());
+        }
+
+        let mut args = args.into_iter();
+        let args: Vec<_> = ty
             .params
+            .iter()
+            .map(|_| {
+                args.next()
+                    .unwrap_or_else(|| self.fresh_type(Mutability::Mutable))
+            })
+            .collect();
+
+        let vars = ty
+            .params
+            .iter()
+            .cloned()
+            .zip(args.iter().cloned())
+            .collect();
+        let inst = self.inst_ty(ty.uninst.clone(), &vars);
+        (inst, args)
+    }
+
+    /// Instantiate the bound variables of a polymorphic type with fresh type
+    /// variables.
+    pub fn instantiate_forall(&mut self, bound: &[Name], ty: Type) -> Type {
+        let vars = bound
             .iter()
             .map(|name| (name.clone(), self.fresh_type(Mutability::Mutable)))
             .collect();
 
-        self.inst_ty(ty.uninst.clone(), &vars)
+        self.inst_ty(ty, &vars)
+    }
+
+    /// Replace the bound variables of a polymorphic type with fresh rigid
+    /// type variables, which are only assignable to themselves.
+    pub fn skolemise(&mut self, bound: &[Name], ty: Type) -> Type {
+        let vars = bound
+            .iter()
+            .map(|name| {
+                let v = self.fresh_tyvar();
+                self.skolems.insert(v, name.clone());
+                (name.clone(), Type::Var(Mutability::Immutable, v))
+            })
+            .collect();
+
+        self.inst_ty(ty, &vars)
+    }
+
+    /// Generalise the types of a group of mutually dependent definitions over
+    /// the type variables left unsolved after checking them. Variables still
+    /// mentioned by pending constraints may yet be solved, and are left alone.
+    pub fn generalise(&mut self, names: &[Name]) {
+        let mut blocked = Vec::new();
+        for ty in self.worklist.iter().flat_map(|ctr| ctr.types()) {
+            let ty = self.apply_subst(ty);
+            ty.free_vars(&mut blocked);
+        }
+
+        let blocked: HashSet<_> = blocked.into_iter().collect();
+        let mut generalised: HashMap<TypeVar, Name> = HashMap::new();
+
+        let free: Vec<_> = names
+            .iter()
+            .map(|name| {
+                let uninst = self.apply_subst(self.context[name].uninst.clone());
+                let mut vars = Vec::new();
+                uninst.free_vars(&mut vars);
+                vars
+            })
+            .collect();
+
+        for (name, vars) in names.iter().zip(free) {
+            for v in vars {
+                if blocked.contains(&v) {
+                    continue;
+                }
+
+                let param = match generalised.get(&v) {
+                    Some(param) => param.clone(),
+                    None => {
+                        let param = param_name(generalised.len());
+                        trace!("Generalising {v:?} as `{param}` in `{name}`");
+                        let ty = Type::Named(param.clone(), Vec::new());
+                        self.with_rule(Rule::Generalise, |this| this.solve_var(v, ty));
+                        generalised.insert(v, param.clone());
+                        param
+                    }
+                };
+
+                let params = &mut self.context.get_mut(name).unwrap().params;
+                if !params.contains(&param) {
+                    params.push(param);
+                }
+            }
+        }
+    }
+
+    /// Make the recursive uses of a generalised group of definitions within
+    /// their bodies instantiate them at their own type parameters, since
+    /// they were monomorphic when the bodies were checked.
+    pub fn instantiate_recursive(&self, names: &[Name], body: &mut tween::Expr) {
+        let params = names
+            .iter()
+            .filter_map(|name| {
+                let params = &self.context[name].params;
+                (!params.is_empty()).then(|| {
+                    let args = params
+                        .iter()
+                        .map(|param| Type::Named(param.clone(), Vec::new()))
+                        .collect();
+                    (name.clone(), args)
+                })
+            })
+            .collect();
+
+        instantiate_recursive(&params, body);
     }
 
     pub fn inst_ty(&mut self, ty: Type, vars: &HashMap<Name, Type>) -> Type {
@@ -50,19 +198,42 @@ impl Checker {
                     self.inst_ty(ty, vars)
                 } else {
                     let w = self.fresh_tyvar();
-                    self.worklist.push(Constraint::Instantiate(
-                        vars.clone(),
-                        v,
-                        Type::Var(mutability, v),
-                    ));
+                    let ty = Type::Var(mutability, v);
+                    self.block(Constraint::Instantiate(vars.clone(), w, ty), vec![v]);
                     Type::Var(Mutability::Mutable, w)
                 }
             }
 
-            Type::Named(name) => vars
-                .get(&name)
-                .cloned()
-                .unwrap_or_else(|| Type::Named(name.clone())),
+            Type::Named(name, args) if args.is_empty() => {
+                vars.get(&name).cloned().unwrap_or(Type::Named(name, args))
+            }
+
+            Type::Named(name, args) => {
+                let args = args
+                    .into_iter()
+                    .map(|arg| self.inst_ty(arg, vars))
+                    .collect();
+                Type::Named(name, args)
+            }
+
+            Type::Alias(name, args, ty) => {
+                let args = args
+                    .into_iter()
+                    .map(|arg| self.inst_ty(arg, vars))
+                    .collect();
+                let ty = self.inst_ty(*ty, vars);
+                Type::Alias(name, args, Box::new(ty))
+            }
+
+            Type::Forall(bound, ty) => {
+                let mut vars = vars.clone();
+                for name in bound.iter() {
+                    vars.remove(name);
+                }
+
+                let ty = self.inst_ty(*ty, &vars);
+                Type::Forall(bound, Box::new(ty))
+            }
 
             Type::Arrow(from, into) => {
                 let from = self.inst_ty(*from, vars);
@@ -72,3 +243,62 @@ impl Checker {
         }
     }
 }
+
+fn instantiate_recursive(params: &HashMap<Name, Vec<Type>>, expr: &mut tween::Expr) {
+    match &mut expr.node {
+        tween::ExprNode::Fun(pat, body) => {
+            if !binds_any(pat, params) {
+                instantiate_recursive(params, body);
+            }
+        }
+
+        tween::ExprNode::Let {
+            pat,
+            bound,
+            then,
+            elze,
+        } => {
+            instantiate_recursive(params, bound);
+            instantiate_recursive(params, elze);
+            if !binds_any(pat, params) {
+                instantiate_recursive(params, then);
+            }
+        }
+
+        tween::ExprNode::Call(lhs, rhs) | tween::ExprNode::Binary(_, lhs, rhs) => {
+            instantiate_recursive(params, lhs);
+            instantiate_recursive(params, rhs);
+        }
+
+        tween::ExprNode::Name(name) => {
+            if let Some(args) = params.get(name) {
+                expr.node = tween::ExprNode::Instantiated(name.clone(), args.clone());
+            }
+        }
+
+        // A use of an explicitly polymorphic definition is instantiated at
+        // its declared parameters, but not those it was generalised over.
+        tween::ExprNode::Instantiated(name, args) => {
+            if let Some(params) = params.get(name) {
+                args.extend(params.iter().skip(args.len()).cloned());
+            }
+        }
+
+        tween::ExprNode::Lit(_)
+        | tween::ExprNode::Dict(_)
+        | tween::ExprNode::Impossible
+        | tween::ExprNode::Invalid => {}
+    }
+}
+
+/// Whether the pattern binds any of the given names, shadowing it.
+fn binds_any<T>(pat: &tween::Pat, names: &HashMap<Name, T>) -> bool {
+    match pat {
+        tween::Pat::Bind(name) => names.contains_key(name),
+        tween::Pat::Apply(ctor, arg) => binds_any(ctor, names) || binds_any(arg, names),
+        tween::Pat::Constructor(_)
+        | tween::Pat::Lit(_)
+        | tween::Pat::Wildcard
+        | tween::Pat::Invalid => false,
+    }
+}
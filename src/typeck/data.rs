@@ -0,0 +1,170 @@
+use super::context::Template;
+use super::tween::{Mutability, Name};
+use super::types::Type;
+use super::Checker;
+use crate::hir;
+use crate::mir;
+use crate::Diagnostic;
+
+/// What is known about a declared type name.
+#[derive(Clone, Debug)]
+pub enum TypeDef {
+    Alias {
+        params: Vec<Name>,
+        body: AliasBody,
+    },
+    /// A data type or newtype.
+    Data {
+        params: Vec<Name>,
+    },
+}
+
+/// The body of a type alias, which is lowered the first time it is needed.
+#[derive(Clone, Debug)]
+pub enum AliasBody {
+    Unlowered(hir::Type),
+    Lowering,
+    Lowered(Type),
+}
+
+impl TypeDef {
+    pub fn params(&self) -> &[Name] {
+        match self {
+            Self::Alias { params, .. } | Self::Data { params } => params,
+        }
+    }
+}
+
+impl Checker {
+    /// Make a type name known, such that the definitions of other types and
+    /// values may refer to it.
+    pub fn declare_type(&mut self, name: mir::Name, def: &hir::TypeDef) {
+        let def = match def {
+            hir::TypeDef::Alias(alias) => TypeDef::Alias {
+                params: alias.vars.clone(),
+                body: AliasBody::Unlowered(alias.body.clone()),
+            },
+
+            hir::TypeDef::Data(data) => TypeDef::Data {
+                params: data.vars.clone(),
+            },
+
+            hir::TypeDef::Newtype(newtype) => TypeDef::Data {
+                params: newtype.vars.clone(),
+            },
+        };
+
+        if self.types.contains_key(&name) {
+            self.current = Some(name.clone());
+            self.report(Diagnostic::new(format!(
+                "type `{name}` is defined more than once"
+            )));
+            self.current = None;
+        }
+
+        self.types.insert(name, def);
+    }
+
+    /// Define a declared type, returning the data type if it is one.
+    pub fn define_type(&mut self, name: &mir::Name, def: &hir::TypeDef) -> Option<mir::DataDef> {
+        match def {
+            hir::TypeDef::Alias(_) => {
+                self.current = Some(name.clone());
+                self.alias_body(name);
+                self.current = None;
+                None
+            }
+
+            hir::TypeDef::Data(data) => Some(self.define_data(name, data)),
+
+            hir::TypeDef::Newtype(newtype) => {
+                let data = hir::DataDef {
+                    vars: newtype.vars.clone(),
+                    ctors: vec![hir::Constructor {
+                        name: name.clone(),
+                        fields: vec![newtype.repr.clone()],
+                    }],
+                };
+
+                Some(self.define_data(name, &data))
+            }
+        }
+    }
+
+    /// Declare a top-level name, reporting an error if it already exists.
+    pub fn declare_name(&mut self, name: mir::Name, ty: Template) {
+        if self.context.contains_key(&name) {
+            self.report(Diagnostic::new(format!(
+                "`{name}` is defined more than once"
+            )));
+        }
+
+        self.context.insert(name, ty);
+    }
+
+    /// Get the lowered body of the given type alias, lowering it if that has
+    /// not already been done.
+    pub fn alias_body(&mut self, name: &mir::Name) -> Type {
+        let body = match self.types.get_mut(name) {
+            Some(TypeDef::Alias { body, .. }) => std::mem::replace(body, AliasBody::Lowering),
+            _ => unreachable!("`{name}` is not an alias"),
+        };
+
+        let lowered = match body {
+            AliasBody::Lowered(ty) => ty,
+
+            AliasBody::Lowering => {
+                self.report(Diagnostic::new(format!(
+                    "type alias `{name}` expands to itself"
+                )));
+                Type::Error
+            }
+
+            AliasBody::Unlowered(ty) => self.lower_type(&ty, Mutability::Immutable),
+        };
+
+        if let Some(TypeDef::Alias { body, .. }) = self.types.get_mut(name) {
+            *body = AliasBody::Lowered(lowered.clone());
+        }
+
+        lowered
+    }
+
+    /// Declare every constructor of a data type as a function from its fields
+    /// to the data type.
+    pub fn define_data(&mut self, name: &mir::Name, data: &hir::DataDef) -> mir::DataDef {
+        let mut ctors = Vec::with_capacity(data.ctors.len());
+        let args: Vec<_> = data
+            .vars
+            .iter()
+            .map(|var| Type::Named(var.clone(), Vec::new()))
+            .collect();
+
+        for ctor in data.ctors.iter() {
+            self.current = Some(ctor.name.clone());
+
+            let mut ty = Type::Named(name.clone(), args.clone());
+            for field in ctor.fields.iter().rev() {
+                let field = self.lower_type(field, Mutability::Immutable);
+                ty = self.fun_type(field, ty);
+            }
+
+            let ty = Template {
+                params: data.vars.clone(),
+                bounds: Vec::new(),
+                uninst: ty,
+            };
+
+            self.declare_name(ctor.name.clone(), ty);
+            self.ctors.insert(ctor.name.clone(), name.clone());
+            ctors.push(ctor.name.clone());
+
+            self.current = None;
+        }
+
+        mir::DataDef {
+            params: data.vars.clone(),
+            ctors,
+        }
+    }
+}
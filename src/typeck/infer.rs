@@ -1,16 +1,35 @@
 use log::trace;
 
+use super::provenance::Rule;
+use super::solve::Constraint;
 use super::tween;
 use super::tween::Mutability;
+use super::types::Type;
 use super::Checker;
 use crate::hir;
+use crate::mir;
+use crate::{Diagnostic, Regex};
 
 impl Checker {
     pub fn infer_expr(&mut self, expr: hir::Expr) -> tween::Expr {
+        self.with_origin(expr, |this, expr| this.infer_expr_inner(expr))
+    }
+
+    fn infer_expr_inner(&mut self, expr: hir::Expr) -> tween::Expr {
         let (node, ty) = match expr {
             hir::Expr::Anno(expr, ty) => {
                 let ty = self.lower_type(&ty, Mutability::Mutable);
-                return self.check_expr(*expr, ty);
+                return self.with_rule(Rule::Annotation, |this| this.check_expr(*expr, ty));
+            }
+
+            // Without an expected type, the parameter's type is left to be
+            // found from how the body uses it.
+            hir::Expr::Fun(bind, body) => {
+                let bind_ty = self.fresh_type(Mutability::Mutable);
+                let bind = self.with_rule(Rule::Pattern, |this| this.bind(bind, bind_ty.clone()));
+                let body = Box::new(self.infer_expr(*body));
+                let ty = self.fun_type(bind_ty, body.anno.clone());
+                (tween::ExprNode::Fun(bind, body), ty)
             }
 
             hir::Expr::Let {
@@ -20,9 +39,11 @@ impl Checker {
                 elze,
             } => {
                 let bound = Box::new(self.infer_expr(*bound));
-                let pat = self.bind(pat, bound.anno.clone());
+                let pat = self.with_rule(Rule::Pattern, |this| this.bind(pat, bound.anno.clone()));
                 let then = Box::new(self.infer_expr(*then));
-                let elze = Box::new(self.check_expr(*elze, then.anno.clone()));
+                let elze =
+                    self.with_rule(Rule::Else, |this| this.check_expr(*elze, then.anno.clone()));
+                let elze = Box::new(elze);
                 let ty = then.anno.clone();
                 (
                     tween::ExprNode::Let {
@@ -37,44 +58,113 @@ impl Checker {
 
             hir::Expr::Call(func, arg) => {
                 let func = Box::new(self.infer_expr(*func));
-                let (arg_ty, ret_ty) = self.as_fun_ty(func.anno.clone());
-                let arg = Box::new(self.check_expr(*arg, arg_ty));
+                let (arg_ty, ret_ty) =
+                    self.with_rule(Rule::Call, |this| this.as_fun_ty(func.anno.clone()));
+                let arg = self.with_rule(Rule::Argument, |this| this.check_expr(*arg, arg_ty));
+                let arg = Box::new(arg);
                 (tween::ExprNode::Call(func, arg), ret_ty)
             }
 
+            hir::Expr::Binary(op, lhs, rhs) => {
+                let op = match op {
+                    hir::BinOp::Concat => tween::BinOp::Concat,
+                };
+
+                let lhs = Box::new(self.infer_expr(*lhs));
+                let rhs = Box::new(self.infer_expr(*rhs));
+                let ty = self.fresh_type(Mutability::Mutable);
+                self.check_binary(op, ty.clone(), lhs.anno.clone(), rhs.anno.clone());
+                (tween::ExprNode::Binary(op, lhs, rhs), ty)
+            }
+
             hir::Expr::Lit(hir::Literal::Boolean(v)) => (
                 tween::ExprNode::Lit(tween::Literal::Boolean(v)),
                 self.boolean_type(),
             ),
 
-            hir::Expr::Lit(hir::Literal::Regex(v)) => (
-                tween::ExprNode::Lit(tween::Literal::Regex(v)),
-                self.regex_type(),
+            // Without an expected type, integer and string literals infer the
+            // type containing only themselves.
+            hir::Expr::Lit(hir::Literal::Integer(v)) => (
+                tween::ExprNode::Lit(tween::Literal::Integer(v)),
+                Type::Range(v, v.saturating_add(1)),
             ),
 
-            hir::Expr::Name(name) => match self.context.get(&name) {
-                Some(ty) => {
-                    trace!("`{name}` infers {ty:?}");
-                    let ty = ty.clone();
-                    let is_monomorphic = ty.params.is_empty();
-                    let ty = self.instantiate(&ty);
-                    if is_monomorphic {
-                        (tween::ExprNode::Name(name), ty)
-                    } else {
-                        (tween::ExprNode::Instantiated(name), ty)
-                    }
-                }
-                None => (tween::ExprNode::Invalid, self.error_type()),
+            hir::Expr::Lit(hir::Literal::String(v)) => {
+                let ty = self.string_type(Regex::literal(&v), "a string literal");
+                (tween::ExprNode::Lit(tween::Literal::String(v)), ty)
+            }
+
+            hir::Expr::Lit(hir::Literal::Regex(v)) => match self.compile_regex(&v) {
+                Some(v) => (
+                    tween::ExprNode::Lit(tween::Literal::Regex(v)),
+                    self.regex_type(),
+                ),
+                None => (tween::ExprNode::Invalid, self.regex_type()),
             },
 
-            hir::Expr::Impossible => (tween::ExprNode::Impossible, self.bottom_type()),
-            hir::Expr::Invalid => (tween::ExprNode::Invalid, self.error_type()),
+            hir::Expr::Name(name) => return self.infer_name(name, Vec::new()),
 
-            _ => {
-                panic!("error: ambiguous expression")
+            hir::Expr::Instantiate(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.lower_type(arg, Mutability::Mutable))
+                    .collect();
+                return self.infer_name(name, args);
             }
+
+            hir::Expr::Impossible => (tween::ExprNode::Impossible, self.bottom_type()),
+            hir::Expr::Invalid => (tween::ExprNode::Invalid, self.error_type()),
         };
 
         tween::Expr { node, anno: ty }
     }
+
+    /// Infer the type of a name, instantiating it with the given explicit
+    /// type arguments if it is polymorphic.
+    fn infer_name(&mut self, name: mir::Name, mut args: Vec<Type>) -> tween::Expr {
+        if let Some(ty) = self.locals.get(&name) {
+            let anno = ty.clone();
+            return tween::Expr {
+                node: tween::ExprNode::Name(name),
+                anno,
+            };
+        }
+
+        // Unknown names have already been reported while resolving them.
+        let Some(ty) = self.context.get(&name).cloned() else {
+            return tween::Expr {
+                node: tween::ExprNode::Invalid,
+                anno: self.error_type(),
+            };
+        };
+
+        trace!("`{name}` infers {ty:?}");
+
+        if args.len() > ty.params.len() {
+            self.report(Diagnostic::new(format!(
+                "`{name}` takes {} type arguments, but {} were given",
+                ty.params.len(),
+                args.len()
+            )));
+            args.truncate(ty.params.len());
+        }
+
+        let rule = Rule::Instantiate(name.clone());
+        if !ty.bounds.is_empty() {
+            return self.with_rule(rule, |this| this.elaborate_use(name, &ty, args));
+        }
+
+        let (anno, args) = self.with_rule(rule.clone(), |this| this.instantiate_args(&ty, args));
+        if self.builtins.contains(&name) {
+            let constraint = Constraint::Builtin(name.clone(), args.clone());
+            self.with_rule(rule, |this| this.constrain(constraint));
+        }
+
+        let node = match ty.params.is_empty() {
+            true => tween::ExprNode::Name(name),
+            false => tween::ExprNode::Instantiated(name, args),
+        };
+
+        tween::Expr { node, anno }
+    }
 }
@@ -1,7 +1,9 @@
-use super::tween::Mutability;
+use super::data::TypeDef;
+use super::tween::{Mutability, Name};
 use super::types::Type;
 use super::Checker;
 use crate::hir;
+use crate::Diagnostic;
 
 impl Checker {
     pub fn lower_type(&mut self, ty: &hir::Type, mutability: Mutability) -> Type {
@@ -9,18 +11,88 @@ impl Checker {
             hir::Type::Bool => self.boolean_type(),
             hir::Type::Regex => self.regex_type(),
             hir::Type::Range(lo, hi) => Type::Range(*lo, *hi),
-            hir::Type::String(pat) => Type::String(pat.clone()),
+            hir::Type::String(pat) => match self.compile_regex(pat) {
+                Some(pat) if pat.has_anchors() => {
+                    self.report(
+                        Diagnostic::new(format!("`/{}/` cannot contain anchors", pat.as_str()))
+                            .with_note("a string type always matches the whole text"),
+                    );
+                    self.error_type()
+                }
+                Some(pat) => Type::String(pat),
+                None => self.error_type(),
+            },
             hir::Type::Arrow(from, into) => {
-                let from = self.lower_type(&*from, mutability);
-                let into = self.lower_type(&*into, mutability);
+                let from = self.lower_type(from, mutability);
+                let into = self.lower_type(into, mutability);
                 self.fun_type(from, into)
             }
-            hir::Type::Named(name) => Type::Named(name.clone()),
+            hir::Type::Apply(..) => {
+                let mut args = Vec::new();
+                let mut head = ty;
+                while let hir::Type::Apply(ctr, arg) = head {
+                    args.push(self.lower_type(arg, mutability));
+                    head = ctr;
+                }
+                args.reverse();
+
+                match head {
+                    hir::Type::Named(name) => self.lower_named(name, args),
+                    _ => {
+                        self.report(Diagnostic::new(
+                            "only named types can be applied to type arguments",
+                        ));
+                        self.error_type()
+                    }
+                }
+            }
+            hir::Type::Named(name) => self.lower_named(name, Vec::new()),
+            hir::Type::Forall(vars, ty) if vars.is_empty() => self.lower_type(ty, mutability),
+            hir::Type::Forall(vars, ty) => {
+                let ty = self.lower_type(ty, mutability);
+                Type::Forall(vars.clone(), Box::new(ty))
+            }
             hir::Type::Wildcard => self.fresh_type(mutability),
             hir::Type::Invalid => self.error_type(),
         }
     }
 
+    /// Lower a named type applied to the given arguments, expanding it if it
+    /// is an alias. Names that are not declared types refer to type
+    /// variables.
+    fn lower_named(&mut self, name: &Name, args: Vec<Type>) -> Type {
+        let Some(def) = self.types.get(name) else {
+            if !args.is_empty() {
+                self.report(Diagnostic::new(format!(
+                    "`{name}` cannot be applied to type arguments"
+                )));
+                return self.error_type();
+            }
+
+            return Type::Named(name.clone(), args);
+        };
+
+        let params = def.params().to_vec();
+        if params.len() != args.len() {
+            self.report(Diagnostic::new(format!(
+                "`{name}` expects {} type arguments, but {} were given",
+                params.len(),
+                args.len()
+            )));
+            return self.error_type();
+        }
+
+        match def {
+            TypeDef::Data { .. } => Type::Named(name.clone(), args),
+            TypeDef::Alias { .. } => {
+                let body = self.alias_body(name);
+                let vars = params.into_iter().zip(args.iter().cloned()).collect();
+                let body = self.inst_ty(body, &vars);
+                Type::Alias(name.clone(), args, Box::new(body))
+            }
+        }
+    }
+
     pub fn boolean_type(&mut self) -> Type {
         Type::Bool
     }
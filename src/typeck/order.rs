@@ -0,0 +1,205 @@
+use std::collections::{HashMap, HashSet};
+
+use indexmap::IndexMap;
+
+use crate::hir;
+use crate::hir::visit::{walk_expr, walk_pat, walk_type, Visitor};
+use crate::mir::Name;
+
+/// Order the given value definitions such that every definition is checked
+/// after the definitions whose types it depends on. Returns the strongly
+/// connected components of the dependency graph, dependencies first, with the
+/// definitions of each component in declaration order.
+///
+/// A use of a value only depends on it if its annotation is partial, since the
+/// type of a fully annotated value is known before its body is checked.
+pub fn components(values: &IndexMap<Name, hir::ValueDef>) -> Vec<Vec<Name>> {
+    let graph: Vec<Vec<usize>> = values
+        .values()
+        .map(|def| {
+            let mut references = References::default();
+            references.visit_expr(&def.body);
+
+            let mut deps = Vec::new();
+            for name in references.refs {
+                let Some((index, _, dep)) = values.get_full(&name) else {
+                    continue;
+                };
+
+                if is_partial(&dep.anno) && !deps.contains(&index) {
+                    deps.push(index);
+                }
+            }
+
+            deps
+        })
+        .collect();
+
+    let mut tarjan = Tarjan {
+        graph: &graph,
+        index: 0,
+        indices: HashMap::new(),
+        lowlinks: HashMap::new(),
+        stack: Vec::new(),
+        on_stack: HashSet::new(),
+        components: Vec::new(),
+    };
+
+    for node in 0..graph.len() {
+        if !tarjan.indices.contains_key(&node) {
+            tarjan.connect(node);
+        }
+    }
+
+    tarjan
+        .components
+        .into_iter()
+        .map(|mut component| {
+            component.sort_unstable();
+            component
+                .into_iter()
+                .map(|index| values.get_index(index).unwrap().0.clone())
+                .collect()
+        })
+        .collect()
+}
+
+/// Tarjan's strongly connected components algorithm, which finds every
+/// component after all the components reachable from it.
+struct Tarjan<'a> {
+    graph: &'a [Vec<usize>],
+    index: usize,
+    indices: HashMap<usize, usize>,
+    lowlinks: HashMap<usize, usize>,
+    stack: Vec<usize>,
+    on_stack: HashSet<usize>,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    fn connect(&mut self, node: usize) {
+        self.indices.insert(node, self.index);
+        self.lowlinks.insert(node, self.index);
+        self.index += 1;
+
+        self.stack.push(node);
+        self.on_stack.insert(node);
+
+        for &dep in self.graph[node].iter() {
+            if !self.indices.contains_key(&dep) {
+                self.connect(dep);
+                let lowlink = self.lowlinks[&node].min(self.lowlinks[&dep]);
+                self.lowlinks.insert(node, lowlink);
+            } else if self.on_stack.contains(&dep) {
+                let lowlink = self.lowlinks[&node].min(self.indices[&dep]);
+                self.lowlinks.insert(node, lowlink);
+            }
+        }
+
+        if self.lowlinks[&node] == self.indices[&node] {
+            let mut component = Vec::new();
+            loop {
+                let member = self.stack.pop().unwrap();
+                self.on_stack.remove(&member);
+                component.push(member);
+
+                if member == node {
+                    break;
+                }
+            }
+
+            self.components.push(component);
+        }
+    }
+}
+
+/// Collects the free names referred to by an expression.
+#[derive(Default)]
+struct References {
+    /// The number of binders in scope for each locally bound name.
+    bound: HashMap<Name, usize>,
+    refs: Vec<Name>,
+}
+
+impl References {
+    /// Visit `expr` with the names bound by `pat` in scope.
+    fn scoped(&mut self, pat: &hir::Pat, expr: &hir::Expr) {
+        let names = binders(pat);
+        for name in names.iter() {
+            *self.bound.entry(name.clone()).or_default() += 1;
+        }
+
+        self.visit_expr(expr);
+
+        for name in names.iter() {
+            let count = self.bound.get_mut(name).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                self.bound.remove(name);
+            }
+        }
+    }
+}
+
+impl Visitor for References {
+    fn visit_expr(&mut self, expr: &hir::Expr) {
+        match expr {
+            hir::Expr::Fun(pat, body) => self.scoped(pat, body),
+
+            hir::Expr::Let {
+                pat,
+                bound,
+                then,
+                elze,
+            } => {
+                self.visit_expr(bound);
+                self.visit_expr(elze);
+                self.scoped(pat, then);
+            }
+
+            hir::Expr::Name(name) | hir::Expr::Instantiate(name, _) => {
+                if !self.bound.contains_key(name) {
+                    self.refs.push(name.clone());
+                }
+            }
+
+            expr => walk_expr(self, expr),
+        }
+    }
+}
+
+/// The names bound by the given pattern.
+fn binders(pat: &hir::Pat) -> Vec<Name> {
+    struct Binders(Vec<Name>);
+
+    impl Visitor for Binders {
+        fn visit_pat(&mut self, pat: &hir::Pat) {
+            match pat {
+                hir::Pat::Bind(name) => self.0.push(name.clone()),
+                pat => walk_pat(self, pat),
+            }
+        }
+    }
+
+    let mut binders = Binders(Vec::new());
+    binders.visit_pat(pat);
+    binders.0
+}
+
+/// Whether the given annotation leaves any part of the type to be inferred.
+fn is_partial(ty: &hir::Type) -> bool {
+    struct Partial(bool);
+
+    impl Visitor for Partial {
+        fn visit_type(&mut self, ty: &hir::Type) {
+            match ty {
+                hir::Type::Wildcard => self.0 = true,
+                ty => walk_type(self, ty),
+            }
+        }
+    }
+
+    let mut partial = Partial(false);
+    partial.visit_type(ty);
+    partial.0
+}
@@ -0,0 +1,44 @@
+use super::types::Type;
+use super::Checker;
+use crate::{Diagnostic, Regex};
+
+impl Checker {
+    /// Compile the given regex pattern, reporting a diagnostic and returning
+    /// `None` if it is invalid. Compiled regexes are cached, so every distinct
+    /// pattern is only compiled once.
+    pub fn compile_regex(&mut self, pattern: &str) -> Option<Regex> {
+        if let Some(regex) = self.regexes.get(pattern) {
+            return Some(regex.clone());
+        }
+
+        match Regex::new(pattern) {
+            Ok(regex) => {
+                self.regexes.insert(pattern.into(), regex.clone());
+                Some(regex)
+            }
+
+            Err(e) => {
+                self.report(
+                    Diagnostic::new(format!("invalid regex `/{pattern}/`"))
+                        .with_note(e.to_string()),
+                );
+                None
+            }
+        }
+    }
+
+    /// The string type of a regex built by combining others, reporting that
+    /// the type of `what` is too large instead if the regex engine refused it.
+    pub fn string_type(&mut self, regex: Result<Regex, regex::Error>, what: &str) -> Type {
+        match regex {
+            Ok(regex) => Type::String(regex),
+            Err(e) => {
+                self.report(
+                    Diagnostic::new(format!("the string type of {what} is too large"))
+                        .with_note(e.to_string()),
+                );
+                Type::Error
+            }
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! The prelude of types and builtin values declared ahead of every program.
+//! The builtins have no bodies, and are implemented by the interpreter.
+
+use std::collections::HashSet;
+
+use log::trace;
+
+use super::solve::Constraint;
+use super::types::Type;
+use super::Checker;
+use crate::hir::visit::{walk_type, Visitor};
+use crate::hir::{self, TypeDef};
+use crate::mir::Name;
+use crate::Diagnostic;
+
+const SOURCE: &str = include_str!("prelude.il");
+
+/// The declarations of the prelude which are not hidden by the given program.
+/// A prelude type is hidden by a type or class of the same name, or by a
+/// value of the same name as one of its constructors, and a prelude value by
+/// a value of the same name, or by hiding any type its type refers to.
+pub fn visible(prog: &hir::Decls) -> hir::Decls {
+    let mut prelude = crate::syntax::parse(SOURCE).expect("the prelude parses");
+
+    let types: HashSet<&Name> = prog.types.keys().chain(prog.classes.keys()).collect();
+    let mut values: HashSet<&Name> = prog.values.keys().collect();
+    for (name, def) in prog.types.iter() {
+        values.extend(ctors(name, def));
+    }
+
+    for def in prog.classes.values() {
+        values.extend(def.methods.iter().map(|(name, _)| name));
+    }
+
+    let hidden: HashSet<Name> = prelude
+        .types
+        .iter()
+        .filter(|(name, def)| {
+            types.contains(name) || ctors(name, def).any(|ctor| values.contains(ctor))
+        })
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    prelude.types.retain(|name, _| !hidden.contains(name));
+    prelude.values.retain(|name, def| {
+        let mut mentions = Mentions {
+            names: &hidden,
+            found: false,
+        };
+
+        mentions.visit_type(&def.anno);
+        !values.contains(name) && !mentions.found
+    });
+
+    prelude
+}
+
+/// The constructors a type declaration introduces.
+pub fn ctors<'a>(name: &'a Name, def: &'a TypeDef) -> Box<dyn Iterator<Item = &'a Name> + 'a> {
+    match def {
+        TypeDef::Alias(_) => Box::new(std::iter::empty()),
+        TypeDef::Data(def) => Box::new(def.ctors.iter().map(|ctor| &ctor.name)),
+        TypeDef::Newtype(_) => Box::new(std::iter::once(name)),
+    }
+}
+
+/// Finds whether a type refers to any of the given named types.
+struct Mentions<'a> {
+    names: &'a HashSet<Name>,
+    found: bool,
+}
+
+impl Visitor for Mentions<'_> {
+    fn visit_type(&mut self, ty: &hir::Type) {
+        match ty {
+            hir::Type::Named(name) => self.found |= self.names.contains(name),
+            ty => walk_type(self, ty),
+        }
+    }
+}
+
+/// The kinds of type the type parameters of the builtins stand for.
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    Range,
+    String,
+}
+
+impl Kind {
+    fn includes(self, ty: &Type) -> bool {
+        matches!(
+            (self, ty),
+            (Self::Range, Type::Range(..)) | (Self::String, Type::String(_))
+        )
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Range => "an integer range",
+            Self::String => "a string type",
+        }
+    }
+}
+
+/// The kinds of the leading type parameters of a builtin, which are given by
+/// the types of its arguments. Any later parameters are computed from these.
+fn kinds(builtin: &str) -> &'static [Kind] {
+    match builtin {
+        "show_int" => &[Kind::Range],
+        "length" | "matches" | "parse_int" => &[Kind::String],
+        "lt" | "le" | "gt" | "ge" | "eq" | "ne" | "widen" | "clamp" => &[Kind::Range, Kind::Range],
+        "repeat" => &[Kind::String, Kind::Range],
+        _ => &[],
+    }
+}
+
+impl Checker {
+    /// Check the type arguments of a use of a builtin, once those of its
+    /// leading type parameters are known, and solve the rest.
+    pub fn solve_builtin(&mut self, name: Name, args: Vec<Type>) {
+        trace!("Solving Builtin({name:?}, {args:?})");
+        let args: Vec<_> = args.into_iter().map(|arg| self.resolve(arg)).collect();
+        let kinds = kinds(&name);
+
+        let vars: Vec<_> = args
+            .iter()
+            .take(kinds.len())
+            .filter_map(|arg| match arg {
+                Type::Var(_, v) => Some(*v),
+                _ => None,
+            })
+            .collect();
+
+        if !vars.is_empty() {
+            self.block(Constraint::Builtin(name, args), vars);
+            return;
+        }
+
+        let params = self.context[&name].params.clone();
+        let mut ok = true;
+        for ((kind, arg), param) in kinds.iter().zip(args.iter()).zip(params.iter()) {
+            if !kind.includes(arg) && *arg != Type::Error {
+                let arg = self.show(arg.clone());
+                self.report(Diagnostic::new(format!(
+                    "`{name}` needs {} for `{param}`, found `{arg}`",
+                    kind.name()
+                )));
+                ok = false;
+            }
+        }
+
+        if !ok || args.contains(&Type::Error) {
+            return;
+        }
+
+        match (name.as_str(), args.as_slice()) {
+            ("length", [Type::String(pat), n]) => {
+                let (min, max) = pat.lengths();
+                let lo = clamp_length(min);
+                let hi = max.map_or(i64::MAX, |max| clamp_length(max).saturating_add(1));
+                self.check_assignable(n.clone(), Type::Range(lo, hi));
+            }
+
+            ("repeat", [Type::String(pat), Type::Range(lo, hi), r]) => {
+                let min = clamp_count(*lo);
+                let max = clamp_count(hi.saturating_sub(1)).max(min);
+                let ty = self.string_type(pat.repeat(min, max), "`repeat`");
+                self.check_assignable(r.clone(), ty);
+            }
+
+            ("parse_int", [Type::String(pat), n]) => {
+                let (_, max) = pat.lengths();
+                self.check_assignable(n.clone(), written_in(max));
+            }
+
+            ("widen", [Type::Range(lo1, hi1), Type::Range(lo2, hi2)]) if lo1 < lo2 || hi1 > hi2 => {
+                let from = self.show(args[0].clone());
+                let into = self.show(args[1].clone());
+                self.report(Diagnostic::new(format!(
+                    "cannot widen `{from}` to `{into}`, which does not contain it"
+                )));
+            }
+
+            ("clamp", [_, Type::Range(lo, hi)]) if lo >= hi => {
+                let into = self.show(args[1].clone());
+                self.report(Diagnostic::new(format!(
+                    "cannot clamp to `{into}`, which is empty"
+                )));
+            }
+
+            _ => {}
+        }
+    }
+}
+
+fn clamp_count(count: i64) -> u32 {
+    count.clamp(0, u32::MAX as i64) as u32
+}
+
+fn clamp_length(length: u64) -> i64 {
+    length.min(i64::MAX as u64) as i64
+}
+
+/// The range of the integers which can be written in decimal in at most the
+/// given number of characters, if there is a limit.
+fn written_in(max: Option<u64>) -> Type {
+    match max {
+        Some(0) => Type::Range(0, 1),
+        // A negative integer spends one character on its sign.
+        Some(max) if max < 19 => {
+            let hi = 10_i64.pow(max as u32);
+            let lo = 1 - 10_i64.pow(max as u32 - 1);
+            Type::Range(lo, hi)
+        }
+        _ => Type::Range(i64::MIN, i64::MAX),
+    }
+}
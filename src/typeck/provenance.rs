@@ -0,0 +1,249 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use super::tween::{Mutability, Name};
+use super::types::{Type, TypeVar};
+use super::Checker;
+use crate::hir;
+use crate::mir::BinOp;
+
+/// Why a constraint arose or a type variable was solved.
+#[derive(Clone, Debug)]
+pub struct Provenance {
+    /// The declaration it arose in, if any.
+    pub within: Option<Name>,
+    pub rule: Rule,
+    /// The expression it arose from, printed. Printing every expression is
+    /// costly, so this is only recorded when tracing.
+    pub origin: Option<String>,
+}
+
+/// The typing rule which produced a constraint or solution.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Rule {
+    /// A definition checked against its declared type.
+    Definition,
+    /// A definition without a declared type, checked against the type
+    /// inferred for it.
+    Inferred,
+    /// An expression checked against its annotation.
+    Annotation,
+    /// A function applied to an argument.
+    Call,
+    /// An argument passed to a function.
+    Argument,
+    /// The operands and result of a binary operator.
+    Binary(BinOp),
+    /// A literal checked against its expected type.
+    Literal,
+    /// A pattern matched against the value it binds.
+    Pattern,
+    /// The else branch of a `let`, which has the same type as the body.
+    Else,
+    /// A use of a polymorphic name, instantiating its type.
+    Instantiate(Name),
+    /// A dictionary resolved to an instance of a class.
+    Instance(Name),
+    /// A type variable left unsolved in a definition, which became one of its
+    /// type parameters.
+    Generalise,
+    /// A type variable which could not be solved, and was given the error
+    /// type.
+    Unsolved,
+}
+
+/// One step in the chain of reasons a type variable has the type it does.
+#[derive(Clone, Debug)]
+pub struct Explanation {
+    pub var: TypeVar,
+    /// The type the variable was solved to, as it was at the time.
+    pub ty: Type,
+    pub provenance: Provenance,
+}
+
+/// A type variable solved to a type for some reason.
+#[derive(Clone, Debug)]
+pub struct Solution {
+    var: TypeVar,
+    ty: Type,
+    provenance: Provenance,
+}
+
+impl Solution {
+    pub fn var(&self) -> TypeVar {
+        self.var
+    }
+}
+
+impl Checker {
+    /// Run `f` with constraints and solutions attributed to the given rule.
+    pub fn with_rule<T>(&mut self, rule: Rule, f: impl FnOnce(&mut Self) -> T) -> T {
+        let outer = std::mem::replace(&mut self.rule, rule);
+        let result = f(self);
+        self.rule = outer;
+        result
+    }
+
+    /// Run `f` with constraints and solutions attributed to the given
+    /// expression, if tracing is enabled.
+    pub fn with_origin<T>(
+        &mut self,
+        expr: hir::Expr,
+        f: impl FnOnce(&mut Self, hir::Expr) -> T,
+    ) -> T {
+        if self.trace.is_none() {
+            return f(self, expr);
+        }
+
+        // Printed on one line, since it is quoted within others.
+        let origin = crate::pretty::hir_expr(&expr, usize::MAX);
+        let origin: Vec<_> = origin.lines().map(str::trim).collect();
+        let origin = origin.join(" ");
+        let outer = self.origin.replace(origin);
+        let result = f(self, expr);
+        self.origin = outer;
+        result
+    }
+
+    /// The provenance of constraints arising at this point.
+    pub fn provenance(&self) -> Provenance {
+        Provenance {
+            within: self.current.clone(),
+            rule: self.rule.clone(),
+            origin: self.origin.clone(),
+        }
+    }
+
+    /// Record that the type variable `v` was solved to `ty` at this point.
+    pub fn record_solution(&mut self, v: TypeVar, ty: &Type) {
+        self.solutions.push(Solution {
+            var: v,
+            ty: ty.clone(),
+            provenance: self.provenance(),
+        });
+    }
+
+    /// Explain why the given type variable has the type it does.
+    pub fn explain(&self, v: TypeVar) -> Vec<Explanation> {
+        explain(&self.solutions, v)
+    }
+
+    /// Describe why the given type variables have their types, for the notes
+    /// of a diagnostic.
+    pub fn explain_notes(&self, vars: &[TypeVar]) -> Vec<String> {
+        let mut notes = Vec::new();
+        for v in vars {
+            for explanation in self.explain(*v) {
+                let var = Type::Var(Mutability::Mutable, explanation.var);
+                let var = var.display(&self.var_names);
+                let ty = explanation.ty.display(&self.var_names);
+                let note = format!("`{var}` is `{ty}` because of {}", explanation.provenance);
+
+                if !notes.contains(&note) {
+                    notes.push(note);
+                }
+            }
+        }
+
+        notes
+    }
+}
+
+/// Explain why the given type variable has the type it does, as the chain of
+/// solutions leading from it to its type. Type variables solved to each other
+/// are followed until one solved to some other type.
+pub fn explain(solutions: &[Solution], v: TypeVar) -> Vec<Explanation> {
+    let mut chain = Vec::new();
+    let mut v = v;
+
+    while let Some(path) = path_to_solution(solutions, v) {
+        let last = path.last().map(|solution| solution.ty.clone());
+        chain.extend(path.into_iter().map(|solution| Explanation {
+            var: solution.var,
+            ty: solution.ty.clone(),
+            provenance: solution.provenance.clone(),
+        }));
+
+        // A variable solved to an immutable one has the type that one was
+        // eventually solved to.
+        match last {
+            Some(Type::Var(Mutability::Immutable, w)) if !chain.iter().any(|e| e.var == w) => v = w,
+            _ => break,
+        }
+    }
+
+    chain
+}
+
+/// Find the shortest path of solutions from `v` through the mutable type
+/// variables it was solved to (in either direction), ending with the solution
+/// of one of them to some other type.
+fn path_to_solution(solutions: &[Solution], v: TypeVar) -> Option<Vec<&Solution>> {
+    let mut previous: HashMap<TypeVar, (TypeVar, &Solution)> = HashMap::new();
+    let mut queue = VecDeque::from([v]);
+    let mut seen = vec![v];
+
+    while let Some(var) = queue.pop_front() {
+        for solution in solutions.iter() {
+            let next = match solution.ty {
+                Type::Var(Mutability::Mutable, w) if solution.var == var => w,
+                Type::Var(Mutability::Mutable, w) if w == var => solution.var,
+                _ if solution.var == var => {
+                    let mut path = vec![solution];
+                    let mut at = var;
+                    while let Some((prev, solution)) = previous.get(&at) {
+                        path.push(solution);
+                        at = *prev;
+                    }
+
+                    path.reverse();
+                    return Some(path);
+                }
+                _ => continue,
+            };
+
+            if !seen.contains(&next) {
+                seen.push(next);
+                previous.insert(next, (var, solution));
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.rule)?;
+        if let Some(origin) = &self.origin {
+            write!(f, " `{origin}`")?;
+        }
+
+        if let Some(name) = &self.within {
+            write!(f, " in `{name}`")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Definition => write!(f, "the declared type"),
+            Self::Inferred => write!(f, "the definition"),
+            Self::Annotation => write!(f, "a type annotation"),
+            Self::Call => write!(f, "a function call"),
+            Self::Argument => write!(f, "a function argument"),
+            Self::Binary(BinOp::Concat) => write!(f, "the operator `++`"),
+            Self::Literal => write!(f, "a literal"),
+            Self::Pattern => write!(f, "a pattern"),
+            Self::Else => write!(f, "the else branch of a `let`"),
+            Self::Instantiate(name) => write!(f, "the use of `{name}`"),
+            Self::Instance(class) => write!(f, "an instance of `{class}`"),
+            Self::Generalise => write!(f, "generalisation"),
+            Self::Unsolved => write!(f, "an unsolved type"),
+        }
+    }
+}
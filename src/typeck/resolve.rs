@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+
+use indexmap::IndexMap;
+
+use super::context::Template;
+use super::Checker;
+use crate::hir;
+use crate::hir::visit::{walk_expr_mut, walk_pat_mut, VisitorMut};
+use crate::mir::Name;
+use crate::Diagnostic;
+
+impl Checker {
+    /// Resolve every name in the body of a declaration, reporting the names
+    /// which are not in scope.
+    ///
+    /// Every name bound by a pattern is given an identifier which is distinct
+    /// from every global and every other binder in the declaration, so the
+    /// checker never confuses a local with a global or an outer binder it
+    /// shadows. A binder keeps its own name unless that is already taken.
+    pub fn resolve_names(&mut self, mut expr: hir::Expr) -> hir::Expr {
+        let mut resolver = Resolver {
+            globals: &self.context,
+            scopes: Vec::new(),
+            taken: HashSet::new(),
+            diagnostics: Vec::new(),
+        };
+
+        resolver.visit_expr_mut(&mut expr);
+        for diagnostic in resolver.diagnostics {
+            self.report(diagnostic);
+        }
+
+        expr
+    }
+}
+
+struct Resolver<'a> {
+    globals: &'a IndexMap<Name, Template>,
+    /// The names bound by each pattern around the current expression,
+    /// innermost last, along with the identifier each was given.
+    scopes: Vec<Vec<(Name, Name)>>,
+    /// Every identifier given to a binder so far.
+    taken: HashSet<Name>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Resolver<'_> {
+    /// The identifier of the innermost binder of a name, if it is bound by a
+    /// pattern.
+    fn local(&self, name: &str) -> Option<&Name> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(bound, _)| bound == name)
+            .map(|(_, id)| id)
+    }
+
+    /// A fresh identifier for a binder of the given name. Identifiers other
+    /// than the name itself contain a `$`, so cannot clash with a name in the
+    /// source.
+    fn fresh(&mut self, name: &Name) -> Name {
+        let mut id = name.clone();
+        let mut n = 0;
+        while self.globals.contains_key(&id) || self.taken.contains(&id) {
+            n += 1;
+            id = format!("{name}${n}");
+        }
+
+        self.taken.insert(id.clone());
+        id
+    }
+
+    /// Resolve an expression within the scope of the names bound by a
+    /// pattern.
+    fn scoped(&mut self, pat: &mut hir::Pat, expr: &mut hir::Expr) {
+        self.scopes.push(Vec::new());
+        self.visit_pat_mut(pat);
+        self.visit_expr_mut(expr);
+        self.scopes.pop();
+    }
+}
+
+impl VisitorMut for Resolver<'_> {
+    fn visit_expr_mut(&mut self, expr: &mut hir::Expr) {
+        match expr {
+            hir::Expr::Fun(pat, body) => self.scoped(pat, body),
+
+            hir::Expr::Let {
+                pat,
+                bound,
+                then,
+                elze,
+            } => {
+                self.visit_expr_mut(bound);
+                self.scoped(pat, then);
+                self.visit_expr_mut(elze);
+            }
+
+            hir::Expr::Name(name) => {
+                if let Some(id) = self.local(name) {
+                    *name = id.clone();
+                } else if !self.globals.contains_key(name) {
+                    let diagnostic = Diagnostic::new(format!("unknown name `{name}`"));
+                    self.diagnostics.push(diagnostic);
+                    *expr = hir::Expr::Invalid;
+                }
+            }
+
+            hir::Expr::Instantiate(name, _) => {
+                let message = if self.local(name).is_some() {
+                    format!("`{name}` is bound by a pattern, so has no type parameters")
+                } else if !self.globals.contains_key(name) {
+                    format!("unknown name `{name}`")
+                } else {
+                    return walk_expr_mut(self, expr);
+                };
+
+                self.diagnostics.push(Diagnostic::new(message));
+                *expr = hir::Expr::Invalid;
+            }
+
+            expr => walk_expr_mut(self, expr),
+        }
+    }
+
+    fn visit_pat_mut(&mut self, pat: &mut hir::Pat) {
+        let hir::Pat::Bind(name) = pat else {
+            return walk_pat_mut(self, pat);
+        };
+
+        let scope = self.scopes.last().unwrap();
+        if scope.iter().any(|(bound, _)| bound == name) {
+            let diagnostic =
+                Diagnostic::new(format!("`{name}` is bound more than once in a pattern"));
+            self.diagnostics.push(diagnostic);
+        }
+
+        let id = self.fresh(name);
+        let name = std::mem::replace(name, id.clone());
+        self.scopes.last_mut().unwrap().push((name, id));
+    }
+}
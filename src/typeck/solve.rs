@@ -1,15 +1,257 @@
 mod constraint;
+mod worklist;
 
 use log::trace;
 
+use indexmap::IndexMap;
+
+use super::provenance::{Provenance, Rule};
+use super::trace::Event;
 use super::tween::Mutability;
-use super::types::Type;
+use super::types::{Type, TypeVar};
 use super::Checker;
-use crate::mir::Literal;
+use crate::mir::{BinOp, Literal};
+use crate::{Diagnostic, Regex};
 
 pub use constraint::Constraint;
+pub use worklist::{Pending, Worklist};
 
 impl Checker {
+    /// Add a constraint arising in the current declaration to be solved.
+    pub fn constrain(&mut self, constraint: Constraint) {
+        let id = self.generate(&constraint);
+        self.worklist.push(Pending {
+            id,
+            constraint,
+            provenance: self.provenance(),
+        });
+    }
+
+    /// Set aside a constraint which cannot make progress until one of the
+    /// given type variables is solved. The first constraint blocked while
+    /// solving another is taken to be that one, set aside again.
+    pub fn block(&mut self, constraint: Constraint, on: Vec<TypeVar>) {
+        let on: Vec<_> = on.into_iter().map(|v| self.subst.find(v)).collect();
+        trace!("Blocking {constraint:?} on {on:?}");
+        let id = match self.solving {
+            Some(id) if !self.deferred => {
+                self.deferred = true;
+                id
+            }
+            _ => self.generate(&constraint),
+        };
+
+        self.record_block(id, &constraint, &on);
+        self.worklist.block(
+            Pending {
+                id,
+                constraint,
+                provenance: self.provenance(),
+            },
+            on,
+        );
+    }
+
+    /// Solve the type variable `v` to `ty`, waking the constraints waiting on
+    /// either.
+    pub fn solve_var(&mut self, v: TypeVar, ty: Type) {
+        self.worklist.wake(self.subst.find(v));
+        if let Type::Var(Mutability::Mutable, w) = &ty {
+            self.worklist.wake(self.subst.find(*w));
+        }
+
+        self.record_solution(v, &ty);
+        self.record_substitute(v, &ty);
+        self.subst.insert(v, ty);
+    }
+
+    /// Solve constraints until every remaining one is blocked. Returns `false`
+    /// if any are.
+    ///
+    /// Whenever nothing else can make progress, the type variables which only
+    /// literals are waiting on are given the type of those literals.
+    pub fn solve_constraints(&mut self) -> bool {
+        let current = self.current.take();
+        let rule = self.rule.clone();
+        let solving = self.solving.take();
+        loop {
+            while let Some(pending) = self.worklist.pop() {
+                self.current = pending.provenance.within;
+                self.rule = pending.provenance.rule;
+                self.solving = Some(pending.id);
+                self.deferred = false;
+                self.record(|_| Event::Solve { id: pending.id });
+                self.solve(pending.constraint);
+            }
+
+            self.solving = None;
+            if !self.default_literals() {
+                break;
+            }
+        }
+
+        self.solving = solving;
+        self.current = current;
+        self.rule = rule;
+        !self.worklist.is_stuck()
+    }
+
+    /// Solve every unsolved type variable which blocked literals are checked
+    /// against to the type those literals would infer without an expected
+    /// type, widened to include all of them: the smallest range containing
+    /// every integer, or the regex matching exactly every string. Returns
+    /// whether any variable was solved.
+    fn default_literals(&mut self) -> bool {
+        let mut defaults: IndexMap<TypeVar, (Vec<Literal>, Provenance)> = IndexMap::new();
+        for (lit, v, provenance) in self.worklist.blocked_literals() {
+            let v = self.subst.find(v);
+            if !self.subst.contains_key(&v) {
+                let (lits, _) = defaults.entry(v).or_insert((Vec::new(), provenance));
+                lits.push(lit);
+            }
+        }
+
+        let solved = !defaults.is_empty();
+        for (v, (lits, provenance)) in defaults {
+            trace!("Defaulting {v:?} to the type of {lits:?}");
+            self.current = provenance.within;
+            let ty = self.literals_type(&lits);
+            self.with_rule(Rule::Literal, |this| this.solve_var(v, ty));
+        }
+
+        solved
+    }
+
+    /// The least type of all the given literals, or that of the first if they
+    /// are of different kinds.
+    fn literals_type(&mut self, lits: &[Literal]) -> Type {
+        match &lits[0] {
+            Literal::Boolean(_) => self.boolean_type(),
+            Literal::Regex(_) => self.regex_type(),
+
+            Literal::Integer(_) => {
+                let ints = lits.iter().filter_map(|lit| match lit {
+                    Literal::Integer(v) => Some(*v),
+                    _ => None,
+                });
+                let lo = ints.clone().min().unwrap();
+                let hi = ints.max().unwrap();
+                Type::Range(lo, hi.saturating_add(1))
+            }
+
+            Literal::String(_) => {
+                let mut texts: Vec<&str> = Vec::new();
+                for lit in lits {
+                    if let Literal::String(v) = lit {
+                        if !texts.contains(&v.as_str()) {
+                            texts.push(v);
+                        }
+                    }
+                }
+
+                let mut pat = Regex::literal(texts[0]);
+                for text in &texts[1..] {
+                    pat = pat.and_then(|pat| pat.alt(&Regex::literal(text)?));
+                }
+
+                self.string_type(pat, "these string literals")
+            }
+        }
+    }
+
+    /// Report every constraint which is still blocked along with the type
+    /// variables it waits on, and give every type variable they mention the
+    /// error type.
+    pub fn report_stuck(&mut self) {
+        let blocked = self.worklist.take_blocked();
+        for blocked in blocked.iter() {
+            trace!("Stuck {blocked:?}");
+            self.record(|_| Event::Stuck {
+                id: blocked.pending.id,
+            });
+            let message = self.describe(&blocked.pending.constraint);
+            let vars: Vec<_> = blocked
+                .on
+                .iter()
+                .map(|v| format!("`{}`", self.show(Type::Var(Mutability::Mutable, *v))))
+                .collect();
+
+            let provenance = &blocked.pending.provenance;
+            let diagnostic = Diagnostic::new(message)
+                .within(provenance.within.clone())
+                .with_note(format!("arising from {}", provenance.rule))
+                .with_note(format!("waiting on {}", vars.join(", ")));
+            self.diagnostics.push(diagnostic);
+            self.var_names.take();
+        }
+
+        for blocked in blocked {
+            let mut vars = Vec::new();
+            for ty in blocked.pending.constraint.types() {
+                self.apply_subst(ty).free_vars(&mut vars);
+            }
+
+            if let Constraint::Class(_, _, dict, _) = blocked.pending.constraint {
+                self.invalid_dict(dict);
+            }
+
+            self.current = blocked.pending.provenance.within;
+            self.with_rule(Rule::Unsolved, |this| {
+                for v in vars {
+                    if !this.subst.contains_key(&v) {
+                        this.solve_var(v, Type::Error);
+                    }
+                }
+            });
+        }
+
+        self.current = None;
+
+        self.solve_constraints();
+    }
+
+    fn describe(&self, constraint: &Constraint) -> String {
+        match constraint {
+            Constraint::FromLit(lit, _) => {
+                format!("cannot infer the type of the literal `{}`", lit_name(lit))
+            }
+
+            Constraint::Assignable(into, from) => {
+                let into = self.show(into.clone());
+                let from = self.show(from.clone());
+                format!("cannot tell whether `{from}` is assignable to `{into}`")
+            }
+
+            Constraint::Instantiate(_, _, ty) => {
+                let ty = self.show(ty.clone());
+                format!("cannot infer the type `{ty}` to instantiate")
+            }
+
+            Constraint::Binary(op, _, lhs, rhs) => {
+                let lhs = self.show(lhs.clone());
+                let rhs = self.show(rhs.clone());
+                let op = op_name(*op);
+                format!("cannot infer the operand types of `{op}`, found `{lhs}` and `{rhs}`")
+            }
+
+            Constraint::Class(class, ty, _, _) => {
+                let ty = self.show(ty.clone());
+                format!("cannot infer which instance of `{class}` to use for `{ty}`")
+            }
+
+            Constraint::Builtin(name, args) => {
+                let args: Vec<_> = args
+                    .iter()
+                    .map(|arg| format!("`{}`", self.show(arg.clone())))
+                    .collect();
+                format!(
+                    "cannot infer the type arguments of `{name}`, found {}",
+                    args.join(", ")
+                )
+            }
+        }
+    }
+
     pub fn solve(&mut self, ctr: Constraint) {
         match ctr {
             Constraint::FromLit(lit, ty) => {
@@ -22,36 +264,158 @@ impl Checker {
                 self.check_assignable(into, from);
             }
 
-            Constraint::Instantiate(vars, v, ty) => {
-                let ty = self.inst_ty(ty, &vars);
-                self.check_assignable(Type::Var(Mutability::Mutable, v), ty);
+            Constraint::Instantiate(vars, v, ty) => match self.resolve(ty) {
+                Type::Var(mutability, w) => {
+                    let ty = Type::Var(mutability, w);
+                    self.block(Constraint::Instantiate(vars, v, ty), vec![w]);
+                }
+
+                ty => {
+                    let ty = self.inst_ty(ty, &vars);
+                    self.check_assignable(Type::Var(Mutability::Mutable, v), ty);
+                }
+            },
+
+            Constraint::Binary(op, into, lhs, rhs) => {
+                trace!("Solving Binary({op:?}, {into:?}, {lhs:?}, {rhs:?})");
+                self.solve_binary(op, into, lhs, rhs);
             }
+
+            Constraint::Class(class, ty, dict, given) => {
+                trace!("Solving Class({class:?}, {ty:?}, {dict:?})");
+                self.solve_class(class, ty, dict, given);
+            }
+
+            Constraint::Builtin(name, args) => self.solve_builtin(name, args),
         }
     }
 
-    fn solve_from_lit(&mut self, lit: Literal, ty: Type) {
-        match (lit, ty) {
-            (Literal::Boolean(_), Type::Bool) => (),
-            (Literal::Integer(val), Type::Range(lo, hi)) => {
-                assert!(lo <= val && val < hi);
+    /// Follow the substitution of the given type and expand aliases until it
+    /// is neither a solved type variable nor an alias.
+    pub fn resolve(&self, ty: Type) -> Type {
+        match ty {
+            Type::Var(mutability, v) => match self.subst.get(&v) {
+                Some(ty) => self.resolve(ty.clone()),
+                None => Type::Var(mutability, self.subst.find(v)),
+            },
+            Type::Alias(_, _, ty) => self.resolve(*ty),
+            ty => ty,
+        }
+    }
+
+    /// Replace every solved type variable in the given type by its solution,
+    /// and every rigid type variable by the name it was skolemised from.
+    pub fn apply_subst(&self, ty: Type) -> Type {
+        match ty {
+            Type::Var(_, v) if self.subst.contains_key(&v) => {
+                self.apply_subst(self.subst.get(&v).unwrap().clone())
             }
 
-            (Literal::Regex(_), Type::Regex) => (),
-            (Literal::String(val), Type::String(pat)) => {
-                assert!(pat.is_match(&val));
+            Type::Var(_, v) if self.skolems.contains_key(&v) => {
+                Type::Named(self.skolems.get(&v).unwrap().clone(), Vec::new())
             }
 
-            (lit, Type::Var(mutability, v)) => {
-                if let Some(ty) = self.subst.get(&v) {
-                    let ty = ty.clone();
-                    self.solve_from_lit(lit, ty);
-                } else {
-                    self.worklist
-                        .push(Constraint::FromLit(lit, Type::Var(mutability, v)))
-                }
+            Type::Var(mutability, v) => Type::Var(mutability, self.subst.find(v)),
+
+            Type::Arrow(from, into) => {
+                let from = self.apply_subst(*from);
+                let into = self.apply_subst(*into);
+                Type::Arrow(Box::new(from), Box::new(into))
+            }
+
+            Type::Named(name, args) => {
+                let args = args.into_iter().map(|arg| self.apply_subst(arg)).collect();
+                Type::Named(name, args)
+            }
+
+            Type::Alias(name, args, ty) => {
+                let args = args.into_iter().map(|arg| self.apply_subst(arg)).collect();
+                let ty = self.apply_subst(*ty);
+                Type::Alias(name, args, Box::new(ty))
+            }
+
+            Type::Forall(vars, ty) => {
+                let ty = self.apply_subst(*ty);
+                Type::Forall(vars, Box::new(ty))
+            }
+
+            ty => ty,
+        }
+    }
+
+    fn solve_from_lit(&mut self, lit: Literal, ty: Type) {
+        let fits = match (&lit, self.resolve(ty.clone())) {
+            (_, Type::Var(mutability, v)) => {
+                self.block(Constraint::FromLit(lit, Type::Var(mutability, v)), vec![v]);
+                return;
             }
 
-            (..) => panic!("literal does not conform to type"),
+            (Literal::Boolean(_), Type::Bool) | (Literal::Regex(_), Type::Regex) => true,
+            (Literal::Integer(val), Type::Range(lo, hi)) => lo <= *val && *val < hi,
+            (Literal::String(val), Type::String(pat)) => pat.is_match(val),
+            (_, Type::Error) => true,
+            _ => false,
+        };
+
+        if !fits {
+            let mut vars = Vec::new();
+            ty.free_vars(&mut vars);
+
+            let lit = lit_name(&lit);
+            let ty = self.show(ty);
+            let mut diagnostic = Diagnostic::new(format!(
+                "mismatched types: expected `{ty}`, found the literal `{lit}`"
+            ));
+            for note in self.explain_notes(&vars) {
+                diagnostic = diagnostic.with_note(note);
+            }
+
+            self.report(diagnostic);
         }
     }
+
+    fn solve_binary(&mut self, op: BinOp, into: Type, lhs: Type, rhs: Type) {
+        let lhs = self.resolve(lhs);
+        let rhs = self.resolve(rhs);
+
+        let ty = match (op, lhs, rhs) {
+            (op, lhs @ Type::Var(_, v), rhs) | (op, lhs, rhs @ Type::Var(_, v)) => {
+                self.block(Constraint::Binary(op, into, lhs, rhs), vec![v]);
+                return;
+            }
+
+            (BinOp::Concat, Type::String(pat1), Type::String(pat2)) => {
+                self.string_type(pat1.concat(&pat2), "`++`")
+            }
+
+            (_, Type::Error, _) | (_, _, Type::Error) => Type::Error,
+
+            (op, lhs, rhs) => {
+                let op = op_name(op);
+                let lhs = self.show(lhs);
+                let rhs = self.show(rhs);
+                self.report(Diagnostic::new(format!(
+                    "`{op}` cannot be applied to `{lhs}` and `{rhs}`"
+                )));
+                Type::Error
+            }
+        };
+
+        self.check_assignable(into, ty);
+    }
+}
+
+fn lit_name(lit: &Literal) -> String {
+    match lit {
+        Literal::Boolean(v) => v.to_string(),
+        Literal::Integer(v) => v.to_string(),
+        Literal::String(v) => format!("{v:?}"),
+        Literal::Regex(v) => format!("/{}/", v.as_str()),
+    }
+}
+
+fn op_name(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Concat => "++",
+    }
 }
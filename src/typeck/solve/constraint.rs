@@ -1,11 +1,61 @@
 use std::collections::HashMap;
+use std::fmt;
 
-use crate::mir::{Literal, Name};
+use crate::mir::{BinOp, Literal, Name};
+use crate::typeck::class::DictId;
+use crate::typeck::context::Bound;
+use crate::typeck::tween::Mutability;
 use crate::typeck::types::{Type, TypeVar};
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Constraint {
     FromLit(Literal, Type),
     Assignable(Type, Type),
     Instantiate(HashMap<Name, Type>, TypeVar, Type),
+    /// The result type of a binary operation applied to operands of the given
+    /// types.
+    Binary(BinOp, Type, Type, Type),
+    /// The dictionary must be resolved to an instance of the class for the
+    /// type, or to a dictionary given by one of the bounds.
+    Class(Name, Type, DictId, Vec<Bound>),
+    /// The type arguments of a use of a builtin of the prelude, which must be
+    /// of the kinds it takes.
+    Builtin(Name, Vec<Type>),
+}
+
+impl Constraint {
+    /// The types this constraint is over.
+    pub fn types(&self) -> Vec<Type> {
+        match self {
+            Self::FromLit(_, ty) | Self::Class(_, ty, _, _) => vec![ty.clone()],
+            Self::Assignable(into, from) => vec![into.clone(), from.clone()],
+            Self::Instantiate(vars, w, ty) => {
+                let mut types: Vec<_> = vars.values().cloned().collect();
+                types.push(Type::Var(Mutability::Mutable, *w));
+                types.push(ty.clone());
+                types
+            }
+            Self::Binary(_, res, lhs, rhs) => vec![res.clone(), lhs.clone(), rhs.clone()],
+            Self::Builtin(_, args) => args.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FromLit(lit, ty) => write!(f, "{} : {ty}", super::lit_name(lit)),
+            Self::Assignable(into, from) => write!(f, "{from} <: {into}"),
+            Self::Instantiate(_, v, ty) => {
+                let v = Type::Var(Mutability::Mutable, *v);
+                write!(f, "{v} := instance of {ty}")
+            }
+            Self::Binary(BinOp::Concat, into, lhs, rhs) => write!(f, "{lhs} ++ {rhs} <: {into}"),
+            Self::Class(class, ty, dict, _) => write!(f, "{class} {ty} for dict #{}", dict.0),
+            Self::Builtin(name, args) => {
+                let args: Vec<_> = args.iter().map(|arg| arg.to_string()).collect();
+                write!(f, "builtin {name} [{}]", args.join(", "))
+            }
+        }
+    }
 }
@@ -0,0 +1,114 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::mir::Literal;
+use crate::typeck::provenance::Provenance;
+use crate::typeck::trace::ConstraintId;
+use crate::typeck::tween::Mutability;
+use crate::typeck::types::{Type, TypeVar};
+
+use super::Constraint;
+
+/// The constraints left to solve. Constraints which cannot make progress until
+/// some type variable is solved are set aside, and only retried once one of
+/// the variables they are blocked on is.
+#[derive(Clone, Debug, Default)]
+pub struct Worklist {
+    ready: VecDeque<Pending>,
+    /// Every blocked constraint, or `None` where it has since been woken.
+    blocked: Vec<Option<Blocked>>,
+    /// The blocked constraints waiting on each type variable.
+    waiting: HashMap<TypeVar, Vec<usize>>,
+}
+
+/// A constraint along with where it arose.
+#[derive(Clone, Debug)]
+pub struct Pending {
+    pub id: ConstraintId,
+    pub constraint: Constraint,
+    pub provenance: Provenance,
+}
+
+/// A constraint waiting for one of the given type variables to be solved.
+#[derive(Clone, Debug)]
+pub struct Blocked {
+    pub pending: Pending,
+    pub on: Vec<TypeVar>,
+}
+
+impl Worklist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a constraint to be solved as soon as possible.
+    pub fn push(&mut self, pending: Pending) {
+        self.ready.push_back(pending);
+    }
+
+    /// Get the next constraint which may make progress, if any.
+    pub fn pop(&mut self) -> Option<Pending> {
+        self.ready.pop_front()
+    }
+
+    /// Set aside a constraint until one of the given type variables is
+    /// solved. The variables should be the representatives of their
+    /// substitution classes.
+    pub fn block(&mut self, pending: Pending, on: Vec<TypeVar>) {
+        let index = self.blocked.len();
+        for v in on.iter() {
+            self.waiting.entry(*v).or_default().push(index);
+        }
+
+        self.blocked.push(Some(Blocked { pending, on }));
+    }
+
+    /// Make every constraint waiting on the given type variable ready to be
+    /// retried.
+    pub fn wake(&mut self, v: TypeVar) {
+        for index in self.waiting.remove(&v).into_iter().flatten() {
+            if let Some(blocked) = self.blocked[index].take() {
+                self.ready.push_back(blocked.pending);
+            }
+        }
+    }
+
+    /// Whether any constraints are still blocked.
+    pub fn is_stuck(&self) -> bool {
+        self.blocked.iter().any(Option::is_some)
+    }
+
+    /// The literals still waiting for the mutable type variable they are
+    /// checked against to be solved, in the order they were blocked, along
+    /// with where each arose.
+    pub fn blocked_literals(&self) -> Vec<(Literal, TypeVar, Provenance)> {
+        self.blocked
+            .iter()
+            .flatten()
+            .filter_map(|blocked| match &blocked.pending.constraint {
+                Constraint::FromLit(lit, Type::Var(Mutability::Mutable, v)) => {
+                    Some((lit.clone(), *v, blocked.pending.provenance.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Remove every blocked constraint, in the order they were blocked.
+    pub fn take_blocked(&mut self) -> Vec<Blocked> {
+        self.waiting.clear();
+        self.blocked.drain(..).flatten().collect()
+    }
+
+    /// Every constraint left to solve.
+    pub fn iter(&self) -> impl Iterator<Item = &Constraint> {
+        let blocked = self
+            .blocked
+            .iter()
+            .flatten()
+            .map(|blocked| &blocked.pending);
+        self.ready
+            .iter()
+            .chain(blocked)
+            .map(|pending| &pending.constraint)
+    }
+}
@@ -0,0 +1,112 @@
+use std::cell::Cell;
+use std::cmp::Ordering;
+
+use super::tween::Mutability;
+use super::types::{Type, TypeVar};
+
+/// The solutions of the type variables, kept as a union-find forest. Type
+/// variables solved to each other share a single representative, so finding
+/// the solution of a variable never chases a chain of variables. Lookups
+/// compress paths as they go, and unions are by rank.
+///
+/// A variable may still be solved to an immutable type variable, which it
+/// does not share a representative with, since it should only be solved
+/// through the immutable occurrence.
+#[derive(Clone, Debug, Default)]
+pub struct Subst {
+    parents: Vec<Cell<usize>>,
+    ranks: Vec<u8>,
+    /// The solution of each representative, if it has one.
+    solutions: Vec<Option<Type>>,
+    /// The first created of the variables each representative stands for.
+    oldest: Vec<usize>,
+}
+
+impl Subst {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a fresh, unsolved type variable.
+    pub fn fresh(&mut self) -> TypeVar {
+        let v = self.parents.len();
+        self.parents.push(Cell::new(v));
+        self.ranks.push(0);
+        self.solutions.push(None);
+        self.oldest.push(v);
+        TypeVar(v)
+    }
+
+    /// The solution of the given type variable, if any.
+    pub fn get(&self, v: &TypeVar) -> Option<&Type> {
+        self.solutions[self.root(v.0)].as_ref()
+    }
+
+    pub fn contains_key(&self, v: &TypeVar) -> bool {
+        self.get(v).is_some()
+    }
+
+    /// Whether the given type variables have been solved to each other.
+    pub fn same(&self, v: &TypeVar, w: &TypeVar) -> bool {
+        self.root(v.0) == self.root(w.0)
+    }
+
+    /// The representative of all the type variables solved to the given one.
+    pub fn find(&self, v: TypeVar) -> TypeVar {
+        TypeVar(self.root(v.0))
+    }
+
+    /// The first created of the type variables solved to the given one. Type
+    /// variables are created in order, so it is the one in the outermost
+    /// scope.
+    pub fn oldest(&self, v: TypeVar) -> TypeVar {
+        TypeVar(self.oldest[self.root(v.0)])
+    }
+
+    /// Solve the unsolved type variable `v` to `ty`.
+    pub fn insert(&mut self, v: TypeVar, ty: Type) {
+        let root = self.root(v.0);
+        debug_assert!(self.solutions[root].is_none());
+
+        match ty {
+            Type::Var(Mutability::Mutable, w) => self.union(root, self.root(w.0)),
+            ty => self.solutions[root] = Some(ty),
+        }
+    }
+
+    fn union(&mut self, v: usize, w: usize) {
+        if v == w {
+            return;
+        }
+
+        let (root, child) = match self.ranks[v].cmp(&self.ranks[w]) {
+            Ordering::Less => (w, v),
+            Ordering::Greater => (v, w),
+            Ordering::Equal => {
+                self.ranks[v] += 1;
+                (v, w)
+            }
+        };
+
+        self.parents[child].set(root);
+        self.oldest[root] = self.oldest[root].min(self.oldest[child]);
+        if self.solutions[root].is_none() {
+            self.solutions[root] = self.solutions[child].take();
+        }
+    }
+
+    /// Find the representative of the given variable, halving the path to it
+    /// along the way.
+    fn root(&self, mut v: usize) -> usize {
+        loop {
+            let parent = self.parents[v].get();
+            if parent == v {
+                return v;
+            }
+
+            let grandparent = self.parents[parent].get();
+            self.parents[v].set(grandparent);
+            v = grandparent;
+        }
+    }
+}
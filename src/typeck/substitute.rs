@@ -1,10 +1,12 @@
 use log::trace;
 
-use super::tween;
+use super::provenance::Rule;
+use super::tween::{self, Mutability};
 use super::types::Type;
 use super::Checker;
 use crate::mir;
 use crate::types as varless;
+use crate::Diagnostic;
 
 impl Checker {
     pub fn substitute(&mut self, expr: tween::Expr) -> mir::Expr {
@@ -41,9 +43,30 @@ impl Checker {
                 mir::ExprNode::Call(func, expr)
             }
 
+            tween::ExprNode::Binary(op, lhs, rhs) => {
+                let lhs = Box::new(self.substitute(*lhs));
+                let rhs = Box::new(self.substitute(*rhs));
+
+                mir::ExprNode::Binary(op, lhs, rhs)
+            }
+
             tween::ExprNode::Lit(lit) => mir::ExprNode::Lit(lit),
             tween::ExprNode::Name(name) => mir::ExprNode::Name(name),
-            tween::ExprNode::Instantiated(name) => mir::ExprNode::Instantiated(name),
+            tween::ExprNode::Instantiated(name, args) => {
+                let args = args.into_iter().map(|arg| self.subst_type(arg)).collect();
+                mir::ExprNode::Instantiated(name, args)
+            }
+            tween::ExprNode::Dict(id) => match self.dicts[id.0].take() {
+                Some(dict) => return self.substitute(dict),
+                None => {
+                    let ty = self.show(expr.anno.clone());
+                    self.report(
+                        Diagnostic::new("unresolved dictionary")
+                            .with_note(format!("no instance was chosen for `{ty}`")),
+                    );
+                    mir::ExprNode::Invalid
+                }
+            },
             tween::ExprNode::Impossible => mir::ExprNode::Impossible,
             tween::ExprNode::Invalid => mir::ExprNode::Invalid,
         };
@@ -69,13 +92,25 @@ impl Checker {
                 if let Some(ty) = self.subst.get(&v) {
                     let ty = ty.clone();
                     return self.subst_type(ty);
+                } else if let Some(name) = self.skolems.get(&v) {
+                    varless::Type::Named(name.clone(), Vec::new())
                 } else {
                     trace!("Unsolved type var {v:?}");
-                    trace!("Subst {:?}", self.subst);
-                    panic!("unsolved type var!");
+                    let var = self.show(Type::Var(Mutability::Mutable, v));
+                    self.report(
+                        Diagnostic::new("type annotations needed")
+                            .with_note(format!("cannot infer `{var}`")),
+                    );
+                    self.with_rule(Rule::Unsolved, |this| this.solve_var(v, Type::Error));
+                    varless::Type::Error
                 }
             }
-            Type::Named(name) => varless::Type::Named(name),
+            Type::Named(name, args) => {
+                let args = args.into_iter().map(|arg| self.subst_type(arg)).collect();
+                varless::Type::Named(name, args)
+            }
+            Type::Alias(_, _, ty) => return self.subst_type(*ty),
+            Type::Forall(vars, ty) => varless::Type::Forall(vars, self.subst_type(*ty)),
             Type::Error => varless::Type::Error,
         };
 
@@ -100,6 +135,7 @@ impl Checker {
             }
             tween::Pat::Lit(lit) => mir::Pat::Lit(lit),
             tween::Pat::Wildcard => mir::Pat::Wildcard,
+            tween::Pat::Invalid => mir::Pat::Invalid,
         }
     }
 }
@@ -0,0 +1,279 @@
+use std::fmt::{self, Write};
+
+use super::provenance::{explain, Solution};
+use super::solve::Constraint;
+use super::tween::Mutability;
+use super::types::{Type, TypeVar};
+use super::Checker;
+
+/// Identifies a constraint within one run of the checker.
+pub type ConstraintId = usize;
+
+/// A record of how the checker generated and solved constraints, for
+/// debugging the checker itself. Types are recorded as they were displayed at
+/// the time of each event.
+#[derive(Clone, Debug, Default)]
+pub struct Trace {
+    events: Vec<Event>,
+    /// Every solution of a type variable, in the order they were made.
+    pub(super) solutions: Vec<Solution>,
+}
+
+/// One step in the chain of reasons a type variable has the type it does.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub var: String,
+    /// The type the variable was solved to, as it was at the time.
+    pub ty: String,
+    pub provenance: String,
+}
+
+#[derive(Clone, Debug)]
+pub enum Event {
+    /// A constraint was generated, either while checking a declaration or
+    /// while solving the parent constraint.
+    Generate {
+        id: ConstraintId,
+        parent: Option<ConstraintId>,
+        constraint: String,
+        provenance: String,
+    },
+    /// A constraint was taken from the worklist to be solved.
+    Solve { id: ConstraintId },
+    /// A constraint was set aside until one of the type variables is solved.
+    Block {
+        id: ConstraintId,
+        constraint: String,
+        on: Vec<String>,
+    },
+    /// A type variable was solved, possibly while solving a constraint.
+    Substitute {
+        var: String,
+        ty: String,
+        by: Option<ConstraintId>,
+        provenance: String,
+    },
+    /// A constraint was still blocked once solving finished.
+    Stuck { id: ConstraintId },
+}
+
+impl Trace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every event, in the order they happened.
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Explain why a type variable, written as in the events, has the type it
+    /// does, as the chain of solutions leading from it to its type.
+    pub fn explain(&self, var: &str) -> Vec<Step> {
+        let solved = self.solutions.iter().find_map(|solution| {
+            let v = solution.var();
+            match Type::Var(Mutability::Mutable, v).to_string() == var {
+                true => Some(v),
+                false => None,
+            }
+        });
+
+        let Some(v) = solved else {
+            return Vec::new();
+        };
+
+        explain(&self.solutions, v)
+            .into_iter()
+            .map(|explanation| Step {
+                var: Type::Var(Mutability::Mutable, explanation.var).to_string(),
+                ty: explanation.ty.to_string(),
+                provenance: explanation.provenance.to_string(),
+            })
+            .collect()
+    }
+
+    /// Render the constraint graph in the Graphviz dot language. Constraints
+    /// are boxes, with edges to the constraints generated while solving them,
+    /// to the type variables they waited on, and to the type variables they
+    /// solved.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        self.write_dot(&mut dot).unwrap();
+        dot
+    }
+
+    fn write_dot(&self, dot: &mut String) -> fmt::Result {
+        writeln!(dot, "digraph constraints {{")?;
+        writeln!(dot, "  node [fontname=\"monospace\"];")?;
+
+        let mut vars = Vec::new();
+        for event in self.events.iter() {
+            match event {
+                Event::Generate {
+                    id,
+                    parent,
+                    constraint,
+                    provenance,
+                } => {
+                    let label = quote(&format!("#{id}\n{constraint}\n{provenance}"));
+                    writeln!(dot, "  c{id} [shape=box, label={label}];")?;
+                    if let Some(parent) = parent {
+                        writeln!(dot, "  c{parent} -> c{id} [label=\"generated\"];")?;
+                    }
+                }
+
+                Event::Solve { .. } => {}
+
+                Event::Block { id, on, .. } => {
+                    for v in on {
+                        var_node(dot, &mut vars, v)?;
+                        let v = quote(v);
+                        writeln!(dot, "  c{id} -> {v} [style=dashed, label=\"waits on\"];")?;
+                    }
+                }
+
+                // Solutions made outside of any constraint, such as by
+                // generalisation, hang off the variable itself.
+                Event::Substitute {
+                    var,
+                    ty,
+                    by,
+                    provenance,
+                } => {
+                    var_node(dot, &mut vars, var)?;
+                    let v = quote(var);
+                    match by {
+                        Some(id) => {
+                            let label = quote(&format!(":= {ty}"));
+                            writeln!(dot, "  c{id} -> {v} [label={label}];")?;
+                        }
+
+                        None => {
+                            let node = quote(&format!("{var} := {ty}\n{provenance}"));
+                            writeln!(dot, "  {node} [shape=plaintext];")?;
+                            writeln!(dot, "  {v} -> {node} [style=dotted];")?;
+                        }
+                    }
+                }
+
+                Event::Stuck { id } => writeln!(dot, "  c{id} [color=red];")?,
+            }
+        }
+
+        writeln!(dot, "}}")
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for event in self.events.iter() {
+            match event {
+                Event::Generate {
+                    id,
+                    parent,
+                    constraint,
+                    provenance,
+                } => {
+                    write!(f, "generate #{id} `{constraint}` from {provenance}")?;
+                    if let Some(parent) = parent {
+                        write!(f, " while solving #{parent}")?;
+                    }
+                }
+
+                Event::Solve { id } => write!(f, "solve #{id}")?,
+
+                Event::Block { id, constraint, on } => {
+                    write!(f, "block #{id} `{constraint}` on {}", on.join(", "))?
+                }
+
+                Event::Substitute {
+                    var,
+                    ty,
+                    by,
+                    provenance,
+                } => {
+                    write!(f, "substitute {var} := {ty} because of {provenance}")?;
+                    if let Some(id) = by {
+                        write!(f, " while solving #{id}")?;
+                    }
+                }
+
+                Event::Stuck { id } => write!(f, "stuck #{id}")?,
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Checker {
+    /// Record an event if tracing is enabled. The event is only built if it
+    /// will be recorded.
+    pub fn record(&mut self, event: impl FnOnce(&Self) -> Event) {
+        if self.trace.is_none() {
+            return;
+        }
+
+        let event = event(self);
+        if let Some(trace) = &mut self.trace {
+            trace.events.push(event);
+        }
+    }
+
+    /// Allocate an id for a newly generated constraint.
+    pub fn generate(&mut self, constraint: &Constraint) -> ConstraintId {
+        let id = self.constraints;
+        self.constraints += 1;
+        self.record(|this| Event::Generate {
+            id,
+            parent: this.solving,
+            constraint: constraint.to_string(),
+            provenance: this.provenance().to_string(),
+        });
+
+        id
+    }
+
+    /// Record that a constraint was blocked on the given type variables.
+    pub fn record_block(&mut self, id: ConstraintId, constraint: &Constraint, on: &[TypeVar]) {
+        self.record(|_| Event::Block {
+            id,
+            constraint: constraint.to_string(),
+            on: on
+                .iter()
+                .map(|v| Type::Var(Mutability::Mutable, *v).to_string())
+                .collect(),
+        });
+    }
+
+    /// Record that the type variable `v` was solved to `ty`.
+    pub fn record_substitute(&mut self, v: TypeVar, ty: &Type) {
+        self.record(|this| Event::Substitute {
+            var: Type::Var(Mutability::Mutable, v).to_string(),
+            ty: ty.to_string(),
+            by: this.solving,
+            provenance: this.provenance().to_string(),
+        });
+    }
+}
+
+/// Declare the node for a type variable, unless it already has been.
+fn var_node<'a>(dot: &mut String, vars: &mut Vec<&'a str>, var: &'a str) -> fmt::Result {
+    if vars.contains(&var) {
+        return Ok(());
+    }
+
+    vars.push(var);
+    writeln!(dot, "  {} [shape=ellipse];", quote(var))
+}
+
+/// Quote a string for use as a dot identifier.
+fn quote(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{escaped}\"")
+}
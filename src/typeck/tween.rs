@@ -1,14 +1,8 @@
-pub use crate::mir::{Literal, Name};
-
-use std::collections::HashMap;
+pub use crate::mir::{BinOp, Literal, Name};
 
+use super::class::DictId;
 use super::types::Type;
 
-#[derive(Clone, Debug)]
-pub struct Decls {
-    pub values: HashMap<Name, Expr>,
-}
-
 #[derive(Clone, Debug)]
 pub struct Expr {
     pub node: ExprNode,
@@ -28,10 +22,15 @@ pub enum ExprNode {
 
     Call(Box<Expr>, Box<Expr>),
 
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+
     Lit(Literal),
 
     Name(Name),
-    Instantiated(Name),
+    Instantiated(Name, Vec<Type>),
+
+    /// A dictionary, which is replaced by the instance it resolves to.
+    Dict(DictId),
 
     Impossible,
     Invalid,
@@ -44,6 +43,7 @@ pub enum Pat {
     Apply(Box<Pat>, Box<Pat>),
     Lit(Literal),
     Wildcard,
+    Invalid,
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
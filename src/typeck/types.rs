@@ -1,3 +1,7 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
 use super::tween::{Mutability, Name};
 use crate::Regex;
 
@@ -16,16 +20,65 @@ pub enum Type {
     Arrow(Box<Type>, Box<Type>),
 
     Var(Mutability, TypeVar),
-    Named(Name),
+    Named(Name, Vec<Type>),
+
+    /// A type alias applied to some arguments, along with its expansion. The
+    /// alias is kept only to mention it in diagnostics.
+    Alias(Name, Vec<Type>, Box<Type>),
+
+    /// A polymorphic type, in which the bound variables occur as named types.
+    Forall(Vec<Name>, Box<Type>),
 
     Error,
 }
 
 impl Type {
+    /// Collect the unsolved type variables in this type, which should have had
+    /// its substitution applied.
+    pub fn free_vars(&self, vars: &mut Vec<TypeVar>) {
+        match self {
+            Type::Var(_, v) => {
+                if !vars.contains(v) {
+                    vars.push(*v);
+                }
+            }
+
+            Type::Arrow(from, into) => {
+                from.free_vars(vars);
+                into.free_vars(vars);
+            }
+
+            Type::Named(_, args) => args.iter().for_each(|arg| arg.free_vars(vars)),
+            Type::Alias(_, _, ty) | Type::Forall(_, ty) => ty.free_vars(vars),
+
+            Type::Bottom
+            | Type::Bool
+            | Type::Regex
+            | Type::Range(..)
+            | Type::String(..)
+            | Type::Error => {}
+        }
+    }
+
     pub fn make_mutable(self) -> Self {
         self.make_mutability(Mutability::Mutable)
     }
 
+    /// Whether this type can be displayed as the argument of a type
+    /// application without parentheses.
+    fn is_atomic(&self) -> bool {
+        match self {
+            Self::Named(_, args) | Self::Alias(_, args, _) => args.is_empty(),
+            Self::Range(..) | Self::Arrow(..) | Self::Forall(..) => false,
+            Self::Bottom
+            | Self::Bool
+            | Self::Regex
+            | Self::String(..)
+            | Self::Var(..)
+            | Self::Error => true,
+        }
+    }
+
     fn make_mutability(self, mutability: Mutability) -> Self {
         match self {
             Self::Bottom
@@ -33,7 +86,6 @@ impl Type {
             | Self::Regex
             | Self::Range(..)
             | Self::String(..)
-            | Self::Named(..)
             | Self::Error => self,
 
             Self::Arrow(from, into) => {
@@ -42,7 +94,172 @@ impl Type {
                 Self::Arrow(Box::new(from), Box::new(into))
             }
 
+            Self::Named(name, args) => {
+                let args = args
+                    .into_iter()
+                    .map(|arg| arg.make_mutability(mutability))
+                    .collect();
+                Self::Named(name, args)
+            }
+
+            Self::Alias(name, args, ty) => {
+                let args = args
+                    .into_iter()
+                    .map(|arg| arg.make_mutability(mutability))
+                    .collect();
+                let ty = ty.make_mutability(mutability);
+                Self::Alias(name, args, Box::new(ty))
+            }
+
+            Self::Forall(vars, ty) => {
+                let ty = ty.make_mutability(mutability);
+                Self::Forall(vars, Box::new(ty))
+            }
+
             Self::Var(_, v) => Self::Var(mutability, v),
         }
     }
 }
+
+impl Type {
+    /// Display this type with its type variables given readable names, which
+    /// are shared with every other type displayed with the same names.
+    pub fn display<'a>(&'a self, names: &'a RefCell<VarNames>) -> impl fmt::Display + 'a {
+        Named { ty: self, names }
+    }
+
+    fn write(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        var: &mut dyn FnMut(TypeVar) -> String,
+    ) -> fmt::Result {
+        match self {
+            Self::Bottom => write!(f, "!"),
+            Self::Bool => write!(f, "Bool"),
+            Self::Regex => write!(f, "Regex"),
+            Self::Range(lo, hi) => write!(f, "{lo}..{hi}"),
+            Self::String(pat) => write!(f, "/{}/", pat.as_str().replace('/', "\\/")),
+            Self::Arrow(from, into) => {
+                match from.as_ref() {
+                    Self::Arrow(..) | Self::Forall(..) => {
+                        write!(f, "(")?;
+                        from.write(f, var)?;
+                        write!(f, ")")?;
+                    }
+                    _ => from.write(f, var)?,
+                }
+
+                write!(f, " -> ")?;
+                into.write(f, var)
+            }
+            Self::Forall(vars, ty) => {
+                write!(f, "forall {}. ", vars.join(" "))?;
+                ty.write(f, var)
+            }
+            Self::Var(_, v) => write!(f, "{}", var(*v)),
+            Self::Named(name, args) | Self::Alias(name, args, _) => {
+                write!(f, "{name}")?;
+                for arg in args {
+                    if arg.is_atomic() {
+                        write!(f, " ")?;
+                        arg.write(f, var)?;
+                    } else {
+                        write!(f, " (")?;
+                        arg.write(f, var)?;
+                        write!(f, ")")?;
+                    }
+                }
+                Ok(())
+            }
+            Self::Error => write!(f, "{{error}}"),
+        }
+    }
+
+    /// Collect the names of type parameters which occur in this type.
+    fn params(&self, params: &mut HashSet<Name>) {
+        match self {
+            Self::Named(name, args) | Self::Alias(name, args, _) => {
+                if name.starts_with('\'') {
+                    params.insert(name.clone());
+                }
+
+                args.iter().for_each(|arg| arg.params(params));
+            }
+
+            Self::Arrow(from, into) => {
+                from.params(params);
+                into.params(params);
+            }
+
+            Self::Forall(_, ty) => ty.params(params),
+
+            Self::Bottom
+            | Self::Bool
+            | Self::Regex
+            | Self::Range(..)
+            | Self::String(..)
+            | Self::Var(..)
+            | Self::Error => {}
+        }
+    }
+}
+
+/// Type variables are displayed as `?0`, `?1` and so on, by their number.
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, &mut |v| format!("?{}", v.0))
+    }
+}
+
+/// Readable names for type variables, given out in the order they are first
+/// displayed: `'a`, `'b` and so on. Names of type parameters occuring in the
+/// displayed types are skipped.
+#[derive(Clone, Debug, Default)]
+pub struct VarNames {
+    names: HashMap<TypeVar, Name>,
+    taken: HashSet<Name>,
+    next: usize,
+}
+
+impl VarNames {
+    fn name(&mut self, v: TypeVar) -> Name {
+        if let Some(name) = self.names.get(&v) {
+            return name.clone();
+        }
+
+        let name = loop {
+            let name = param_name(self.next);
+            self.next += 1;
+            if !self.taken.contains(&name) {
+                break name;
+            }
+        };
+
+        self.taken.insert(name.clone());
+        self.names.insert(v, name.clone());
+        name
+    }
+}
+
+struct Named<'a> {
+    ty: &'a Type,
+    names: &'a RefCell<VarNames>,
+}
+
+impl fmt::Display for Named<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names = self.names.borrow_mut();
+        self.ty.params(&mut names.taken);
+        self.ty.write(f, &mut |v| names.name(v))
+    }
+}
+
+/// The name of the `n`th type parameter: `'a`, `'b`, ..., `'z`, `'a1` and so
+/// on.
+pub fn param_name(n: usize) -> Name {
+    let letter = char::from(b'a' + (n % 26) as u8);
+    match n / 26 {
+        0 => format!("'{letter}"),
+        m => format!("'{letter}{m}"),
+    }
+}
@@ -1,9 +1,10 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::fmt;
 
 use crate::mir::Name;
 use crate::Regex;
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct TypeId(pub(crate) usize);
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
@@ -16,20 +17,21 @@ pub enum Type {
     String(Regex),
 
     Arrow(TypeId, TypeId),
-    Named(Name),
+    Named(Name, Vec<TypeId>),
+    Forall(Vec<Name>, TypeId),
 
     Error,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq)]
 pub struct Types {
-    types: HashMap<TypeId, Type>,
+    types: BTreeMap<TypeId, Type>,
 }
 
 impl Types {
     pub(crate) fn new() -> Self {
         Self {
-            types: HashMap::new(),
+            types: BTreeMap::new(),
         }
     }
 
@@ -40,6 +42,20 @@ impl Types {
     pub fn get(&self, id: &TypeId) -> &Type {
         self.types.get(id).unwrap()
     }
+
+    /// The type of the given id, if it is defined.
+    pub fn try_get(&self, id: &TypeId) -> Option<&Type> {
+        self.types.get(id)
+    }
+
+    pub fn contains(&self, id: &TypeId) -> bool {
+        self.types.contains_key(id)
+    }
+
+    /// Every type, in order of their ids.
+    pub fn iter(&self) -> impl Iterator<Item = (TypeId, &Type)> {
+        self.types.iter().map(|(id, ty)| (*id, ty))
+    }
 }
 
 impl FromIterator<(TypeId, Type)> for Types {
@@ -54,3 +70,72 @@ impl FromIterator<(TypeId, Type)> for Types {
         types
     }
 }
+
+impl Types {
+    /// Display the type of the given id, in the syntax of type annotations.
+    pub fn display(&self, id: TypeId) -> Display<'_> {
+        Display { types: self, id }
+    }
+}
+
+/// A type along with the types it refers to, for display.
+pub struct Display<'a> {
+    types: &'a Types,
+    id: TypeId,
+}
+
+impl Display<'_> {
+    fn of(&self, id: TypeId) -> Self {
+        Self {
+            types: self.types,
+            id,
+        }
+    }
+
+    fn is_atomic(&self) -> bool {
+        match self.types.get(&self.id) {
+            Type::Arrow(..) | Type::Forall(..) => false,
+            Type::Named(_, args) => args.is_empty(),
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Display<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.types.get(&self.id) {
+            Type::Bottom => write!(f, "!"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Regex => write!(f, "Regex"),
+            Type::Range(lo, hi) => write!(f, "{lo}..{hi}"),
+            Type::String(pat) => write!(f, "/{}/", pat.as_str().replace('/', "\\/")),
+
+            Type::Arrow(from, into) => {
+                let from = self.of(*from);
+                match self.types.get(&from.id) {
+                    Type::Arrow(..) | Type::Forall(..) => write!(f, "({from})")?,
+                    _ => write!(f, "{from}")?,
+                }
+
+                write!(f, " -> {}", self.of(*into))
+            }
+
+            Type::Named(name, args) => {
+                write!(f, "{name}")?;
+                for arg in args {
+                    let arg = self.of(*arg);
+                    if arg.is_atomic() {
+                        write!(f, " {arg}")?;
+                    } else {
+                        write!(f, " ({arg})")?;
+                    }
+                }
+
+                Ok(())
+            }
+
+            Type::Forall(vars, ty) => write!(f, "forall {}. {}", vars.join(" "), self.of(*ty)),
+            Type::Error => write!(f, "{{error}}"),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use common::{errors, run};
+
+#[test]
+fn resolves_instances_through_their_bounds() {
+    let src = "class Describe a { describe : a -> /[a-z]+/ }
+    data Box a = Box a
+    instance Describe Bool { describe = fun b => \"bool\" }
+    instance Describe a => Describe (Box a) {
+        describe = fun b => let Box x = b in describe x else \"box\"
+    }
+    val main = describe (Box true)";
+    assert_eq!(run(src, "main"), "\"bool\"");
+}
+
+#[test]
+fn reports_extra_methods_in_declaration_order() {
+    let src = "class Describe a { describe : a -> /[a-z]+/ }
+    instance Describe Bool {
+        describe = fun b => \"bool\",
+        zeta = true,
+        alpha = true,
+        mu = true
+    }";
+    assert_eq!(
+        errors(src),
+        [
+            "`zeta` is not a method of class `Describe`",
+            "`alpha` is not a method of class `Describe`",
+            "`mu` is not a method of class `Describe`",
+        ]
+    );
+}
+
+#[test]
+fn reports_a_missing_instance_once() {
+    let src = "class Describe a { describe : a -> /[a-z]+/ }
+    val main = describe true";
+    assert_eq!(errors(src), ["no instance of `Describe` for `Bool`"]);
+}
+
+#[test]
+fn reports_overlapping_instances() {
+    let src = "class Eq a { eq : a -> a -> Bool }
+    data Box a = Box a
+    instance Eq Bool { eq = fun a => fun b => true }
+    instance Eq Bool { eq = fun a => fun b => false }
+    instance Eq a => Eq (Box a) { eq = fun a => fun b => true }
+    instance Eq (Box Bool) { eq = fun a => fun b => false }";
+    let decls = illiol::syntax::parse(src).unwrap();
+    let (_, diagnostics) = illiol::typeck(decls);
+    let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        [
+            "more than one instance of `Eq` for `Bool`",
+            "more than one instance of `Eq` for `Box Bool`",
+        ]
+    );
+    assert!(diagnostics.iter().all(|d| d.within.is_none()));
+}
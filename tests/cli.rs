@@ -0,0 +1,94 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A directory of its own for one test, holding the given files.
+fn dir(test: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("illiol-{}-{test}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for (name, src) in files {
+        fs::write(dir.join(name), src).unwrap();
+    }
+    dir
+}
+
+/// Run the command line interface in the given directory, giving whether it
+/// succeeded and what it printed to standard output and standard error.
+fn illiol(dir: &Path, args: &[&str]) -> (bool, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_illiol"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    (output.status.success(), stdout, stderr)
+}
+
+#[test]
+fn check_traces_constraints() {
+    let dir = dir("trace", &[("main.il", "val x : 0..10 = 3\n")]);
+    let (ok, stdout, _) = illiol(&dir, &["check", "--trace-constraints", "main.il"]);
+    assert!(ok);
+    assert_eq!(
+        stdout,
+        "generate #0 `3 : 0..10` from a literal `3` in `x`\nsolve #0\n"
+    );
+
+    let (ok, stdout, _) = illiol(&dir, &["check", "--trace-constraints=dot", "main.il"]);
+    assert!(ok);
+    assert!(stdout.starts_with("digraph constraints {"));
+}
+
+#[test]
+fn fmt_checks_and_rewrites_files() {
+    let messy = "val   x=true\n";
+    let dir = dir("fmt", &[("main.il", messy)]);
+    let (ok, stdout, _) = illiol(&dir, &["fmt", "--check", "main.il"]);
+    assert!(!ok);
+    assert_eq!(stdout, "main.il is not formatted\n");
+    assert_eq!(fs::read_to_string(dir.join("main.il")).unwrap(), messy);
+
+    let (ok, _, _) = illiol(&dir, &["fmt", "main.il"]);
+    assert!(ok);
+    assert_eq!(
+        fs::read_to_string(dir.join("main.il")).unwrap(),
+        "val x = true\n"
+    );
+
+    let (ok, stdout, _) = illiol(&dir, &["fmt", "--check", "main.il"]);
+    assert!(ok);
+    assert_eq!(stdout, "");
+}
+
+#[test]
+fn run_loads_imported_modules() {
+    let dir = dir(
+        "run",
+        &[
+            ("util.il", "pub val greeting : /hello/ = \"hello\"\n"),
+            (
+                "main.il",
+                "import util\nval main = util.greeting ++ \"!\"\n",
+            ),
+        ],
+    );
+    let (ok, stdout, _) = illiol(&dir, &["run", "main.il"]);
+    assert!(ok);
+    assert_eq!(stdout, "\"hello!\"\n");
+}
+
+#[test]
+fn run_reads_back_printed_programs() {
+    let src = "val g : (forall a. a -> a) -> Bool = fun h => h true\nval main = g (fun x => x)\n";
+    let dir = dir("mir", &[("main.il", src)]);
+    let (ok, mir, _) = illiol(&dir, &["check", "--print=mir-text", "main.il"]);
+    assert!(ok);
+    fs::write(dir.join("main.mir"), mir).unwrap();
+
+    let (ok, stdout, stderr) = illiol(&dir, &["run", "main.mir"]);
+    assert!(ok, "{stderr}");
+    assert_eq!(stdout, "true\n");
+}
@@ -0,0 +1,32 @@
+#![allow(dead_code)]
+
+use illiol::eval::Interpreter;
+use illiol::{mir, syntax, typeck};
+
+/// Check the given source, which must parse, panicking with the messages of
+/// any diagnostics.
+pub fn check(src: &str) -> mir::Program {
+    let decls = syntax::parse(src).unwrap_or_else(|d| panic!("{}", d.message));
+    let (program, diagnostics) = typeck(decls);
+    let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert!(messages.is_empty(), "unexpected errors: {messages:#?}");
+    program
+}
+
+/// The messages of the diagnostics reported when checking the given source,
+/// which must parse.
+pub fn errors(src: &str) -> Vec<String> {
+    let decls = syntax::parse(src).unwrap_or_else(|d| panic!("{}", d.message));
+    let (_, diagnostics) = typeck(decls);
+    diagnostics.into_iter().map(|d| d.message).collect()
+}
+
+/// Check the given source and evaluate one of its values, giving the value
+/// as it is printed.
+pub fn run(src: &str, name: &str) -> String {
+    let program = check(src);
+    let value = Interpreter::new(&program)
+        .global(name)
+        .unwrap_or_else(|d| panic!("{}", d.message));
+    value.to_string()
+}
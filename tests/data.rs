@@ -0,0 +1,43 @@
+mod common;
+
+use common::{check, errors};
+
+#[test]
+fn data_types_may_refer_to_themselves() {
+    let src = "data List a = Nil | Cons a (List a)
+    val xs = Cons true (Cons false Nil)
+    val main = let Cons x rest = xs in let Cons y tail = rest in y else true else true";
+    check(src);
+}
+
+#[test]
+fn reports_infinite_types_with_their_equation() {
+    let decls = illiol::syntax::parse("val main = fun x => x x").unwrap();
+    let (_, diagnostics) = illiol::typeck(decls);
+    let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(messages, ["cannot construct an infinite type"]);
+    assert_eq!(diagnostics[0].notes, ["'a = 'a -> 'b"]);
+}
+
+#[test]
+fn newtypes_are_wrapped_explicitly() {
+    let src = "newtype UserId = 0..1000000
+    val id = UserId 42
+    val main = let UserId n = id in n";
+    check(src);
+}
+
+#[test]
+fn newtypes_are_distinct_from_their_representation() {
+    let src = "newtype UserId = 0..1000000
+    val n : 0..1000000 = 42
+    val a : UserId = n
+    val b : 0..1000000 = UserId 42";
+    assert_eq!(
+        errors(src),
+        [
+            "mismatched types: expected `UserId`, found `0..1000000`",
+            "mismatched types: expected `0..1000000`, found `UserId`",
+        ]
+    );
+}
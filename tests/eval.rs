@@ -0,0 +1,56 @@
+mod common;
+
+use common::{check, run};
+use illiol::eval::{Interpreter, Value};
+
+#[test]
+fn closures_capture_their_environment() {
+    let src = "val main = let x = \"a\" in let f = fun y => x ++ y in let x = \"c\" in f \"b\"";
+    assert_eq!(run(src, "main"), "\"ab\"");
+}
+
+#[test]
+fn lets_take_the_else_branch_when_the_pattern_fails() {
+    let src = "data Option a = None | Some a
+    val get = fun o => let Some x = o in x else false
+    val a = get (Some true)
+    val b = get None";
+    assert_eq!(run(src, "a"), "true");
+    assert_eq!(run(src, "b"), "false");
+}
+
+#[test]
+fn polymorphic_names_run_at_every_type() {
+    let src = "val id [a] : a -> a = fun x => x
+    val s : /s/ = \"s\"
+    val main = let b = id true in id s";
+    assert_eq!(run(src, "main"), "\"s\"");
+}
+
+#[test]
+fn prints_every_kind_of_value() {
+    let src = "data Pair a b = Pair a b
+    val b = true
+    val i : 0..4 = 3
+    val s : /s/ = \"s\"
+    val r = /a+/
+    val p : Pair (0..2) /x/ = Pair 1 \"x\"
+    val f = fun x => x";
+    let program = check(src);
+    let interpreter = Interpreter::new(&program);
+    let show = |name| interpreter.global(name).unwrap().to_string();
+    assert_eq!(show("b"), "true");
+    assert_eq!(show("i"), "3");
+    assert_eq!(show("s"), "\"s\"");
+    assert_eq!(show("r"), "/a+/");
+    assert_eq!(show("p"), "Pair 1 \"x\"");
+    assert_eq!(show("f"), "<function>");
+    assert!(matches!(interpreter.global("i"), Ok(Value::Integer(3))));
+}
+
+#[test]
+fn reaching_impossible_is_an_error() {
+    let program = check("val main : Bool = impossible");
+    let error = Interpreter::new(&program).global("main").unwrap_err();
+    assert_eq!(error.message, "reached `impossible`");
+}
@@ -0,0 +1,41 @@
+use illiol::syntax::{format, parse_cst};
+
+const MESSY: &str = "-- a list
+data   List a = Nil|Cons a (List a)
+
+
+
+val xs=Cons true   Nil -- trailing
+-- end
+";
+
+const FORMATTED: &str = "-- a list
+data List a = Nil | Cons a (List a)
+
+val xs = Cons true Nil -- trailing
+-- end
+";
+
+#[test]
+fn syntax_trees_keep_every_character() {
+    assert_eq!(parse_cst(MESSY).unwrap().source(), MESSY);
+}
+
+#[test]
+fn formats_declarations_and_keeps_comments() {
+    assert_eq!(format(MESSY, 80).unwrap(), FORMATTED);
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    assert_eq!(format(FORMATTED, 80).unwrap(), FORMATTED);
+}
+
+#[test]
+fn reports_syntax_errors() {
+    let error = format("val x = ", 80).unwrap_err();
+    assert_eq!(
+        error.message,
+        "expected an expression, found the end of the input"
+    );
+}
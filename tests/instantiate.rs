@@ -0,0 +1,41 @@
+mod common;
+
+use common::{check, errors};
+use illiol::mir::ExprNode;
+use illiol::types::Type;
+
+#[test]
+fn applies_explicit_type_arguments() {
+    let src = "val id [a] : a -> a = fun x => x\nval main = id @(0..10) 5";
+    check(src);
+
+    let src = "val id [a] : a -> a = fun x => x\nval n : 0..10 = 5\nval main = id @Bool n";
+    assert_eq!(
+        errors(src),
+        ["mismatched types: expected `Bool`, found `0..10`"]
+    );
+}
+
+#[test]
+fn reports_the_wrong_number_of_type_arguments() {
+    let src = "val id [a] : a -> a = fun x => x\nval main = id @Bool @Bool true";
+    assert_eq!(
+        errors(src),
+        ["`id` takes 1 type arguments, but 2 were given"]
+    );
+}
+
+#[test]
+fn instantiations_carry_their_type_arguments() {
+    let program = check("val id [a] : a -> a = fun x => x\nval main = id true");
+    let main = &program.decls.values["main"];
+    let ExprNode::Call(func, _) = &main.node else {
+        panic!("expected a call, found {main:?}");
+    };
+    let ExprNode::Instantiated(name, args) = &func.node else {
+        panic!("expected an instantiation, found {func:?}");
+    };
+    assert_eq!(name, "id");
+    assert_eq!(args.len(), 1);
+    assert_eq!(program.types.get(&args[0]), &Type::Bool);
+}
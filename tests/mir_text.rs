@@ -0,0 +1,312 @@
+mod common;
+
+use illiol::mir::text::{parse, print};
+use illiol::mir::Program;
+
+/// Check the given source, then assert its typed program reads back from its
+/// textual form unchanged.
+fn round_trip(src: &str) {
+    let program = common::check(src);
+    assert!(!program.context.is_empty());
+    assert!(program.types.iter().next().is_some());
+    assert!(!program.decls.values.is_empty());
+
+    assert_reads_back(&program);
+}
+
+/// Assert the given program reads back from its textual form unchanged,
+/// including the order of its entries, which equality of maps ignores.
+fn assert_reads_back(program: &Program) {
+    let text = print(program);
+    let read = parse(&text).unwrap_or_else(|d| panic!("{}\n{text}", d.message));
+    assert_eq!(&read, program, "{text}");
+    assert!(read.context.keys().eq(program.context.keys()), "{text}");
+    assert!(
+        read.decls.types.keys().eq(program.decls.types.keys()),
+        "{text}"
+    );
+    assert!(
+        read.decls.values.keys().eq(program.decls.values.keys()),
+        "{text}"
+    );
+}
+
+/// Names which are words, and names which must be quoted.
+const NAMES: &[&str] = &[
+    "a",
+    "x$1",
+    "List",
+    "data.list.Cons",
+    "_",
+    "true",
+    "#1",
+    "é",
+    "",
+    "with space",
+    "quo\"te",
+    "back\\slash",
+    "tab\t",
+    "(paren)",
+    "a=b:c",
+    "[x]",
+    "/slash/",
+    "nul\u{0}",
+];
+
+const STRINGS: &[&str] = &[
+    "",
+    "a",
+    "quo\"te",
+    "line\nbreak",
+    "\\",
+    "/",
+    "é\u{1f600}",
+    "\u{7}",
+];
+
+const PATTERNS: &[&str] = &["a", "a/b", r"\d+", "[a-z]*", "(?:x|y)", r"\\/", "é+", ""];
+
+/// A small deterministic source of randomness, so failures can be replayed.
+struct Gen(u64);
+
+impl Gen {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+
+    fn name(&mut self) -> String {
+        quote(self.pick(NAMES), '"')
+    }
+
+    fn names(&mut self) -> String {
+        let names: Vec<_> = (0..self.below(3)).map(|_| self.name()).collect();
+        format!("[{}]", names.join(" "))
+    }
+
+    fn id(&mut self, types: usize) -> String {
+        format!("#{}", self.below(types))
+    }
+
+    fn ids(&mut self, types: usize) -> String {
+        let ids: Vec<_> = (0..self.below(3)).map(|_| self.id(types)).collect();
+        format!("[{}]", ids.join(" "))
+    }
+
+    fn integer(&mut self) -> i64 {
+        match self.below(4) {
+            0 => i64::MIN,
+            1 => i64::MAX,
+            _ => self.next() as i64 % 1000,
+        }
+    }
+
+    fn ty(&mut self, types: usize) -> String {
+        match self.below(9) {
+            0 => "Bottom".into(),
+            1 => "Bool".into(),
+            2 => "Regex".into(),
+            3 => format!("Range {} {}", self.integer(), self.integer()),
+            4 => format!("String {}", quote(self.pick(PATTERNS), '/')),
+            5 => format!("Arrow {} {}", self.id(types), self.id(types)),
+            6 => format!("Named {} {}", self.name(), self.ids(types)),
+            7 => format!("Forall {} {}", self.names(), self.id(types)),
+            _ => "Error".into(),
+        }
+    }
+
+    fn literal(&mut self) -> String {
+        match self.below(4) {
+            0 => ["true", "false"][self.below(2)].into(),
+            1 => self.integer().to_string(),
+            2 => quote(self.pick(STRINGS), '"'),
+            _ => quote(self.pick(PATTERNS), '/'),
+        }
+    }
+
+    fn pat(&mut self, depth: usize) -> String {
+        match self.below(if depth == 0 { 5 } else { 6 }) {
+            0 => format!("(ctor {})", self.name()),
+            1 => format!("(bind {})", self.name()),
+            2 => format!("(lit {})", self.literal()),
+            3 => "_".into(),
+            4 => "(invalid)".into(),
+            _ => format!("(apply {} {})", self.pat(depth - 1), self.pat(depth - 1)),
+        }
+    }
+
+    fn expr(&mut self, types: usize, depth: usize) -> String {
+        let ty = self.id(types);
+        match self.below(if depth == 0 { 5 } else { 10 }) {
+            0 => format!("(lit {ty} {})", self.literal()),
+            1 => format!("(name {ty} {})", self.name()),
+            2 => format!("(inst {ty} {} {})", self.name(), self.ids(types)),
+            3 => format!("(impossible {ty})"),
+            4 => format!("(invalid {ty})"),
+            5 => format!(
+                "(fun {ty} {} {})",
+                self.pat(depth),
+                self.expr(types, depth - 1)
+            ),
+            6 => format!(
+                "(let {ty} {} {} {} {})",
+                self.pat(depth),
+                self.expr(types, depth - 1),
+                self.expr(types, depth - 1),
+                self.expr(types, depth - 1)
+            ),
+            7 => {
+                let exprs: Vec<_> = (0..self.below(4))
+                    .map(|_| self.expr(types, depth - 1))
+                    .collect();
+                format!("(tuple {ty} {})", exprs.join(" "))
+            }
+            8 => format!(
+                "(call {ty} {} {})",
+                self.expr(types, depth - 1),
+                self.expr(types, depth - 1)
+            ),
+            _ => format!(
+                "(concat {ty} {} {})",
+                self.expr(types, depth - 1),
+                self.expr(types, depth - 1)
+            ),
+        }
+    }
+
+    /// The text of a program whose entries are in no particular order, each
+    /// kind of entry defining every name at most once.
+    fn program(&mut self) -> String {
+        let types = 1 + self.below(8);
+        let mut entries = Vec::new();
+        for id in 0..types {
+            entries.push(format!("type #{id} = {}", self.ty(types)));
+        }
+
+        for kind in ["template", "data", "val"] {
+            let mut names = NAMES.to_vec();
+            for _ in 0..self.below(6) {
+                let name = quote(names.remove(self.below(names.len())), '"');
+                entries.push(match kind {
+                    "template" => format!("template {name} {} : {}", self.names(), self.id(types)),
+                    "data" => format!("data {name} {} {}", self.names(), self.names()),
+                    _ => format!("val {name} {}", self.expr(types, 4)),
+                });
+            }
+        }
+
+        for i in (1..entries.len()).rev() {
+            entries.swap(i, self.below(i + 1));
+        }
+
+        entries.join("\n")
+    }
+}
+
+/// Text in the given delimiters, escaping every character which could be
+/// special.
+fn quote(text: &str, delimiter: char) -> String {
+    let mut out = String::from(delimiter);
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            c if c == delimiter => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_alphanumeric() => out.push(c),
+            c => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+        }
+    }
+
+    out.push(delimiter);
+    out
+}
+
+#[test]
+fn generated_programs_round_trip() {
+    for seed in 1..=500u64 {
+        let mut gen = Gen(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        let text = gen.program();
+        let program = parse(&text).unwrap_or_else(|d| panic!("seed {seed}: {d}\n{text}"));
+        assert_reads_back(&program);
+    }
+}
+
+#[test]
+fn round_trips_literals() {
+    round_trip(
+        r#"val b = true
+        val n : -50..0 = -42
+        val s : /a "quoted"\nline\\/ = "a \"quoted\"\nline\\"
+        val r = /[a-z]+\/x/
+        val c : /ab/ = "a" ++ "b""#,
+    );
+}
+
+#[test]
+fn round_trips_functions_and_lets() {
+    round_trip(
+        "val const [a, b] : a -> b -> a = fun x => fun y => x
+        val pick : Bool -> 0..10 = fun b => let true = b in 1 else 2
+        val twice [a] : (a -> a) -> a -> a = fun f => fun x => f (f x)
+        val three : 0..10 = 3
+        val main = twice (fun x => x) three",
+    );
+}
+
+#[test]
+fn round_trips_data_types_and_aliases() {
+    round_trip(
+        "data List a = Nil | Cons a (List a)
+        type Port = 1..65536
+        newtype UserId = 0..1000000
+        val ports : List Port = Cons 80 (Cons 443 Nil)
+        val seven : 0..1000000 = 7
+        val user = UserId seven
+        val head [a] : a -> List a -> a =
+            fun d => fun xs => let Cons x _ = xs in x else d",
+    );
+}
+
+#[test]
+fn round_trips_higher_rank_types() {
+    round_trip(
+        "val apply : (forall a. a -> a) -> Bool = fun f => f true
+        val main = apply (fun x => x)",
+    );
+}
+
+#[test]
+fn round_trips_classes_and_instances() {
+    round_trip(
+        "class Describe a { describe : a -> /[a-z]+/ }
+        data Box a = Box a
+        instance Describe Bool { describe = fun b => \"bool\" }
+        instance Describe a => Describe (Box a) {
+            describe = fun b => let Box x = b in describe x else \"box\"
+        }
+        val show [a: Describe] : a -> /[a-z]+/ = fun x => describe x
+        val main = show (Box true)",
+    );
+}
+
+#[test]
+fn reports_where_parsing_failed() {
+    let error = parse("type #0 = Bool\ngarbage").unwrap_err();
+    assert_eq!(error.message, "expected an entry, found `garbage`");
+    assert_eq!(error.notes, ["at line 2, column 1"]);
+
+    let error = parse("type #0 = String /a(/").unwrap_err();
+    assert!(error.message.starts_with("invalid regex: "));
+}
@@ -0,0 +1,111 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use illiol::eval::Interpreter;
+use illiol::{module, typeck};
+
+/// A directory of its own for one test, holding the given files.
+fn dir(test: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("illiol-module-{}-{test}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    for (name, src) in files {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, src).unwrap();
+    }
+    dir
+}
+
+/// The messages of the diagnostics reported while loading and linking the
+/// program whose root module is `main.il`.
+fn errors(dir: &Path) -> Vec<String> {
+    let diagnostics = match module::load(&dir.join("main.il")) {
+        Ok(modules) => module::link(modules).1,
+        Err(diagnostics) => diagnostics,
+    };
+    diagnostics.into_iter().map(|d| d.message).collect()
+}
+
+#[test]
+fn links_modules_with_qualified_names() {
+    let dir = dir(
+        "link",
+        &[
+            ("data/list.il", "pub data List a = Nil | Cons a (List a)\npub val one = Cons true Nil"),
+            ("util.il", "import data.list as list\npub val first = fun xs => let list.Cons x rest = xs in x else false"),
+            ("main.il", "import util\nimport data.list\nval main = util.first data.list.one"),
+        ],
+    );
+
+    let modules = module::load(&dir.join("main.il")).unwrap();
+    let names: Vec<_> = modules.iter().map(|module| module.name.as_str()).collect();
+    assert_eq!(names, ["data.list", "util", ""]);
+
+    let (decls, diagnostics) = module::link(modules);
+    assert!(diagnostics.is_empty());
+    let (program, diagnostics) = typeck(decls);
+    assert!(diagnostics.is_empty());
+    assert!(program.context.contains_key("data.list.one"));
+    assert!(program.context.contains_key("util.first"));
+
+    let value = Interpreter::new(&program).global("main").unwrap();
+    assert_eq!(value.to_string(), "true");
+}
+
+#[test]
+fn reports_uses_of_private_declarations() {
+    let dir = dir(
+        "private",
+        &[
+            ("util.il", "val secret = true\npub val open = secret"),
+            ("main.il", "import util\nval main = util.secret"),
+        ],
+    );
+    assert_eq!(errors(&dir), ["`secret` is private to the module `util`"]);
+}
+
+#[test]
+fn reports_names_a_module_does_not_declare() {
+    let dir = dir(
+        "undeclared",
+        &[
+            ("util.il", "pub val a = true"),
+            ("main.il", "import util\nval main = util.b"),
+        ],
+    );
+    assert_eq!(errors(&dir), ["the module `util` does not declare `b`"]);
+}
+
+#[test]
+fn reports_modules_which_are_not_imported() {
+    let dir = dir(
+        "unimported",
+        &[
+            ("util.il", "pub val a = true"),
+            ("main.il", "val main = util.a"),
+        ],
+    );
+    assert_eq!(errors(&dir), ["`util` is not an imported module"]);
+}
+
+#[test]
+fn reports_missing_modules() {
+    let dir = dir("missing", &[("main.il", "import nowhere\nval main = true")]);
+    assert_eq!(errors(&dir), ["cannot find the module `nowhere`"]);
+}
+
+#[test]
+fn reports_import_cycles() {
+    let dir = dir(
+        "cycle",
+        &[
+            ("a.il", "import b\npub val x = true"),
+            ("b.il", "import a\npub val y = true"),
+            ("main.il", "import a\nval main = a.x"),
+        ],
+    );
+    assert_eq!(
+        errors(&dir),
+        ["modules import each other in a cycle: a -> b -> a"]
+    );
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use illiol::eval::Interpreter;
+use illiol::mir;
+use illiol::mono::monomorphise;
+
+#[test]
+fn monomorphises_constructors_and_builtins() {
+    let program = common::check(
+        "data Color = Red | Green
+        data Option2 a = None2 | Some2 a
+        val small = lt 1 2
+        val wrapped = Some2 (length \"abc\")
+        val parsed = parse_int \"42\"
+        val main = let Some2 n = wrapped in show_int n else \"0\"",
+    );
+
+    let roots: Vec<_> = ["Red", "None2", "lt", "small", "parsed", "main"]
+        .map(String::from)
+        .into();
+    let mono = monomorphise(&program, &roots).unwrap();
+    assert!(
+        mir::validate(&mono).is_empty(),
+        "{:?}",
+        mir::validate(&mono)
+    );
+    let interpreter = Interpreter::new(&mono);
+    assert_eq!(interpreter.global("small").unwrap().to_string(), "true");
+    assert_eq!(
+        interpreter.global("parsed").unwrap().to_string(),
+        "Some#0 42"
+    );
+    assert_eq!(interpreter.global("main").unwrap().to_string(), "\"3\"");
+}
+
+#[test]
+fn specialises_polymorphic_values_per_type() {
+    let program = common::check(
+        "val id [a] : a -> a = fun x => x
+        val main = let true = id true in id 1 else 0",
+    );
+
+    let mono = monomorphise(&program, &["main".to_string()]).unwrap();
+    assert!(mir::validate(&mono).is_empty());
+    let values: Vec<_> = mono.decls.values.keys().map(String::as_str).collect();
+    assert_eq!(values, ["main", "id#1", "id#0"]);
+    assert_eq!(
+        Interpreter::new(&mono).global("main").unwrap().to_string(),
+        "1"
+    );
+}
+
+#[test]
+fn locals_shadow_globals_declared_later() {
+    let mut session = illiol::Session::new();
+    let first = illiol::syntax::parse("val f : Bool -> Bool = fun x => x").unwrap();
+    session.add(first).unwrap();
+    let second = illiol::syntax::parse("val x = true").unwrap();
+    session.add(second).unwrap();
+
+    let mono = monomorphise(session.program(), &["f".to_string()]).unwrap();
+    let values: Vec<_> = mono.decls.values.keys().map(String::as_str).collect();
+    assert_eq!(values, ["f"]);
+    assert!(mir::validate(&mono).is_empty());
+}
+
+#[test]
+fn reports_constructors_of_another_type() {
+    let program = common::check(
+        "data A = A1 | A2
+        data B = B1 | B2
+        val main = let A1 = A2 in true else false",
+    );
+
+    let text = mir::text::print(&program).replace("(ctor A1)", "(ctor B1)");
+    let program = mir::text::parse(&text).unwrap();
+    assert!(mir::validate(&program).is_empty());
+
+    let errors = monomorphise(&program, &["main".to_string()]).unwrap_err();
+    let messages: Vec<_> = errors.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        ["the constructor `B1` does not belong to the type `A`"]
+    );
+}
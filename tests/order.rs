@@ -0,0 +1,48 @@
+mod common;
+
+use common::{check, run};
+use illiol::mir;
+
+#[test]
+fn checking_is_reproducible() {
+    let src = "data Pair a b = Pair a b
+    val swap [a, b] : Pair a b -> Pair b a = fun p => let Pair l r = p in Pair r l
+    val one : 0..2 = 1
+    val z = swap (Pair one true)
+    val y : /a/ -> /a!/ = fun s => s ++ \"!\"
+    val x = y \"a\"";
+    let first = check(src);
+    for _ in 0..8 {
+        let again = check(src);
+        assert_eq!(format!("{again:#?}"), format!("{first:#?}"));
+        assert_eq!(mir::text::print(&again), mir::text::print(&first));
+    }
+}
+
+#[test]
+fn declarations_keep_their_source_order() {
+    let program = check("val c = true\nval a = false\nval b = true");
+    let names: Vec<_> = program.decls.values.keys().map(String::as_str).collect();
+    assert_eq!(names, ["c", "a", "b"]);
+}
+
+#[test]
+fn definitions_are_checked_after_their_dependencies() {
+    let src = "val main = shout \"a\"\nval shout = fun s => s ++ \"!\"";
+    assert_eq!(run(src, "main"), "\"a!\"");
+}
+
+#[test]
+fn mutually_dependent_definitions_are_checked_together() {
+    let src = "val a = fun x => let true = x in b false else true
+    val b = fun x => let true = x in false else a false
+    val main = a true";
+    assert_eq!(run(src, "main"), "true");
+}
+
+#[test]
+fn unannotated_definitions_are_generalised() {
+    let src = "val id = fun x => x\nval a = id true\nval b = id \"b\"";
+    assert_eq!(run(src, "a"), "true");
+    assert_eq!(run(src, "b"), "\"b\"");
+}
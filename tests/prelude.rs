@@ -0,0 +1,103 @@
+mod common;
+
+use common::{errors, run};
+
+#[test]
+fn comparisons_take_literals() {
+    assert_eq!(run("val main = lt 3 4", "main"), "true");
+    assert_eq!(run("val main = ge 3 4", "main"), "false");
+    assert_eq!(run("val main = eq 7 7", "main"), "true");
+}
+
+#[test]
+fn comparisons_take_integers_of_different_ranges() {
+    let src = "val x : 0..10 = 3\nval y : 0..100 = 50\nval main = lt x y";
+    assert_eq!(run(src, "main"), "true");
+    let src = "val x : 0..10 = 3\nval main = ge x 50";
+    assert_eq!(run(src, "main"), "false");
+}
+
+#[test]
+fn length_takes_a_literal() {
+    assert_eq!(run("val main = length \"abc\"", "main"), "3");
+}
+
+#[test]
+fn parse_int_takes_a_literal() {
+    assert_eq!(run("val main = parse_int \"42\"", "main"), "Some 42");
+    assert_eq!(run("val main = parse_int \"4x\"", "main"), "None");
+}
+
+#[test]
+fn matches_takes_literals() {
+    assert_eq!(run("val main = matches \"abc\" /a.c/", "main"), "true");
+}
+
+#[test]
+fn widen_and_clamp_take_literals() {
+    let src = "val main : 0..10 = widen 3";
+    assert_eq!(run(src, "main"), "3");
+    let src = "val main : 0..10 = clamp 42";
+    assert_eq!(run(src, "main"), "9");
+}
+
+#[test]
+fn show_int_takes_a_literal() {
+    assert_eq!(run("val main = show_int -12", "main"), "\"-12\"");
+}
+
+#[test]
+fn literals_of_the_same_variable_share_a_range() {
+    let src = "val f [a] : a -> a -> a = impossible\nval main = lt (f 1 5) 3";
+    assert_eq!(errors(src), Vec::<String>::new());
+}
+
+#[test]
+fn builtins_check_the_kind_of_their_arguments() {
+    let errors = errors("val main = lt true 3");
+    assert_eq!(
+        errors,
+        ["`lt` needs an integer range for `a`, found `Bool`"]
+    );
+}
+
+#[test]
+fn widen_rejects_a_smaller_range() {
+    let errors = errors("val x : 0..100 = 50\nval main : 0..10 = widen x");
+    assert_eq!(
+        errors,
+        ["cannot widen `0..100` to `0..10`, which does not contain it"]
+    );
+}
+
+#[test]
+fn repeat_is_a_builtin() {
+    assert_eq!(run("val main = repeat \"ab\" 3", "main"), "\"ababab\"");
+    let src = "val main : /(?:ab){3,3}/ = repeat \"ab\" 3";
+    assert_eq!(run(src, "main"), "\"ababab\"");
+}
+
+#[test]
+fn repeat_reports_a_type_too_large_to_build() {
+    let errors = errors("val main = repeat \"abcdefghij\" 2000000");
+    assert_eq!(errors, ["the string type of `repeat` is too large"]);
+}
+
+#[test]
+fn repeat_refuses_to_build_a_huge_string() {
+    // The checker could not have given this a type, so build it by hand.
+    let mut text = illiol::mir::text::print(&common::check("val main = repeat \"ab\" 3"));
+    text = text
+        .replace("Range 3 4", "Range 100000000 100000001")
+        .replace("String /(?:ab){3,3}/", "String /(?:ab)*/")
+        .replace(" 3))", " 100000000))");
+
+    let program = illiol::mir::text::parse(&text).unwrap();
+    let error = illiol::eval::Interpreter::new(&program)
+        .global("main")
+        .unwrap_err();
+    assert_eq!(
+        error.message,
+        "`repeat` would build a string of 200000000 characters, more than the limit of 16777216"
+    );
+}
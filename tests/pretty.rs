@@ -0,0 +1,76 @@
+mod common;
+
+use common::{check, errors};
+use illiol::{pretty, syntax};
+
+const SRC: &str = "data Pair a b = Pair a b
+val swap [a, b] : Pair a b -> Pair b a = fun p => let Pair x y = p in Pair y x
+val f : 0..10 -> 0..10 = fun x => x";
+
+#[test]
+fn prints_declarations_in_concrete_syntax() {
+    let decls = syntax::parse(SRC).unwrap();
+    assert_eq!(
+        pretty::hir(&decls, 80),
+        "data Pair a b = Pair a b
+
+val swap [a, b] : Pair a b -> Pair b a = fun p => let Pair x y = p in Pair y x
+
+val f : 0..10 -> 0..10 = fun x => x
+"
+    );
+}
+
+#[test]
+fn breaks_lines_to_fit_the_width() {
+    let decls = syntax::parse(SRC).unwrap();
+    assert_eq!(
+        pretty::hir(&decls, 30),
+        "data Pair a b = Pair a b
+
+val swap [a, b] : Pair a b -> Pair b a =
+  fun p =>
+    let Pair x y = p in
+    Pair y x
+
+val f : 0..10 -> 0..10 =
+  fun x => x
+"
+    );
+}
+
+#[test]
+fn prints_checked_programs_with_their_types() {
+    let program = check(SRC);
+    let printed = pretty::mir(&program, 80);
+    assert!(printed.contains(
+        "val swap [a, b] : Pair a b -> Pair b a =\n  fun p => let Pair x y = p in Pair @b @a y x\n"
+    ));
+    assert!(printed.contains("val f : 0..10 -> 0..10 = fun x => x\n"));
+}
+
+#[test]
+fn displays_types_by_resolving_their_ids() {
+    let program = check(SRC);
+    let f = program.context["f"].uninst;
+    assert_eq!(program.types.display(f).to_string(), "0..10 -> 0..10");
+}
+
+#[test]
+fn names_type_variables_readably() {
+    let src = "data Pair a b = Pair a b\nval main : Bool = let f = fun x => fun y => Pair x y in f";
+    assert_eq!(
+        errors(src)[0],
+        "mismatched types: expected `Bool`, found `'a -> 'b -> Pair 'a 'b`"
+    );
+}
+
+#[test]
+fn diagnostics_show_string_types_as_annotations() {
+    let src = "val s = \"a\" ++ \"b\"\nval t : Bool = s";
+    assert_eq!(
+        errors(src),
+        ["mismatched types: expected `Bool`, found `/ab/`"]
+    );
+    check("val s = \"a\" ++ \"b\"\nval t : /ab/ = s");
+}
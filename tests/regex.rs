@@ -0,0 +1,74 @@
+mod common;
+
+use common::{check, errors};
+use illiol::Regex;
+
+#[test]
+fn reports_invalid_regex_literals() {
+    assert_eq!(errors("val r = /a(b/"), ["invalid regex `/a(b/`"]);
+}
+
+#[test]
+fn reports_invalid_annotation_patterns() {
+    assert_eq!(
+        errors("val s : /[z-a]/ = impossible"),
+        ["invalid regex `/[z-a]/`"]
+    );
+}
+
+#[test]
+fn reports_each_invalid_pattern_where_it_is_used() {
+    let src = "val a : /(/ = impossible\nval b : /(/ = impossible";
+    assert_eq!(errors(src), ["invalid regex `/(/`", "invalid regex `/(/`"]);
+}
+
+#[test]
+fn accepts_valid_regex_literals() {
+    check("val r = /ab+/");
+}
+
+#[test]
+fn literals_escape_their_text() {
+    let regex = Regex::literal("a.b*").unwrap();
+    assert!(regex.is_match("a.b*"));
+    assert!(!regex.is_match("axbb"));
+}
+
+#[test]
+fn combinators_keep_their_operands_whole() {
+    let ab = Regex::new("a|b").unwrap();
+    let c = Regex::literal("c").unwrap();
+    let concat = ab.concat(&c).unwrap();
+    assert!(concat.is_match("ac") && concat.is_match("bc"));
+    assert!(!concat.is_match("a"));
+
+    let alt = ab.alt(&c).unwrap();
+    assert!(alt.is_match("a") && alt.is_match("c"));
+
+    let star = Regex::literal("ab").unwrap().star().unwrap();
+    assert!(star.is_match("") && star.is_match("abab"));
+    assert!(!star.is_match("abb"));
+}
+
+#[test]
+fn concatenation_is_typed_by_its_operands() {
+    let src = "val a : /a|b/ = \"a\"\nval main : /(?:a|b)c/ = a ++ \"c\"";
+    check(src);
+
+    let src = "val a : /a|b/ = \"a\"\nval main : /ac/ = a ++ \"c\"";
+    assert_eq!(
+        errors(src),
+        ["mismatched types: expected `/ac/`, found `/(?:a|b)c/`"]
+    );
+}
+
+#[test]
+fn string_types_cannot_contain_anchors() {
+    for pattern in ["a$", "^a", r"\Aa", r"a\z", "(?:b|a$)*"] {
+        let src = format!("val s : /{pattern}/ = \"a\"\nval main = s ++ \"b\"");
+        assert_eq!(
+            errors(&src),
+            [format!("`/{pattern}/` cannot contain anchors")]
+        );
+    }
+}
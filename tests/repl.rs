@@ -0,0 +1,111 @@
+use illiol::repl::{Repl, Reply};
+
+/// The output of an input which must succeed.
+fn output(repl: &mut Repl, input: &str) -> String {
+    match repl.input(input) {
+        Reply::Output(output) => output,
+        Reply::Errors(errors) => panic!("`{input}` failed: {errors:?}"),
+        Reply::Incomplete => panic!("`{input}` is incomplete"),
+        Reply::Quit => panic!("`{input}` quit"),
+    }
+}
+
+/// The messages of the errors of an input which must fail.
+fn errors(repl: &mut Repl, input: &str) -> Vec<String> {
+    match repl.input(input) {
+        Reply::Errors(errors) => errors.into_iter().map(|d| d.message).collect(),
+        _ => panic!("`{input}` did not fail"),
+    }
+}
+
+#[test]
+fn evaluates_expressions_against_declarations() {
+    let mut repl = Repl::new();
+    assert_eq!(output(&mut repl, "val x = 1"), "x : 1..2");
+    assert_eq!(output(&mut repl, "val y : 0..10 = widen x"), "y : 0..10");
+    assert_eq!(output(&mut repl, "y"), "1 : 0..10");
+    assert_eq!(output(&mut repl, ":type x"), "1..2");
+}
+
+#[test]
+fn infers_functions_at_the_prompt() {
+    let mut repl = Repl::new();
+    let input = "let f = fun a => a in f true";
+    assert_eq!(output(&mut repl, input), "true : Bool");
+}
+
+#[test]
+fn expressions_are_not_kept() {
+    let mut repl = Repl::new();
+    output(&mut repl, "true");
+    assert_eq!(output(&mut repl, "lt 1 2"), "true : Bool");
+    assert_eq!(output(&mut repl, "val z = 3"), "z : 3..4");
+    assert_eq!(output(&mut repl, "z"), "3 : 3..4");
+}
+
+#[test]
+fn rejected_declarations_are_forgotten() {
+    let mut repl = Repl::new();
+    let errors = errors(&mut repl, "val x : Bool = 3\nval y = x");
+    assert!(!errors.is_empty());
+    assert!(matches!(repl.input("x"), Reply::Errors(_)));
+    assert_eq!(output(&mut repl, "val x = true"), "x : Bool");
+    assert_eq!(output(&mut repl, "val y = x"), "y : Bool");
+}
+
+#[test]
+fn replacing_a_declaration_checks_its_uses_again() {
+    let mut repl = Repl::new();
+    output(&mut repl, "val x = 1");
+    output(&mut repl, "val y = x");
+    assert_eq!(output(&mut repl, "val x = true"), "x : Bool");
+    assert_eq!(output(&mut repl, "y"), "true : Bool");
+
+    output(&mut repl, "val n : Bool = x");
+    let errors = errors(&mut repl, "val x = \"a\"");
+    assert!(!errors.is_empty());
+    assert_eq!(output(&mut repl, "x"), "true : Bool");
+}
+
+#[test]
+fn declarations_hide_the_prelude() {
+    let mut repl = Repl::new();
+    assert_eq!(output(&mut repl, "length \"ab\""), "2 : 2..3");
+    assert_eq!(output(&mut repl, "val length = 5"), "length : 5..6");
+    assert_eq!(output(&mut repl, "length"), "5 : 5..6");
+}
+
+#[test]
+fn input_ending_early_is_incomplete() {
+    let mut repl = Repl::new();
+    assert!(matches!(repl.input("val f ="), Reply::Incomplete));
+    assert!(matches!(
+        repl.input("data List a = Nil |"),
+        Reply::Incomplete
+    ));
+    assert!(matches!(repl.input("let x = 1 in"), Reply::Incomplete));
+    assert!(matches!(repl.input("(1"), Reply::Incomplete));
+}
+
+#[test]
+fn invalid_input_is_an_error() {
+    let mut repl = Repl::new();
+    assert_eq!(
+        errors(&mut repl, "val 3"),
+        ["expected a value name, found `3`"]
+    );
+    assert_eq!(
+        errors(&mut repl, "1 )"),
+        ["expected the end of the input, found `)`"]
+    );
+    assert_eq!(errors(&mut repl, ":frob"), ["unknown command `:frob`"]);
+}
+
+#[test]
+fn reset_forgets_declarations() {
+    let mut repl = Repl::new();
+    output(&mut repl, "val x = 1");
+    output(&mut repl, ":reset");
+    assert!(matches!(repl.input("x"), Reply::Errors(_)));
+    assert!(matches!(repl.input(":quit"), Reply::Quit));
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::{check, errors, run};
+use illiol::mir::{ExprNode, Pat};
+
+#[test]
+fn locals_shadow_globals_only_within_their_scope() {
+    let src = "val a : /global/ = \"global\"
+    val l : /local/ = \"local\"
+    val f = fun a => a
+    val main = let b = f l in a
+    val g = f l";
+    assert_eq!(run(src, "main"), "\"global\"");
+    assert_eq!(run(src, "g"), "\"local\"");
+}
+
+#[test]
+fn inner_bindings_shadow_outer_ones() {
+    let src = "val main = let x = true in let x = \"s\" in x";
+    assert_eq!(run(src, "main"), "\"s\"");
+}
+
+#[test]
+fn locals_are_not_visible_after_their_scope() {
+    let src = "val f = fun x => x\nval main : Bool = let y = f true in x";
+    assert_eq!(errors(src), ["unknown name `x`"]);
+}
+
+#[test]
+fn binders_are_given_unique_names() {
+    let program = check("val main = let x = true in let x = false in x");
+    let ExprNode::Let {
+        pat: Pat::Bind(outer),
+        then,
+        ..
+    } = &program.decls.values["main"].node
+    else {
+        panic!("expected a let");
+    };
+    let ExprNode::Let {
+        pat: Pat::Bind(inner),
+        then,
+        ..
+    } = &then.node
+    else {
+        panic!("expected a let");
+    };
+    let ExprNode::Name(used) = &then.node else {
+        panic!("expected a name");
+    };
+    assert_ne!(outer, inner);
+    assert_eq!(used, inner);
+}
+
+#[test]
+fn reports_names_bound_twice_in_a_pattern() {
+    let src = "data Pair a b = Pair a b\nval f = fun p => let Pair x x = p in x";
+    assert_eq!(errors(src), ["`x` is bound more than once in a pattern"]);
+}
+
+#[test]
+fn reports_type_arguments_given_to_locals() {
+    let src = "val main = let x = true in x @Bool";
+    assert_eq!(
+        errors(src),
+        ["`x` is bound by a pattern, so has no type parameters"]
+    );
+}
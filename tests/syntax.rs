@@ -0,0 +1,185 @@
+use illiol::hir::{Expr, TypeDef};
+use illiol::syntax::{parse, parse_expr};
+
+/// The annotation of a value declared with the given type, as debug output.
+fn ty(src: &str) -> String {
+    let decls = parse(&format!("val x : {src} = impossible")).unwrap();
+    format!("{:?}", decls.values["x"].anno)
+}
+
+/// The message and notes of the error parsing the given source.
+fn error(src: &str) -> Vec<String> {
+    let error = parse(src).unwrap_err();
+    let mut lines = vec![error.message];
+    lines.extend(error.notes);
+    lines
+}
+
+#[test]
+fn parses_every_kind_of_declaration() {
+    let decls = parse(
+        "import data.list as list
+        data List a = Nil | Cons a (List a)
+        type Port = 1..65536
+        pub newtype UserId = 0..1000000
+        class Show a { show : a -> String }
+        instance Show a => Show (List a) { show = fun x => \"list\" }
+        val id [a] : a -> a = fun x => x",
+    )
+    .unwrap();
+
+    assert_eq!(decls.imports.len(), 1);
+    assert_eq!(decls.imports[0].module, "data.list");
+    assert_eq!(decls.imports[0].alias.as_deref(), Some("list"));
+
+    let types: Vec<_> = decls.types.keys().map(String::as_str).collect();
+    assert_eq!(types, ["List", "Port", "UserId"]);
+    let TypeDef::Data(list) = &decls.types["List"] else {
+        panic!("`List` is not a data type");
+    };
+    assert_eq!(list.vars, ["a"]);
+    let ctors: Vec<_> = list.ctors.iter().map(|ctor| ctor.name.as_str()).collect();
+    assert_eq!(ctors, ["Nil", "Cons"]);
+    assert_eq!(list.ctors[1].fields.len(), 2);
+    assert!(matches!(decls.types["Port"], TypeDef::Alias(_)));
+    assert!(matches!(decls.types["UserId"], TypeDef::Newtype(_)));
+    assert!(decls.exports.contains("UserId"));
+    assert_eq!(decls.exports.len(), 1);
+
+    assert_eq!(decls.classes["Show"].var, "a");
+    assert_eq!(decls.classes["Show"].methods[0].0, "show");
+
+    let instance = &decls.instances[0];
+    assert_eq!(instance.class, "Show");
+    assert_eq!(instance.vars[0].name, "a");
+    assert_eq!(instance.vars[0].bounds, ["Show"]);
+    assert_eq!(instance.methods[0].0, "show");
+
+    let id = &decls.values["id"];
+    assert_eq!(id.vars[0].name, "a");
+    assert!(matches!(id.body, Expr::Fun(..)));
+}
+
+#[test]
+fn exports_keep_the_order_of_declarations() {
+    let decls = parse(
+        "pub val zeta = true
+        pub data Maybe a = Nothing | Just a
+        val hidden = false
+        pub newtype Alpha = 0..10
+        pub val beta = true",
+    )
+    .unwrap();
+    let exports: Vec<_> = decls.exports.iter().map(|name| name.as_str()).collect();
+    assert_eq!(exports, ["zeta", "Maybe", "Alpha", "beta"]);
+}
+
+#[test]
+fn parses_type_parameters_with_bounds() {
+    let decls = parse("val f [a: Show + Eq, b] = impossible").unwrap();
+    let vars = &decls.values["f"].vars;
+    assert_eq!(vars[0].name, "a");
+    assert_eq!(vars[0].bounds, ["Show", "Eq"]);
+    assert_eq!(vars[1].name, "b");
+    assert!(vars[1].bounds.is_empty());
+}
+
+#[test]
+fn parses_ranges() {
+    assert_eq!(ty("0..10"), "Range(0, 10)");
+    assert_eq!(ty("-5..-1"), "Range(-5, -1)");
+}
+
+#[test]
+fn parses_regexes() {
+    assert_eq!(ty("/[a-z]+/"), r#"String("[a-z]+")"#);
+    assert_eq!(ty(r"/a\/b/"), r#"String("a/b")"#);
+}
+
+#[test]
+fn application_binds_tighter_than_arrows() {
+    assert_eq!(
+        ty("Map k v -> List (Option a)"),
+        r#"Arrow(Apply(Apply(Named("Map"), Named("k")), Named("v")), Apply(Named("List"), Apply(Named("Option"), Named("a"))))"#
+    );
+}
+
+#[test]
+fn arrows_associate_to_the_right() {
+    assert_eq!(
+        ty("a -> b -> c"),
+        r#"Arrow(Named("a"), Arrow(Named("b"), Named("c")))"#
+    );
+    assert_eq!(
+        ty("(a -> b) -> c"),
+        r#"Arrow(Arrow(Named("a"), Named("b")), Named("c"))"#
+    );
+}
+
+#[test]
+fn parses_forall_and_builtin_types() {
+    assert_eq!(
+        ty("(forall a. a -> a) -> Bool"),
+        r#"Arrow(Forall(["a"], Arrow(Named("a"), Named("a"))), Bool)"#
+    );
+    assert_eq!(ty("Regex -> _"), "Arrow(Regex, Wildcard)");
+}
+
+#[test]
+fn parses_expressions() {
+    let expr = parse_expr("let x = f 1 2 in x ++ \"a\" else \"b\"").unwrap();
+    assert!(matches!(expr, Expr::Let { .. }), "{expr:?}");
+}
+
+#[test]
+fn reports_what_was_expected() {
+    assert_eq!(
+        error("val x = 1\nval"),
+        [
+            "expected a value name, found the end of the input",
+            "at line 2, column 4"
+        ]
+    );
+    assert_eq!(
+        error("val x : 1.. = 1"),
+        ["expected an integer, found `=`", "at line 1, column 13"]
+    );
+    assert_eq!(
+        error("data = A"),
+        ["expected a type name, found `=`", "at line 1, column 6"]
+    );
+    assert_eq!(
+        error("pub import a"),
+        [
+            "expected a named declaration after `pub`, found `import`",
+            "at line 1, column 5"
+        ]
+    );
+    assert_eq!(
+        error("val x : (a = 1"),
+        ["expected `)`, found `=`", "at line 1, column 12"]
+    );
+    assert_eq!(
+        error("x = 1"),
+        ["expected a declaration, found `x`", "at line 1, column 1"]
+    );
+}
+
+#[test]
+fn reports_definitions_made_twice() {
+    assert_eq!(
+        error("val x = 1\nval x = 2"),
+        ["`x` is defined more than once", "at line 2, column 5"]
+    );
+}
+
+#[test]
+fn reports_integers_which_are_too_large() {
+    assert_eq!(
+        error("val x = 99999999999999999999"),
+        [
+            "the integer `99999999999999999999` is too large",
+            "at line 1, column 9"
+        ]
+    );
+}
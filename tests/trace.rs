@@ -0,0 +1,79 @@
+use illiol::{syntax, typeck_traced, Event, Step};
+
+#[test]
+fn explains_solutions_with_their_origin() {
+    let decls = syntax::parse("val main = let f = fun a => a in f true").unwrap();
+    let (_, diagnostics, trace) = typeck_traced(decls);
+    assert!(diagnostics.is_empty());
+
+    let steps = trace.explain("?0");
+    assert_eq!(
+        steps,
+        [Step {
+            var: "?0".into(),
+            ty: "Bool".into(),
+            provenance: "the definition `f true` in `main`".into(),
+        }]
+    );
+}
+
+#[test]
+fn records_the_origin_of_constraints() {
+    let decls = syntax::parse("val x : 0..10 = 3").unwrap();
+    let (_, _, trace) = typeck_traced(decls);
+    let generated = trace.events().iter().find_map(|event| match event {
+        Event::Generate { provenance, .. } => Some(provenance.as_str()),
+        _ => None,
+    });
+
+    assert_eq!(generated, Some("a literal `3` in `x`"));
+}
+
+#[test]
+fn unknown_variables_have_no_explanation() {
+    let decls = syntax::parse("val x = true").unwrap();
+    let (_, _, trace) = typeck_traced(decls);
+    assert!(trace.explain("?100").is_empty());
+}
+
+#[test]
+fn prints_each_step_as_text() {
+    let decls = syntax::parse("val x : 0..10 = 3").unwrap();
+    let (_, _, trace) = typeck_traced(decls);
+    assert_eq!(
+        trace.to_string(),
+        "generate #0 `3 : 0..10` from a literal `3` in `x`\nsolve #0\n"
+    );
+}
+
+#[test]
+fn exports_the_constraint_graph_as_dot() {
+    let decls = syntax::parse("val y = fun s => s ++ \"a\"").unwrap();
+    let (_, _, trace) = typeck_traced(decls);
+    let dot = trace.to_dot();
+    assert!(dot.starts_with("digraph constraints {\n"));
+    assert!(dot.ends_with("}\n"));
+    assert!(dot.contains("c1 -> \"?1\" [style=dashed, label=\"waits on\"];"));
+    assert!(dot.contains("c1 [color=red];"));
+}
+
+#[test]
+fn notes_name_the_variables_they_explain() {
+    let src = "val f : 0..10 -> Bool = fun x => true
+    val main = let g = fun y => f y in g true";
+    let (_, diagnostics) = illiol::typeck(syntax::parse(src).unwrap());
+    assert_eq!(
+        diagnostics[0].notes,
+        ["`'a` is `0..10` because of a function argument in `main`"]
+    );
+}
+
+#[test]
+fn definitions_without_annotations_are_not_declared_types() {
+    let src = "val a = true\nval main : 0..1 = let b = a in b";
+    let (_, diagnostics) = illiol::typeck(syntax::parse(src).unwrap());
+    assert_eq!(
+        diagnostics[0].notes,
+        ["`'a` is `Bool` because of the definition in `a`"]
+    );
+}
@@ -0,0 +1,199 @@
+mod common;
+
+use std::thread;
+
+use common::{check, errors, run};
+
+#[test]
+fn infers_functions_without_an_expected_type() {
+    let src = "val main = let f = fun a => a in f true";
+    assert_eq!(run(src, "main"), "true");
+}
+
+#[test]
+fn infers_a_parameter_type_from_its_uses() {
+    let src = "val shout = fun x => x ++ \"!\"\nval main : /a!/ = shout \"a\"";
+    assert_eq!(run(src, "main"), "\"a!\"");
+}
+
+#[test]
+fn sessions_check_batches_against_earlier_ones() {
+    let mut session = illiol::Session::new();
+    let first = illiol::syntax::parse("data Box a = Box a\nval x = Box true").unwrap();
+    session.add(first).unwrap();
+    let second = illiol::syntax::parse("val y = let Box b = x in b else false").unwrap();
+    session.add(second).unwrap();
+    let bad = illiol::syntax::parse("val z : 0..1 = y").unwrap();
+    assert!(session.add(bad).is_err());
+
+    let program = session.program();
+    assert!(program.context.contains_key("x"));
+    assert!(program.context.contains_key("y"));
+    assert!(!program.context.contains_key("z"));
+    assert!(illiol::mir::validate(program).is_empty());
+    let value = illiol::eval::Interpreter::new(program).global("y").unwrap();
+    assert_eq!(value.to_string(), "true");
+}
+
+#[test]
+fn polymorphic_arguments_are_used_at_several_types() {
+    let src = "val s : /s/ = \"s\"
+    val both : (forall a. a -> a) -> Bool = fun f => let t = f s in f true
+    val main = both (fun x => x)";
+    check(src);
+}
+
+#[test]
+fn monomorphic_functions_are_not_polymorphic_arguments() {
+    let src = "val apply : (forall a. a -> a) -> Bool = fun f => f true
+    val main = apply (fun x => true)";
+    assert_eq!(
+        errors(src),
+        ["mismatched types: expected `a`, found `Bool`"]
+    );
+}
+
+#[test]
+fn errors_are_assignable_to_rigid_type_variables() {
+    let src = "val f : (forall a. a -> a) -> Bool = fun g => true
+    val main = f (fun x => nope)";
+    assert_eq!(errors(src), ["unknown name `nope`"]);
+}
+
+#[test]
+fn rigid_type_variables_cannot_escape_their_scope() {
+    let src = "val bad = fun x => ((fun y => x) : forall a. a -> a)";
+    assert_eq!(
+        errors(src),
+        ["the type variable `a` would escape the scope it is bound in"]
+    );
+}
+
+#[test]
+fn rigid_type_variables_stay_in_their_scope() {
+    let src = "val apply : (forall a. a -> a) -> Bool = fun f => f true
+    val main = apply (fun x => let y = x in y else x)";
+    assert_eq!(run(src, "main"), "true");
+}
+
+#[test]
+fn inferred_types_keep_alias_names() {
+    let src = "type Port = 1..65536
+    val p : Port = 80
+    val q = p
+    val r : Bool = q";
+    assert_eq!(
+        errors(src),
+        ["mismatched types: expected `Bool`, found `Port`"]
+    );
+}
+
+#[test]
+fn variables_solved_to_aliases_still_check() {
+    let src = "type Pair a = a -> a -> Bool
+    val small : 0..10 = 3
+    val same : Pair (0..10) = lt
+    val main = let s = small in same s 4 else false";
+    assert_eq!(run(src, "main"), "true");
+}
+
+#[test]
+fn aliases_and_named_types_check_their_arguments() {
+    let src = "data Box a = Box a
+    type Both a = Box (Box a)
+    val b : Both Bool = Box (Box true)
+    val c : Box Bool Bool = Box true
+    val d : Both = b";
+    assert_eq!(
+        errors(src),
+        [
+            "`Box` expects 1 type arguments, but 2 were given",
+            "`Both` expects 1 type arguments, but 0 were given",
+        ]
+    );
+}
+
+#[test]
+fn long_chains_of_variables_are_solved() {
+    // The checker recurses over the nested lets, so give it a larger stack.
+    let errors = thread::Builder::new()
+        .stack_size(1 << 28)
+        .spawn(|| {
+            let mut src = String::from("val main : 0..1 = let x0 = true in ");
+            for i in 1..2000 {
+                src += &format!("let x{i} = x{} in ", i - 1);
+            }
+
+            errors(&(src + "x1999"))
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    assert_eq!(errors, ["mismatched types: expected `0..1`, found `Bool`"]);
+}
+
+#[test]
+fn stuck_constraints_report_what_they_wait_on() {
+    let decls = illiol::syntax::parse("val main = fun x => fun y => x ++ y").unwrap();
+    let (_, diagnostics) = illiol::typeck(decls);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].message,
+        "cannot infer the operand types of `++`, found `'a` and `'b`"
+    );
+    assert_eq!(diagnostics[0].within.as_deref(), Some("main"));
+    assert_eq!(
+        diagnostics[0].notes,
+        ["arising from the operator `++`", "waiting on `'a`"]
+    );
+}
+
+#[test]
+fn constraints_wake_once_their_variables_are_solved() {
+    let src = "val main = let f = fun x => x ++ \"!\" in let g = fun y => f y in g \"a\"";
+    assert_eq!(run(src, "main"), "\"a!\"");
+}
+
+#[test]
+fn mismatches_explain_how_variables_were_solved() {
+    let decls = illiol::syntax::parse(
+        "val main : _ -> 0..10 = fun x => x
+        val y : 0..10 = main true",
+    )
+    .unwrap();
+    let (_, diagnostics) = illiol::typeck(decls);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].message,
+        "mismatched types: expected `0..10`, found `Bool`"
+    );
+    assert_eq!(
+        diagnostics[0].notes,
+        ["`'a` is `0..10` because of the declared type in `main`"]
+    );
+}
+
+#[test]
+fn literals_which_do_not_fit_are_reported() {
+    assert_eq!(
+        errors("val n : 0..10 = 12"),
+        ["mismatched types: expected `0..10`, found the literal `12`"]
+    );
+}
+
+#[test]
+fn parameters_are_contravariant() {
+    let src = "val g : (forall a. a -> a) -> 5..6 = fun f => 5
+    val k : (Bool -> Bool) -> 5..6 = g
+    val main = k (fun b => true)";
+    assert_eq!(
+        errors(src),
+        ["mismatched types: expected `(Bool -> Bool) -> 5..6`, found `(forall a. a -> a) -> 5..6`"]
+    );
+
+    let src = "val g : (Bool -> Bool) -> Bool = fun f => f true
+    val k : (forall a. a -> a) -> Bool = g
+    val main = k (fun x => x)";
+    assert_eq!(run(src, "main"), "true");
+}
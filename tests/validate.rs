@@ -0,0 +1,73 @@
+mod common;
+
+use common::check;
+use illiol::mir::{text, validate};
+
+const SRC: &str = "val id [a] : a -> a = fun x => x\nval x : Bool = id true";
+
+/// Validate the textual form of the checked source, after replacing part of
+/// it, giving the messages of the violations.
+fn violations(from: &str, to: &str) -> Vec<String> {
+    let printed = text::print(&check(SRC));
+    assert!(printed.contains(from), "{printed}");
+    let program = text::parse(&printed.replace(from, to)).unwrap();
+    validate(&program).into_iter().map(|d| d.message).collect()
+}
+
+#[test]
+fn checked_programs_are_valid() {
+    assert!(validate(&check(SRC)).is_empty());
+
+    let src = "val g : (forall a. a -> a) -> Bool = fun h => h true
+    val main = g (fun x => x)";
+    assert!(validate(&check(src)).is_empty());
+}
+
+#[test]
+fn reports_undefined_types() {
+    assert_eq!(
+        violations("(lit #4 true)", "(lit #99 true)"),
+        ["the type `#99` is not defined"]
+    );
+}
+
+#[test]
+fn reports_unbound_names() {
+    assert_eq!(
+        violations("(name #0 x$1)", "(name #0 y$1)"),
+        ["`y$1` is not bound"]
+    );
+}
+
+#[test]
+fn reports_calls_of_non_functions() {
+    assert_eq!(
+        violations("(inst #22 id [#4])", "(lit #4 false)"),
+        ["a callee has the type `Bool`, which is not a function type"]
+    );
+}
+
+#[test]
+fn reports_literals_of_the_wrong_type() {
+    assert_eq!(
+        violations("(lit #4 true)", "(lit #21 true)"),
+        ["the literal `true` does not have its annotated type `a -> a`"]
+    );
+}
+
+#[test]
+fn reports_instantiations_of_monomorphic_names() {
+    assert_eq!(
+        violations("(inst #22 id [#4])", "(inst #22 x [#4])"),
+        ["`x` is instantiated but has no type parameters"]
+    );
+}
+
+#[test]
+fn reports_every_violation() {
+    let printed = text::print(&check(SRC))
+        .replace("(name #0 x$1)", "(name #0 y$1)")
+        .replace("(lit #4 true)", "(lit #21 true)");
+    let program = text::parse(&printed).unwrap();
+    assert_eq!(validate(&program).len(), 2);
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use common::check;
+use illiol::eval::Interpreter;
+use illiol::syntax::parse_expr;
+use illiol::{hir, mir, pretty};
+
+/// Collects the names used in an expression, in order.
+#[derive(Default)]
+struct Names(Vec<String>);
+
+impl hir::visit::Visitor for Names {
+    fn visit_expr(&mut self, expr: &hir::Expr) {
+        if let hir::Expr::Name(name) = expr {
+            self.0.push(name.clone());
+        }
+        hir::visit::walk_expr(self, expr);
+    }
+}
+
+/// Renames every named type.
+struct Rename;
+
+impl hir::visit::VisitorMut for Rename {
+    fn visit_type_mut(&mut self, ty: &mut hir::Type) {
+        if let hir::Type::Named(name) = ty {
+            name.push('2');
+        }
+        hir::visit::walk_type_mut(self, ty);
+    }
+}
+
+/// Negates every boolean literal.
+struct Negate;
+
+impl hir::visit::Folder for Negate {
+    fn fold_literal(&mut self, lit: hir::Literal) -> hir::Literal {
+        match lit {
+            hir::Literal::Boolean(b) => hir::Literal::Boolean(!b),
+            lit => lit,
+        }
+    }
+}
+
+impl mir::visit::Folder for Negate {
+    fn fold_literal(&mut self, lit: mir::Literal) -> mir::Literal {
+        match lit {
+            mir::Literal::Boolean(b) => mir::Literal::Boolean(!b),
+            lit => lit,
+        }
+    }
+}
+
+/// Counts the literals of an expression.
+#[derive(Default)]
+struct Literals(usize);
+
+impl mir::visit::Visitor for Literals {
+    fn visit_literal(&mut self, _: &mir::Literal) {
+        self.0 += 1;
+    }
+}
+
+#[test]
+fn hir_visitors_reach_every_node() {
+    use hir::visit::Visitor;
+
+    let expr = parse_expr("let f = fun a => g a in f (h x) else y").unwrap();
+    let mut names = Names::default();
+    names.visit_expr(&expr);
+    assert_eq!(names.0, ["g", "a", "f", "h", "x", "y"]);
+}
+
+#[test]
+fn hir_visitors_change_nodes_in_place() {
+    use hir::visit::VisitorMut;
+
+    let mut expr = parse_expr("(x : Box Item -> Item)").unwrap();
+    Rename.visit_expr_mut(&mut expr);
+    assert_eq!(pretty::hir_expr(&expr, 80), "(x : Box2 Item2 -> Item2)");
+}
+
+#[test]
+fn hir_folders_rebuild_nodes() {
+    use hir::visit::Folder;
+
+    let expr = parse_expr("let true = x in false else \"s\"").unwrap();
+    let expr = Negate.fold_expr(expr);
+    assert_eq!(
+        pretty::hir_expr(&expr, 80),
+        "let false = x in true else \"s\""
+    );
+}
+
+#[test]
+fn mir_visitors_reach_every_node() {
+    use mir::visit::Visitor;
+
+    let program = check("val main = let x = \"a\" in x ++ \"b\"");
+    let mut literals = Literals::default();
+    literals.visit_expr(&program.decls.values["main"]);
+    assert_eq!(literals.0, 2);
+}
+
+#[test]
+fn mir_folders_rebuild_nodes() {
+    use mir::visit::Folder;
+
+    let mut program = check("val main = let true = true in false else true");
+    let main = program.decls.values.shift_remove("main").unwrap();
+    program
+        .decls
+        .values
+        .insert("main".into(), Negate.fold_expr(main));
+    let value = Interpreter::new(&program).global("main").unwrap();
+    assert_eq!(value.to_string(), "true");
+}